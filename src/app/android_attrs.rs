@@ -0,0 +1,227 @@
+pub mod attrs {
+    // 常用的系统属性 id 和名称，来自 android 的 public.xml
+    const ATTRIBUTES: &[(u32, &str)] = &[
+        (0x01010000, "theme"),
+        (0x01010001, "label"),
+        (0x01010002, "icon"),
+        (0x01010003, "name"),
+        (0x01010006, "permission"),
+        (0x01010007, "readPermission"),
+        (0x01010008, "writePermission"),
+        (0x01010009, "protectionLevel"),
+        (0x0101000b, "sharedUserId"),
+        (0x0101000c, "hasCode"),
+        (0x0101000e, "enabled"),
+        (0x0101000f, "debuggable"),
+        (0x01010010, "exported"),
+        (0x01010011, "process"),
+        (0x01010012, "taskAffinity"),
+        (0x01010018, "authorities"),
+        (0x0101001b, "grantUriPermissions"),
+        (0x0101001c, "priority"),
+        (0x0101001d, "launchMode"),
+        (0x0101001e, "screenOrientation"),
+        (0x0101001f, "configChanges"),
+        (0x01010024, "value"),
+        (0x01010025, "resource"),
+        (0x01010026, "mimeType"),
+        (0x01010027, "scheme"),
+        (0x01010028, "host"),
+        (0x0101002a, "path"),
+        (0x010100c4, "orientation"),
+        (0x010100dc, "visibility"),
+        (0x010100f4, "layout_width"),
+        (0x010100f5, "layout_height"),
+        (0x0101014f, "text"),
+        (0x01010159, "width"),
+        (0x0101020c, "minSdkVersion"),
+        (0x0101021b, "versionCode"),
+        (0x0101021c, "versionName"),
+        (0x0101022b, "windowSoftInputMode"),
+        (0x01010270, "targetSdkVersion"),
+        (0x01010271, "maxSdkVersion"),
+        (0x01010280, "allowBackup"),
+        (0x01010281, "glEsVersion"),
+        (0x0101028e, "required"),
+        (0x010102b7, "installLocation"),
+        (0x010102d3, "hardwareAccelerated"),
+        (0x0101035a, "largeHeap"),
+        (0x01010405, "pathData"),
+        (0x010104ea, "extractNativeLibs"),
+        (0x010104ec, "usesCleartextTraffic"),
+        (0x01010527, "networkSecurityConfig"),
+        (0x0101052c, "roundIcon"),
+        (0x01010572, "compileSdkVersion"),
+        (0x01010573, "compileSdkVersionCodename"),
+    ];
+
+    /**
+     * 系统属性 id 对应的名称
+     */
+    pub fn attribute_name(id: u32) -> Option<&'static str> {
+        ATTRIBUTES.iter().find(|(value, _)| *value == id).map(|(_, name)| *name)
+    }
+
+    /**
+     * 系统属性名称对应的 id，用于只给出属性名的解码结果
+     */
+    pub fn attribute_id(name: &str) -> Option<u32> {
+        ATTRIBUTES.iter().find(|(_, value)| *value == name).map(|(id, _)| *id)
+    }
+
+    /**
+     * 枚举和标志位类型的属性，把整数值转换成 xml 中的写法
+     * 不是这类属性或者值无法完整表示时返回 None
+     */
+    pub fn format_int(id: u32, data: u32) -> Option<String> {
+        match id {
+            // layout_width / layout_height 也可以是尺寸，整数时只有这两个取值
+            0x010100f4 | 0x010100f5 => enum_name(LAYOUT_SIZE, data),
+            0x010100c4 => enum_name(ORIENTATION, data),
+            0x010100dc => enum_name(VISIBILITY, data),
+            0x0101001d => enum_name(LAUNCH_MODE, data),
+            0x0101001e => enum_name(SCREEN_ORIENTATION, data),
+            0x010102b7 => enum_name(INSTALL_LOCATION, data),
+            0x0101001f => flag_names(CONFIG_CHANGES, data),
+            0x01010009 => {
+                // 低 4 位是保护级别，其余是附加标志
+                let base = enum_name(PROTECTION_LEVEL, data & 0xf)?;
+                if data & !0xf == 0 {
+                    return Some(base);
+                }
+                Some(format!("{}|{}", base, flag_names(PROTECTION_FLAGS, data & !0xf)?))
+            }
+            0x0101022b => {
+                // 低 4 位是键盘状态，4~7 位是调整方式
+                let mut names = Vec::new();
+                if data & 0xf != 0 {
+                    names.push(enum_name(SOFT_INPUT_STATE, data & 0xf)?);
+                }
+                if data & 0xf0 != 0 {
+                    names.push(enum_name(SOFT_INPUT_ADJUST, data & 0xf0)?);
+                }
+                if data & !0xff != 0 {
+                    return None;
+                }
+                if names.is_empty() {
+                    names.push("stateUnspecified".to_string());
+                }
+                Some(names.join("|"))
+            }
+            _ => None,
+        }
+    }
+
+    fn enum_name(values: &[(&str, u32)], data: u32) -> Option<String> {
+        values
+            .iter()
+            .find(|(_, value)| *value == data)
+            .map(|(name, _)| name.to_string())
+    }
+
+    /**
+     * 按标志位拆分，有无法识别的位时返回 None
+     */
+    fn flag_names(values: &[(&str, u32)], data: u32) -> Option<String> {
+        let mut names = Vec::new();
+        let mut rest = data;
+        for (name, value) in values {
+            if *value != 0 && data & value == *value {
+                names.push(*name);
+                rest &= !value;
+            }
+        }
+        if rest != 0 || names.is_empty() {
+            return None;
+        }
+        Some(names.join("|"))
+    }
+
+    const LAYOUT_SIZE: &[(&str, u32)] = &[("match_parent", 0xFFFFFFFF), ("wrap_content", 0xFFFFFFFE)];
+
+    const ORIENTATION: &[(&str, u32)] = &[("horizontal", 0), ("vertical", 1)];
+
+    const VISIBILITY: &[(&str, u32)] = &[("visible", 0), ("invisible", 1), ("gone", 2)];
+
+    const LAUNCH_MODE: &[(&str, u32)] = &[
+        ("standard", 0),
+        ("singleTop", 1),
+        ("singleTask", 2),
+        ("singleInstance", 3),
+        ("singleInstancePerTask", 4),
+    ];
+
+    const SCREEN_ORIENTATION: &[(&str, u32)] = &[
+        ("unspecified", 0xFFFFFFFF),
+        ("landscape", 0),
+        ("portrait", 1),
+        ("user", 2),
+        ("behind", 3),
+        ("sensor", 4),
+        ("nosensor", 5),
+        ("sensorLandscape", 6),
+        ("sensorPortrait", 7),
+        ("reverseLandscape", 8),
+        ("reversePortrait", 9),
+        ("fullSensor", 10),
+        ("userLandscape", 11),
+        ("userPortrait", 12),
+        ("fullUser", 13),
+        ("locked", 14),
+    ];
+
+    const INSTALL_LOCATION: &[(&str, u32)] = &[("auto", 0), ("internalOnly", 1), ("preferExternal", 2)];
+
+    const CONFIG_CHANGES: &[(&str, u32)] = &[
+        ("mcc", 0x0001),
+        ("mnc", 0x0002),
+        ("locale", 0x0004),
+        ("touchscreen", 0x0008),
+        ("keyboard", 0x0010),
+        ("keyboardHidden", 0x0020),
+        ("navigation", 0x0040),
+        ("orientation", 0x0080),
+        ("screenLayout", 0x0100),
+        ("uiMode", 0x0200),
+        ("screenSize", 0x0400),
+        ("smallestScreenSize", 0x0800),
+        ("density", 0x1000),
+        ("layoutDirection", 0x2000),
+        ("colorMode", 0x4000),
+        ("grammaticalGender", 0x8000),
+        ("fontWeightAdjustment", 0x10000000),
+        ("fontScale", 0x40000000),
+    ];
+
+    const PROTECTION_LEVEL: &[(&str, u32)] = &[
+        ("normal", 0),
+        ("dangerous", 1),
+        ("signature", 2),
+        ("signatureOrSystem", 3),
+    ];
+
+    const PROTECTION_FLAGS: &[(&str, u32)] = &[
+        ("privileged", 0x10),
+        ("development", 0x20),
+        ("appop", 0x40),
+        ("pre23", 0x80),
+        ("installer", 0x100),
+        ("verifier", 0x200),
+        ("preinstalled", 0x400),
+        ("setup", 0x800),
+    ];
+
+    const SOFT_INPUT_STATE: &[(&str, u32)] = &[
+        ("stateUnchanged", 1),
+        ("stateHidden", 2),
+        ("stateAlwaysHidden", 3),
+        ("stateVisible", 4),
+        ("stateAlwaysVisible", 5),
+    ];
+
+    const SOFT_INPUT_ADJUST: &[(&str, u32)] = &[
+        ("adjustResize", 0x10),
+        ("adjustPan", 0x20),
+        ("adjustNothing", 0x30),
+    ];
+}
@@ -1,77 +1,349 @@
 pub mod axml {
-    use axmldecoder::{Cdata, Element, Node};
+    use std::fmt;
+    use std::panic;
 
+    use axmldecoder::Node;
+    use xml::namespace::{NS_NO_PREFIX, NS_XMLNS_PREFIX, NS_XML_PREFIX};
+    use xml::reader::XmlEvent;
+    use xml::EventReader;
+
+    use crate::app::android_attrs::attrs;
+    use crate::app::arsc_parser::arsc::ResourceTable;
+    use crate::app::axml_walker::walker::{
+        self, XmlAttribute, XmlDocument, XmlElement, ANDROID_NAMESPACE, TYPE_ATTRIBUTE, TYPE_DIMENSION,
+        TYPE_FLOAT, TYPE_FRACTION, TYPE_INT_BOOLEAN, TYPE_INT_COLOR_ARGB4, TYPE_INT_COLOR_ARGB8,
+        TYPE_INT_COLOR_RGB4, TYPE_INT_COLOR_RGB8, TYPE_INT_DEC, TYPE_INT_HEX, TYPE_NULL, TYPE_REFERENCE,
+        TYPE_STRING,
+    };
+
+    // axmldecoder 只在 manifest 上给出命名空间声明，其他文件中 android 以外的前缀按 res-auto 处理
+    const RES_AUTO_NAMESPACE: &str = "http://schemas.android.com/apk/res-auto";
+
+    /**
+     * 二进制 xml 解析错误
+     */
+    #[derive(Debug)]
+    pub enum AxmlError {
+        // 内容为空
+        Empty,
+        // 不是以 RES_XML_TYPE chunk 开头
+        NotBinaryXml,
+        // 没有根节点
+        NoRoot,
+        // 文本 xml 格式错误，例如 aar 中的清单文件
+        InvalidText(String),
+    }
+
+    impl fmt::Display for AxmlError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                AxmlError::Empty => write!(f, "binary xml is empty"),
+                AxmlError::NotBinaryXml => write!(f, "not a binary xml"),
+                AxmlError::NoRoot => write!(f, "binary xml has no root element"),
+                AxmlError::InvalidText(reason) => write!(f, "invalid text xml: {}", reason),
+            }
+        }
+    }
+
+    impl std::error::Error for AxmlError {}
+
+    /**
+     * 把二进制 xml 转换成文本 xml，解析失败时返回空字符串
+     */
+    #[deprecated(note = "use parse_document and format_document, which report AxmlError")]
     pub fn extract_xml(content: Vec<u8>) -> String {
-        let xml = axmldecoder::parse(content.as_slice()).unwrap();
-        let root = xml.get_root().as_ref().unwrap();
-        let mut xml_as_string = String::new();
-        format_xml(root, 0_usize, &mut xml_as_string);
-        xml_as_string
+        parse_document(&content)
+            .map(|document| format_document(&document, None))
+            .unwrap_or_default()
     }
 
-    fn format_xml(e: &Node, level: usize, output: &mut String) {
-        match e {
-            Node::Element(e) => {
-                output.push_str(&format!(
-                    "{:indent$}{}\n",
-                    "",
-                    &format_start_element(e),
-                    indent = level * 2
-                ));
+    /**
+     * 解析二进制 xml，优先使用 axmldecoder
+     * axmldecoder 解析失败时使用容错的 walker，可以处理混淆工具改过 chunk 大小和字符串池的文件
+     */
+    pub fn parse_document(content: &[u8]) -> Result<XmlDocument, AxmlError> {
+        match decode(content) {
+            Some(document) => Ok(document),
+            None => walker::walk(content),
+        }
+    }
 
-                for child in e.get_children() {
-                    format_xml(child, level + 1, output)
+    /**
+     * 是否是文本 xml，aar 中的清单文件没有编译成二进制
+     */
+    pub fn is_text_xml(data: &[u8]) -> bool {
+        let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+        data.iter()
+            .find(|byte| !byte.is_ascii_whitespace())
+            .is_some_and(|byte| *byte == b'<')
+    }
+
+    /**
+     * 用 xml-rs 解析文本 xml，生成与二进制 xml 相同的元素树，属性值按字符串保存
+     */
+    pub fn parse_text_document(content: &[u8]) -> Result<XmlDocument, AxmlError> {
+        if content.is_empty() {
+            return Err(AxmlError::Empty);
+        }
+        let mut namespaces: Vec<(String, String)> = Vec::new();
+        let mut stack: Vec<XmlElement> = Vec::new();
+        let mut root: Option<XmlElement> = None;
+        for event in EventReader::new(content) {
+            match event.map_err(|err| AxmlError::InvalidText(err.to_string()))? {
+                XmlEvent::StartElement {
+                    name,
+                    attributes,
+                    namespace,
+                } => {
+                    for (prefix, uri) in &namespace {
+                        if [NS_NO_PREFIX, NS_XML_PREFIX, NS_XMLNS_PREFIX].contains(&prefix) {
+                            continue;
+                        }
+                        if !namespaces.iter().any(|(p, u)| p == prefix && u == uri) {
+                            namespaces.push((prefix.to_string(), uri.to_string()));
+                        }
+                    }
+                    let attributes = attributes
+                        .iter()
+                        .map(|attribute| {
+                            let namespace = attribute.name.namespace.as_deref().unwrap_or_default();
+                            XmlAttribute::text(namespace, &attribute.name.local_name, &attribute.value)
+                        })
+                        .collect();
+                    stack.push(XmlElement {
+                        tag: name.local_name,
+                        attributes,
+                        ..XmlElement::default()
+                    });
+                }
+                XmlEvent::EndElement { .. } => {
+                    if let Some(element) = stack.pop() {
+                        match stack.last_mut() {
+                            Some(parent) => parent.children.push(element),
+                            None => root = Some(element),
+                        }
+                    }
+                }
+                XmlEvent::CData(data) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&data);
+                    }
                 }
+                XmlEvent::Characters(data) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(data.trim());
+                    }
+                }
+                _ => {}
+            }
+        }
+        let root = root.ok_or(AxmlError::NoRoot)?;
+        Ok(XmlDocument { namespaces, root })
+    }
+
+    fn decode(content: &[u8]) -> Option<XmlDocument> {
+        // axmldecoder 在字符串索引越界，结束标签多于开始标签时会 panic，当作解析失败
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let result = panic::catch_unwind(|| axmldecoder::parse(content).ok());
+        panic::set_hook(hook);
+        let document = result.ok().flatten()?;
+        let Some(Node::Element(root)) = document.get_root() else {
+            return None;
+        };
+        let mut namespaces = Vec::new();
+        let root = convert_element(root, &mut namespaces);
+        Some(XmlDocument { namespaces, root })
+    }
 
-                if !e.get_children().is_empty() {
-                    output.push_str(&format!(
-                        "{:indent$}{}\n",
-                        "",
-                        format_end_element(e),
-                        indent = level * 2
-                    ));
+    fn convert_element(element: &axmldecoder::Element, namespaces: &mut Vec<(String, String)>) -> XmlElement {
+        let mut converted = XmlElement {
+            tag: element.get_tag().to_string(),
+            ..XmlElement::default()
+        };
+        for (key, value) in element.get_attributes() {
+            // manifest 上的 xmlns:* 是 axmldecoder 添加的命名空间声明
+            if let Some(prefix) = key.strip_prefix("xmlns:") {
+                if !namespaces.iter().any(|(declared, _)| declared == prefix) {
+                    namespaces.push((prefix.to_string(), value.clone()));
                 }
+                continue;
             }
-            Node::Cdata(e) => {
-                output.push_str(&format!(
-                    "{:indent$}{}\n",
-                    "",
-                    &format_cdata(e, level),
-                    indent = level * 2
-                ));
+            converted.attributes.push(convert_attribute(key, value, namespaces));
+        }
+        for child in element.get_children() {
+            match child {
+                Node::Element(child) => converted.children.push(convert_element(child, namespaces)),
+                Node::Cdata(cdata) => converted.text.push_str(cdata.get_data()),
+            }
+        }
+        converted
+    }
+
+    /**
+     * axmldecoder 的属性名为 前缀:名称，值已经转换成文本：
+     * 字符串原样输出，整数和布尔值输出为数字和 true/false，其他类型输出为 ResourceValueType::类型/数值
+     */
+    fn convert_attribute(key: &str, value: &str, namespaces: &mut Vec<(String, String)>) -> XmlAttribute {
+        let (namespace, name) = match key.split_once(':') {
+            Some(("android", name)) => (ANDROID_NAMESPACE.to_string(), name),
+            Some((prefix, name)) => {
+                let uri = match namespaces.iter().find(|(declared, _)| declared == prefix) {
+                    Some((_, uri)) => uri.clone(),
+                    None => {
+                        namespaces.push((prefix.to_string(), RES_AUTO_NAMESPACE.to_string()));
+                        RES_AUTO_NAMESPACE.to_string()
+                    }
+                };
+                (uri, name)
             }
+            None => (String::new(), key),
+        };
+        let resource_id = if namespace == ANDROID_NAMESPACE { attrs::attribute_id(name) } else { None };
+        let (data_type, data) = typed_value(value).unwrap_or((TYPE_STRING, 0));
+        XmlAttribute {
+            namespace,
+            name: name.to_string(),
+            resource_id,
+            raw_value: (data_type == TYPE_STRING).then(|| value.to_string()),
+            data_type,
+            data,
+        }
+    }
+
+    fn typed_value(value: &str) -> Option<(u8, u32)> {
+        if let Some(typed) = value.strip_prefix("ResourceValueType::") {
+            let (kind, data) = typed.split_once('/')?;
+            let data_type = match kind {
+                "Null" => TYPE_NULL,
+                "Reference" => TYPE_REFERENCE,
+                "Attribute" => TYPE_ATTRIBUTE,
+                "Float" => TYPE_FLOAT,
+                "Dimension" => TYPE_DIMENSION,
+                "Fraction" => TYPE_FRACTION,
+                "ColorArgb8" => TYPE_INT_COLOR_ARGB8,
+                "ColorRgb8" => TYPE_INT_COLOR_RGB8,
+                "ColorArgb4" => TYPE_INT_COLOR_ARGB4,
+                "ColorRgb4" => TYPE_INT_COLOR_RGB4,
+                _ => return None,
+            };
+            return Some((data_type, data.parse().ok()?));
+        }
+        match value {
+            "true" => Some((TYPE_INT_BOOLEAN, 1)),
+            "false" => Some((TYPE_INT_BOOLEAN, 0)),
+            // axmldecoder 把十六进制整数输出为 0x 加十进制数值
+            _ => match value.strip_prefix("0x") {
+                Some(data) => Some((TYPE_INT_HEX, data.parse().ok()?)),
+                None => Some((TYPE_INT_DEC, value.parse().ok()?)),
+            },
         }
     }
 
-    fn format_cdata(e: &Cdata, level: usize) -> String {
+    /**
+     * 把文档格式化成文本 xml，资源引用通过 resources.arsc 转换成 @type/name
+     */
+    pub fn format_document(document: &XmlDocument, resources: Option<&ResourceTable>) -> String {
+        let mut xml_as_string = String::new();
+        format_xml(document, &document.root, 0_usize, &mut xml_as_string, resources);
+        xml_as_string
+    }
+
+    fn format_xml(
+        document: &XmlDocument,
+        e: &XmlElement,
+        level: usize,
+        output: &mut String,
+        resources: Option<&ResourceTable>,
+    ) {
+        output.push_str(&format!(
+            "{:indent$}{}\n",
+            "",
+            &format_start_element(document, e, level == 0, resources),
+            indent = level * 2
+        ));
+
+        if !e.text.is_empty() {
+            output.push_str(&format!(
+                "{:indent$}{}\n",
+                "",
+                &format_cdata(&e.text, level + 1),
+                indent = (level + 1) * 2
+            ));
+        }
+
+        for child in &e.children {
+            format_xml(document, child, level + 1, output, resources)
+        }
+
+        if !is_empty_element(e) {
+            output.push_str(&format!(
+                "{:indent$}{}\n",
+                "",
+                format_end_element(e),
+                indent = level * 2
+            ));
+        }
+    }
+
+    fn is_empty_element(e: &XmlElement) -> bool {
+        e.children.is_empty() && e.text.is_empty()
+    }
+
+    fn format_cdata(data: &str, level: usize) -> String {
         let indent = format!("{:indent$}", "", indent = level * 2);
         let mut s = String::new();
         s.push_str("<![CDATA[");
-        s.push_str(&e.get_data().replace('\n', &format!("\n{}", &indent)));
+        s.push_str(&data.replace('\n', &format!("\n{}", &indent)));
         s.push_str("]]>");
         s
     }
 
-    fn format_start_element(e: &Element) -> String {
+    fn format_start_element(
+        document: &XmlDocument,
+        e: &XmlElement,
+        root: bool,
+        resources: Option<&ResourceTable>,
+    ) -> String {
         let mut s = String::new();
         s.push('<');
-        s.push_str(e.get_tag());
+        s.push_str(&e.tag);
 
-        if e.get_tag() == "manifest" {
-            s.push(' ');
-            s.push_str("xmlns:android=\"http://schemas.android.com/apk/res/android\"");
+        // 命名空间都声明在根节点上，android 前缀始终指向 android 命名空间，
+        // 混淆工具改过 uri 时也按标准 uri 输出
+        if root {
+            let mut declared_android = false;
+            for (prefix, uri) in &document.namespaces {
+                let uri = if prefix == "android" {
+                    if declared_android {
+                        continue;
+                    }
+                    declared_android = true;
+                    ANDROID_NAMESPACE
+                } else {
+                    uri.as_str()
+                };
+                s.push_str(&format!(" xmlns:{}=\"{}\"", prefix, escape(uri)));
+            }
+            if !declared_android {
+                s.push_str(&format!(" xmlns:android=\"{}\"", ANDROID_NAMESPACE));
+            }
         }
 
-        for (key, val) in e.get_attributes().iter() {
+        for attribute in &e.attributes {
             s.push(' ');
-            s.push_str(key);
+            if let Some(prefix) = namespace_prefix(document, &attribute.namespace) {
+                s.push_str(prefix);
+                s.push(':');
+            }
+            s.push_str(&attribute.name);
             s.push('=');
             s.push('"');
-            s.push_str(val);
+            s.push_str(&escape(&attribute.format_value(resources)));
             s.push('"');
         }
 
-        if e.get_children().is_empty() {
+        if is_empty_element(e) {
             s.push('/');
         }
 
@@ -80,11 +352,35 @@ pub mod axml {
         s
     }
 
-    fn format_end_element(e: &Element) -> String {
+    /**
+     * 查找命名空间对应的前缀，没有声明过的命名空间不输出前缀
+     */
+    fn namespace_prefix<'a>(document: &'a XmlDocument, namespace: &str) -> Option<&'a str> {
+        if namespace.is_empty() {
+            return None;
+        }
+        if namespace == ANDROID_NAMESPACE {
+            return Some("android");
+        }
+        document
+            .namespaces
+            .iter()
+            .find(|(prefix, uri)| uri == namespace && prefix != "android")
+            .map(|(prefix, _)| prefix.as_str())
+    }
+
+    fn escape(val: &str) -> String {
+        val.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+
+    fn format_end_element(e: &XmlElement) -> String {
         let mut s = String::new();
         s.push('<');
         s.push('/');
-        s.push_str(e.get_tag());
+        s.push_str(&e.tag);
         s.push('>');
         s
     }
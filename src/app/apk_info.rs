@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ApkParsedInfo {
     pub package_name: String,
     pub version_code: String,
@@ -11,6 +13,33 @@ pub struct ApkParsedInfo {
     pub compile_sdk_version_code_name: String,
     pub permissions: Vec<String>,
     pub icon: String,
+    // uses-feature，可选的特性以 (optional) 结尾
+    #[serde(default)]
+    pub features: Vec<String>,
+    // 四大组件和 activity-alias
+    #[serde(default)]
+    pub components: Vec<ManifestComponent>,
+    // manifest，uses-sdk 和 application 上的属性，键为 元素:属性名，例如 application:usesCleartextTraffic
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    // attributes 中引用资源的属性在默认配置下的值，例如 @bool/cleartext 为 true
+    #[serde(default)]
+    pub resolved_attributes: BTreeMap<String, String>,
+}
+
+/**
+ * 清单文件中声明的组件
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManifestComponent {
+    // activity，activity-alias，service，receiver 或者 provider
+    pub kind: String,
+    // 完整类名，以 . 开头的相对类名会补上包名
+    pub name: String,
+    // 没有声明 android:exported 时，有 intent-filter 的组件视为导出
+    pub exported: bool,
+    // 访问组件需要的权限
+    pub permission: String,
 }
 
 impl ApkParsedInfo {
@@ -25,6 +54,10 @@ impl ApkParsedInfo {
             compile_sdk_version_code_name: "".to_owned(),
             permissions: Vec::new(),
             icon: "".to_owned(),
+            features: Vec::new(),
+            components: Vec::new(),
+            attributes: BTreeMap::new(),
+            resolved_attributes: BTreeMap::new(),
         }
     }
 }
@@ -0,0 +1,534 @@
+pub mod arsc {
+    use std::collections::HashMap;
+
+    use crate::app::res_chunk::chunk::{
+        read_u16, read_u32, read_u8, ChunkHeader, StringPool, RES_STRING_POOL_TYPE,
+        RES_TABLE_PACKAGE_TYPE, RES_TABLE_TYPE, RES_TABLE_TYPE_TYPE,
+    };
+
+    const NO_ENTRY: u32 = 0xFFFFFFFF;
+    const TYPE_FLAG_SPARSE: u8 = 0x01;
+    const TYPE_FLAG_OFFSET16: u8 = 0x02;
+    const ENTRY_FLAG_COMPLEX: u16 = 0x0001;
+    const ENTRY_FLAG_COMPACT: u16 = 0x0008;
+    const RES_VALUE_SIZE: u32 = 8;
+    const RES_MAP_SIZE: u32 = 12;
+    const TYPE_STRING: u8 = 0x03;
+
+    /**
+     * 解析后的 resources.arsc
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ResourceTable {
+        // 资源 id -> (类型, 名称)
+        names: HashMap<u32, (String, String)>,
+        // 资源 id -> 默认配置下的简单值，复杂条目(style，数组等)不保存
+        values: HashMap<u32, ResourceValue>,
+        // 每个配置下的每个条目
+        pub entries: Vec<ResourceEntry>,
+        // resources.arsc 的字节数
+        pub size: u64,
+        // 全局字符串池中的字符串数和 chunk 字节数
+        pub string_count: u32,
+        pub string_bytes: u32,
+    }
+
+    /**
+     * 一个资源在一个配置下的条目
+     * size 为条目和值的字节，字符串值加上全局字符串池中字符串的长度
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ResourceEntry {
+        pub id: u32,
+        pub type_name: String,
+        pub name: String,
+        // 配置限定符，例如 zh-rCN-xxhdpi-v21，默认配置为空
+        pub config: String,
+        pub size: u32,
+    }
+
+    /**
+     * 条目中的 Res_value，字符串值从全局字符串池中取出
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ResourceValue {
+        pub data_type: u8,
+        pub data: u32,
+        pub string: Option<String>,
+    }
+
+    impl ResourceTable {
+        /**
+         * 资源在默认配置下的值，没有默认配置或者是复杂条目时返回 None
+         */
+        pub fn value(&self, id: u32) -> Option<&ResourceValue> {
+            self.values.get(&id)
+        }
+
+        /**
+         * 根据资源 id 查找名称，返回 type/name
+         */
+        pub fn resource_name(&self, id: u32) -> Option<String> {
+            self.names
+                .get(&id)
+                .map(|(type_name, key)| format!("{}/{}", type_name, key))
+        }
+
+        /**
+         * 格式化资源引用，找不到名称时使用十六进制 id
+         * prefix 为 @ 或者 ?
+         */
+        pub fn format_reference(&self, prefix: &str, id: u32) -> String {
+            match self.resource_name(id) {
+                Some(name) => format!("{}{}", prefix, name),
+                None if id >> 24 == 0x01 => format!("{}android:0x{:08x}", prefix, id),
+                None => format!("{}0x{:08x}", prefix, id),
+            }
+        }
+    }
+
+    /**
+     * 解析 resources.arsc，格式不正确时返回 None
+     * 单个 chunk 不完整时跳过该 chunk，尽量多的读取资源
+     */
+    pub fn parse(data: &[u8]) -> Option<ResourceTable> {
+        let header = ChunkHeader::read(data, 0)?;
+        if header.chunk_type != RES_TABLE_TYPE {
+            return None;
+        }
+        let end = header.end().min(data.len());
+        let mut table = ResourceTable {
+            size: data.len() as u64,
+            ..ResourceTable::default()
+        };
+        let mut values = StringPool::default();
+
+        let mut offset = header.body_start();
+        while let Some(sub) = ChunkHeader::read(&data[..end], offset) {
+            if sub.chunk_type == RES_STRING_POOL_TYPE && table.string_bytes == 0 {
+                values = StringPool::read(&data[..end], &sub).unwrap_or_default();
+                table.string_count = values.strings.len() as u32;
+                table.string_bytes = sub.size;
+            } else if sub.chunk_type == RES_TABLE_PACKAGE_TYPE {
+                parse_package(&data[..sub.end().min(end)], &sub, &values, &mut table);
+            }
+            offset = sub.end();
+        }
+
+        Some(table)
+    }
+
+    fn parse_package(data: &[u8], header: &ChunkHeader, values: &StringPool, table: &mut ResourceTable) {
+        let start = header.offset;
+        let id = match read_u32(data, start + 8) {
+            Some(id) => id,
+            None => return,
+        };
+        let type_strings = read_u32(data, start + 268)
+            .and_then(|offset| ChunkHeader::read(data, start + offset as usize))
+            .and_then(|pool| StringPool::read(data, &pool))
+            .unwrap_or_default();
+        let key_strings = read_u32(data, start + 276)
+            .and_then(|offset| ChunkHeader::read(data, start + offset as usize))
+            .and_then(|pool| StringPool::read(data, &pool))
+            .unwrap_or_default();
+
+        let mut offset = header.body_start();
+        while let Some(sub) = ChunkHeader::read(data, offset) {
+            if sub.chunk_type == RES_TABLE_TYPE_TYPE {
+                let pools = Pools {
+                    types: &type_strings,
+                    keys: &key_strings,
+                    values,
+                };
+                parse_type(data, &sub, id, &pools, table);
+            }
+            offset = sub.end();
+        }
+    }
+
+    // 类型名，键名和全局字符串池
+    struct Pools<'a> {
+        types: &'a StringPool,
+        keys: &'a StringPool,
+        values: &'a StringPool,
+    }
+
+    fn parse_type(data: &[u8], header: &ChunkHeader, package_id: u32, pools: &Pools, table: &mut ResourceTable) {
+        let start = header.offset;
+        let data = &data[..header.end().min(data.len())];
+        let (type_id, flags, entry_count, entries_start) = match (
+            read_u8(data, start + 8),
+            read_u8(data, start + 9),
+            read_u32(data, start + 12),
+            read_u32(data, start + 16),
+        ) {
+            (Some(type_id), Some(flags), Some(count), Some(entries_start)) => {
+                (type_id, flags, count as usize, entries_start as usize)
+            }
+            _ => return,
+        };
+        let type_name = pools
+            .types
+            .get((type_id as u32).wrapping_sub(1))
+            .unwrap_or_default()
+            .to_string();
+        let config = read_config(data, start + 20).unwrap_or_default();
+
+        // 偏移表，(条目序号, 条目相对 entries_start 的偏移)
+        let offsets_start = header.body_start();
+        let mut offsets: Vec<(u32, u32)> = Vec::new();
+        for i in 0..entry_count {
+            let (index, entry_offset) = if flags & TYPE_FLAG_SPARSE != 0 {
+                let index = read_u16(data, offsets_start + i * 4).map(u32::from);
+                let entry_offset = read_u16(data, offsets_start + i * 4 + 2).map(|v| v as u32 * 4);
+                (index, entry_offset)
+            } else if flags & TYPE_FLAG_OFFSET16 != 0 {
+                let entry_offset = read_u16(data, offsets_start + i * 2).map(|v| {
+                    if v == 0xFFFF {
+                        NO_ENTRY
+                    } else {
+                        v as u32 * 4
+                    }
+                });
+                (Some(i as u32), entry_offset)
+            } else {
+                (Some(i as u32), read_u32(data, offsets_start + i * 4))
+            };
+            let (index, entry_offset) = match (index, entry_offset) {
+                (Some(index), Some(entry_offset)) => (index, entry_offset),
+                _ => break,
+            };
+            if entry_offset != NO_ENTRY {
+                offsets.push((index, entry_offset));
+            }
+        }
+
+        for (index, entry_offset) in offsets {
+            let position = start + entries_start + entry_offset as usize;
+            let id = (package_id << 24) | ((type_id as u32) << 16) | index;
+            if let Some(key) = read_entry_key(data, position) {
+                let key = pools.keys.get(key).unwrap_or_default().to_string();
+                table.entries.push(ResourceEntry {
+                    id,
+                    type_name: type_name.clone(),
+                    name: key.clone(),
+                    config: config.clone(),
+                    size: read_entry_size(data, position, pools.values).unwrap_or(0),
+                });
+                table
+                    .names
+                    .entry(id)
+                    .or_insert_with(|| (type_name.clone(), key));
+                if config.is_empty() {
+                    if let Some(value) = read_entry_value(data, position, pools.values) {
+                        table.values.insert(id, value);
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * 条目占用的字节，复杂条目为头加上每一项 ResTable_map，字符串值加上字符串的长度
+     */
+    fn read_entry_size(data: &[u8], position: usize, values: &StringPool) -> Option<u32> {
+        let size = read_u16(data, position)? as u32;
+        let flags = read_u16(data, position + 2)?;
+        let string_size = |data_type: u8, index: u32| {
+            if data_type == TYPE_STRING {
+                values.get(index).map_or(0, |value| value.len() as u32)
+            } else {
+                0
+            }
+        };
+        if flags & ENTRY_FLAG_COMPACT != 0 {
+            // 高 8 位是值的类型
+            let data_type = (flags >> 8) as u8;
+            return Some(RES_VALUE_SIZE + string_size(data_type, read_u32(data, position + 4)?));
+        }
+        if flags & ENTRY_FLAG_COMPLEX != 0 {
+            let count = read_u32(data, position + 12)?;
+            return Some(size + count.saturating_mul(RES_MAP_SIZE));
+        }
+        let data_type = read_u8(data, position + size as usize + 3)?;
+        let value = read_u32(data, position + size as usize + 4)?;
+        Some(size + RES_VALUE_SIZE + string_size(data_type, value))
+    }
+
+    /**
+     * 简单条目的值，复杂条目返回 None
+     */
+    fn read_entry_value(data: &[u8], position: usize, values: &StringPool) -> Option<ResourceValue> {
+        let size = read_u16(data, position)? as usize;
+        let flags = read_u16(data, position + 2)?;
+        let (data_type, value) = if flags & ENTRY_FLAG_COMPACT != 0 {
+            ((flags >> 8) as u8, read_u32(data, position + 4)?)
+        } else if flags & ENTRY_FLAG_COMPLEX != 0 {
+            return None;
+        } else {
+            (read_u8(data, position + size + 3)?, read_u32(data, position + size + 4)?)
+        };
+        Some(ResourceValue {
+            data_type,
+            data: value,
+            string: (data_type == TYPE_STRING).then(|| values.get(value).unwrap_or_default().to_string()),
+        })
+    }
+
+    /**
+     * 把 ResTable_config 转换成 aapt 的限定符形式，只处理常用的字段
+     */
+    fn read_config(data: &[u8], position: usize) -> Option<String> {
+        let size = read_u32(data, position)? as usize;
+        let field_u8 = |offset: usize| if offset < size { read_u8(data, position + offset) } else { None };
+        let field_u16 = |offset: usize| if offset + 1 < size { read_u16(data, position + offset) } else { None };
+        let mut parts: Vec<String> = Vec::new();
+
+        let mcc = field_u16(4).unwrap_or(0);
+        let mnc = field_u16(6).unwrap_or(0);
+        if mcc != 0 {
+            parts.push(format!("mcc{}", mcc));
+        }
+        if mnc != 0 {
+            parts.push(format!("mnc{}", mnc));
+        }
+        let language = unpack_locale(field_u8(8).unwrap_or(0), field_u8(9).unwrap_or(0), b'a');
+        let country = unpack_locale(field_u8(10).unwrap_or(0), field_u8(11).unwrap_or(0), b'0');
+        if !language.is_empty() {
+            if country.is_empty() {
+                parts.push(language);
+            } else {
+                parts.push(format!("{}-r{}", language, country));
+            }
+        }
+        match field_u8(28).unwrap_or(0) & 0xC0 {
+            0x40 => parts.push("ldltr".to_string()),
+            0x80 => parts.push("ldrtl".to_string()),
+            _ => {}
+        }
+        let smallest_width = field_u16(30).unwrap_or(0);
+        if smallest_width != 0 {
+            parts.push(format!("sw{}dp", smallest_width));
+        }
+        let width = field_u16(32).unwrap_or(0);
+        if width != 0 {
+            parts.push(format!("w{}dp", width));
+        }
+        let height = field_u16(34).unwrap_or(0);
+        if height != 0 {
+            parts.push(format!("h{}dp", height));
+        }
+        match field_u8(12).unwrap_or(0) {
+            1 => parts.push("port".to_string()),
+            2 => parts.push("land".to_string()),
+            3 => parts.push("square".to_string()),
+            _ => {}
+        }
+        match field_u8(29).unwrap_or(0) & 0x30 {
+            0x10 => parts.push("notnight".to_string()),
+            0x20 => parts.push("night".to_string()),
+            _ => {}
+        }
+        match field_u16(14).unwrap_or(0) {
+            0 => {}
+            120 => parts.push("ldpi".to_string()),
+            160 => parts.push("mdpi".to_string()),
+            213 => parts.push("tvdpi".to_string()),
+            240 => parts.push("hdpi".to_string()),
+            320 => parts.push("xhdpi".to_string()),
+            480 => parts.push("xxhdpi".to_string()),
+            640 => parts.push("xxxhdpi".to_string()),
+            0xFFFE => parts.push("anydpi".to_string()),
+            0xFFFF => parts.push("nodpi".to_string()),
+            density => parts.push(format!("{}dpi", density)),
+        }
+        let sdk = field_u16(24).unwrap_or(0);
+        if sdk != 0 {
+            parts.push(format!("v{}", sdk));
+        }
+        Some(parts.join("-"))
+    }
+
+    /**
+     * 语言和地区是两个字符，最高位为 1 时是压缩的三个字符，每个字符 5 位
+     */
+    fn unpack_locale(first: u8, second: u8, base: u8) -> String {
+        if first == 0 {
+            return String::new();
+        }
+        if first & 0x80 == 0 {
+            return String::from_utf8_lossy(&[first, second]).to_string();
+        }
+        let chars = [
+            second & 0x1f,
+            ((second & 0xe0) >> 5) | ((first & 0x03) << 3),
+            (first & 0x7c) >> 2,
+        ];
+        chars.iter().map(|value| (base + value) as char).collect()
+    }
+
+    /**
+     * 读取一个条目的 key 在 key 字符串池中的序号
+     */
+    fn read_entry_key(data: &[u8], position: usize) -> Option<u32> {
+        let size = read_u16(data, position)?;
+        let flags = read_u16(data, position + 2)?;
+        if flags & ENTRY_FLAG_COMPACT != 0 {
+            // 紧凑格式: key(u16) flags(u16) data(u32)
+            return Some(size as u32);
+        }
+        read_u32(data, position + 4)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{parse, ResourceTable, TYPE_STRING};
+        use crate::app::res_chunk::chunk::{
+            RES_STRING_POOL_TYPE, RES_TABLE_PACKAGE_TYPE, RES_TABLE_TYPE, RES_TABLE_TYPE_TYPE,
+        };
+
+        const TYPE_INT_COLOR_ARGB8: u8 = 0x1c;
+
+        fn chunk(chunk_type: u16, header: &[u8], body: &[u8]) -> Vec<u8> {
+            let header_size = 8 + header.len();
+            let mut data = Vec::new();
+            data.extend_from_slice(&chunk_type.to_le_bytes());
+            data.extend_from_slice(&(header_size as u16).to_le_bytes());
+            data.extend_from_slice(&((header_size + body.len()) as u32).to_le_bytes());
+            data.extend_from_slice(header);
+            data.extend_from_slice(body);
+            data
+        }
+
+        fn words(values: &[u32]) -> Vec<u8> {
+            values.iter().flat_map(|value| value.to_le_bytes()).collect()
+        }
+
+        // utf8 字符串池
+        fn string_pool(strings: &[&str]) -> Vec<u8> {
+            let mut offsets = Vec::new();
+            let mut bytes = Vec::new();
+            for value in strings {
+                offsets.push(bytes.len() as u32);
+                bytes.extend_from_slice(&[value.chars().count() as u8, value.len() as u8]);
+                bytes.extend_from_slice(value.as_bytes());
+                bytes.push(0);
+            }
+            let header = words(&[strings.len() as u32, 0, 1 << 8, 28 + 4 * strings.len() as u32, 0]);
+            chunk(RES_STRING_POOL_TYPE, &header, &[words(&offsets), bytes].concat())
+        }
+
+        /**
+         * 一个类型在一个配置下的 chunk，entries 为 (key 序号, 值的类型, 值)
+         */
+        fn type_chunk(type_id: u8, language: &str, entries: &[(u32, u8, u32)]) -> Vec<u8> {
+            let mut config = vec![0u8; 64];
+            config[0] = 64;
+            config[8..8 + language.len()].copy_from_slice(language.as_bytes());
+            let count = entries.len() as u32;
+            let mut header = vec![type_id, 0, 0, 0];
+            header.extend_from_slice(&words(&[count, 8 + 12 + 64 + 4 * count]));
+            header.extend_from_slice(&config);
+            let mut offsets = Vec::new();
+            let mut body = Vec::new();
+            for (key, data_type, value) in entries {
+                offsets.push(body.len() as u32);
+                body.extend_from_slice(&[8, 0, 0, 0]);
+                body.extend_from_slice(&key.to_le_bytes());
+                body.extend_from_slice(&[8, 0, 0, *data_type]);
+                body.extend_from_slice(&value.to_le_bytes());
+            }
+            chunk(RES_TABLE_TYPE_TYPE, &header, &[words(&offsets), body].concat())
+        }
+
+        /**
+         * string/app_name 默认为 App，fr 下为 Appli，color/primary 为 #ff00ff00
+         */
+        fn table() -> Vec<u8> {
+            let type_pool = string_pool(&["string", "color"]);
+            let key_pool = string_pool(&["app_name", "primary"]);
+            let mut header = words(&[0x7f]);
+            header.extend_from_slice(&[0u8; 256]);
+            let header_size = 8 + header.len() as u32 + 20;
+            header.extend_from_slice(&words(&[header_size, 2, header_size + type_pool.len() as u32, 2, 0]));
+            let body = [
+                type_pool,
+                key_pool,
+                type_chunk(1, "", &[(0, TYPE_STRING, 0)]),
+                type_chunk(1, "fr", &[(0, TYPE_STRING, 1)]),
+                type_chunk(2, "", &[(1, TYPE_INT_COLOR_ARGB8, 0xff00ff00)]),
+            ]
+            .concat();
+            let package = chunk(RES_TABLE_PACKAGE_TYPE, &header, &body);
+            chunk(RES_TABLE_TYPE, &words(&[1]), &[string_pool(&["App", "Appli"]), package].concat())
+        }
+
+        fn entry_names(table: &ResourceTable) -> Vec<(String, String, u32)> {
+            table
+                .entries
+                .iter()
+                .map(|entry| (entry.config.clone(), format!("{}/{}", entry.type_name, entry.name), entry.size))
+                .collect()
+        }
+
+        #[test]
+        fn parses_names_configs_and_values() {
+            let data = table();
+            let table = parse(&data).unwrap();
+            assert_eq!(table.size, data.len() as u64);
+            assert_eq!(table.string_count, 2);
+            assert_eq!(
+                entry_names(&table),
+                vec![
+                    (String::new(), "string/app_name".to_string(), 8 + 8 + 3),
+                    ("fr".to_string(), "string/app_name".to_string(), 8 + 8 + 5),
+                    (String::new(), "color/primary".to_string(), 8 + 8),
+                ]
+            );
+            assert_eq!(table.resource_name(0x7f010000).as_deref(), Some("string/app_name"));
+            assert_eq!(table.format_reference("@", 0x7f020000), "@color/primary");
+            assert_eq!(table.format_reference("@", 0x7f030000), "@0x7f030000");
+            // 默认配置的值
+            let value = table.value(0x7f010000).unwrap();
+            assert_eq!(value.string.as_deref(), Some("App"));
+            assert_eq!(table.value(0x7f020000).unwrap().data, 0xff00ff00);
+        }
+
+        #[test]
+        fn rejects_other_chunks() {
+            assert!(parse(&[]).is_none());
+            assert!(parse(&string_pool(&["App"])).is_none());
+        }
+
+        #[test]
+        fn truncated_table_does_not_panic() {
+            let data = table();
+            for len in 0..data.len() {
+                if let Some(table) = parse(&data[..len]) {
+                    assert!(table.entries.len() <= 3);
+                }
+            }
+        }
+
+        #[test]
+        fn bogus_counts_and_offsets_are_skipped() {
+            let data = table();
+            let type_start = data.windows(4).position(|window| window == [0x01, 0x02, 0x54, 0x00]).unwrap();
+            // 条目数远大于 chunk，只读到 chunk 的结尾
+            let mut count = data.clone();
+            count[type_start + 12..type_start + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(parse(&count).unwrap().entries.len() < 16);
+            // 条目偏移指向 chunk 之外
+            let mut offset = data.clone();
+            offset[type_start + 84..type_start + 88].copy_from_slice(&0x7fff_fff0u32.to_le_bytes());
+            assert_eq!(parse(&offset).unwrap().entries.len(), 2);
+            // 类型池偏移指向文件之外
+            let mut pool = data.clone();
+            let package_start = 12 + string_pool(&["App", "Appli"]).len();
+            pool[package_start + 268..package_start + 272].copy_from_slice(&u32::MAX.to_le_bytes());
+            let table = parse(&pool).unwrap();
+            assert!(table.entries.iter().all(|entry| entry.type_name.is_empty()));
+        }
+    }
+}
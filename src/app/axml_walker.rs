@@ -0,0 +1,414 @@
+pub mod walker {
+    use crate::app::android_attrs::attrs;
+    use crate::app::android_xml::axml::AxmlError;
+    use crate::app::arsc_parser::arsc::ResourceTable;
+    use crate::app::res_chunk::chunk::{
+        read_u16, read_u32, read_u8, ChunkHeader, StringPool, RES_STRING_POOL_TYPE,
+        RES_XML_CDATA_TYPE, RES_XML_END_ELEMENT_TYPE, RES_XML_END_NAMESPACE_TYPE,
+        RES_XML_RESOURCE_MAP_TYPE, RES_XML_START_ELEMENT_TYPE, RES_XML_START_NAMESPACE_TYPE,
+        RES_XML_TYPE,
+    };
+
+    pub const ANDROID_NAMESPACE: &str = "http://schemas.android.com/apk/res/android";
+
+    const NO_INDEX: u32 = 0xFFFFFFFF;
+    // chunk 头 8 字节 + 行号 4 字节 + 注释 4 字节
+    const NODE_HEADER_SIZE: usize = 16;
+    const STRING_POOL_HEADER_SIZE: u16 = 28;
+    const ATTRIBUTE_SIZE: usize = 20;
+
+    // Res_value 中的数据类型
+    pub const TYPE_NULL: u8 = 0x00;
+    pub const TYPE_REFERENCE: u8 = 0x01;
+    pub const TYPE_ATTRIBUTE: u8 = 0x02;
+    pub const TYPE_STRING: u8 = 0x03;
+    pub const TYPE_FLOAT: u8 = 0x04;
+    pub const TYPE_DIMENSION: u8 = 0x05;
+    pub const TYPE_FRACTION: u8 = 0x06;
+    pub const TYPE_DYNAMIC_REFERENCE: u8 = 0x07;
+    pub const TYPE_DYNAMIC_ATTRIBUTE: u8 = 0x08;
+    pub const TYPE_INT_DEC: u8 = 0x10;
+    pub const TYPE_INT_HEX: u8 = 0x11;
+    pub const TYPE_INT_BOOLEAN: u8 = 0x12;
+    pub const TYPE_INT_COLOR_ARGB8: u8 = 0x1c;
+    pub const TYPE_INT_COLOR_RGB8: u8 = 0x1d;
+    pub const TYPE_INT_COLOR_ARGB4: u8 = 0x1e;
+    pub const TYPE_INT_COLOR_RGB4: u8 = 0x1f;
+
+    const DIMENSION_UNITS: [&str; 6] = ["px", "dp", "sp", "pt", "in", "mm"];
+    const FRACTION_UNITS: [&str; 2] = ["%", "%p"];
+
+    /**
+     * 元素上的一个属性
+     * 名称优先来自资源映射表中的 id，系统按 id 识别属性，混淆工具改不了 id
+     */
+    #[derive(Debug, Clone)]
+    pub struct XmlAttribute {
+        pub namespace: String,
+        pub name: String,
+        pub resource_id: Option<u32>,
+        pub raw_value: Option<String>,
+        pub data_type: u8,
+        pub data: u32,
+    }
+
+    impl XmlAttribute {
+        /**
+         * 文本 xml 中的属性，值按字符串保存
+         */
+        pub fn text(namespace: &str, name: &str, value: &str) -> XmlAttribute {
+            XmlAttribute {
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+                resource_id: None,
+                raw_value: Some(value.to_string()),
+                data_type: TYPE_STRING,
+                data: 0,
+            }
+        }
+
+        /**
+         * 属性值的文本形式，资源引用输出为十六进制 id
+         */
+        pub fn value(&self) -> String {
+            self.format_value(None)
+        }
+
+        /**
+         * 按数据类型格式化属性值，资源引用通过 resources.arsc 转换成 @type/name
+         * 枚举和标志位属性输出为名称，例如 screenOrientation="portrait"
+         */
+        pub fn format_value(&self, resources: Option<&ResourceTable>) -> String {
+            let data = self.data;
+            match self.data_type {
+                TYPE_NULL => match data {
+                    1 => "@empty".to_string(),
+                    _ => String::new(),
+                },
+                TYPE_REFERENCE | TYPE_DYNAMIC_REFERENCE if data == 0 => "@null".to_string(),
+                TYPE_REFERENCE | TYPE_DYNAMIC_REFERENCE => format_reference("@", data, resources),
+                TYPE_ATTRIBUTE | TYPE_DYNAMIC_ATTRIBUTE => format_reference("?", data, resources),
+                TYPE_STRING => self.raw_value.clone().unwrap_or_default(),
+                TYPE_FLOAT => f32::from_bits(data).to_string(),
+                TYPE_DIMENSION => {
+                    let unit = DIMENSION_UNITS.get((data & 0xf) as usize).unwrap_or(&"");
+                    format!("{}{}", complex_to_float(data), unit)
+                }
+                TYPE_FRACTION => {
+                    let unit = FRACTION_UNITS.get((data & 0xf) as usize).unwrap_or(&"");
+                    format!("{}{}", complex_to_float(data) * 100.0, unit)
+                }
+                TYPE_INT_DEC | TYPE_INT_HEX => {
+                    let named = self.resource_id.and_then(|id| attrs::format_int(id, data));
+                    match (named, self.data_type) {
+                        (Some(named), _) => named,
+                        (None, TYPE_INT_HEX) => format!("0x{:08x}", data),
+                        (None, _) => (data as i32).to_string(),
+                    }
+                }
+                TYPE_INT_BOOLEAN => (data != 0).to_string(),
+                TYPE_INT_COLOR_ARGB8 | TYPE_INT_COLOR_ARGB4 => format!("#{:08x}", data),
+                TYPE_INT_COLOR_RGB8 | TYPE_INT_COLOR_RGB4 => format!("#{:06x}", data & 0xFFFFFF),
+                _ => self
+                    .raw_value
+                    .clone()
+                    .unwrap_or_else(|| format!("0x{:08x}", data)),
+            }
+        }
+
+        /**
+         * 资源引用替换成默认配置下的值，例如 @bool/cleartext 替换成 true
+         * 引用其他资源的继续查找，找不到值时与 format_value 相同
+         */
+        pub fn resolved_value(&self, resources: Option<&ResourceTable>) -> String {
+            let mut attribute = self.clone();
+            if let Some(resources) = resources {
+                // 限制查找次数，避免循环引用
+                for _ in 0..8 {
+                    if attribute.data_type != TYPE_REFERENCE && attribute.data_type != TYPE_DYNAMIC_REFERENCE {
+                        break;
+                    }
+                    let Some(value) = resources.value(attribute.data) else {
+                        break;
+                    };
+                    attribute.data_type = value.data_type;
+                    attribute.data = value.data;
+                    attribute.raw_value = value.string.clone();
+                }
+            }
+            attribute.format_value(resources)
+        }
+    }
+
+    fn format_reference(prefix: &str, id: u32, resources: Option<&ResourceTable>) -> String {
+        match resources {
+            Some(resources) => resources.format_reference(prefix, id),
+            None if id >> 24 == 0x01 => format!("{}android:0x{:08x}", prefix, id),
+            None => format!("{}0x{:08x}", prefix, id),
+        }
+    }
+
+    /**
+     * 解析尺寸和分数使用的复合值：高 24 位是尾数，4~5 位是小数点位置，低 4 位是单位
+     */
+    fn complex_to_float(data: u32) -> f32 {
+        const RADIX_MULTIPLIERS: [f32; 4] = [
+            1.0 / 256.0,
+            1.0 / 32768.0,
+            1.0 / 8388608.0,
+            1.0 / 2147483648.0,
+        ];
+        let mantissa = (data & 0xFFFFFF00) as i32 as f32;
+        mantissa * RADIX_MULTIPLIERS[((data >> 4) & 0x3) as usize]
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct XmlElement {
+        pub tag: String,
+        pub attributes: Vec<XmlAttribute>,
+        pub children: Vec<XmlElement>,
+        // CDATA 文本
+        pub text: String,
+    }
+
+    impl XmlElement {
+        /**
+         * 按命名空间和名称精确查找属性
+         */
+        pub fn attribute(&self, namespace: &str, name: &str) -> Option<&XmlAttribute> {
+            self.attributes
+                .iter()
+                .find(|attribute| attribute.namespace == namespace && attribute.name == name)
+        }
+    }
+
+    /**
+     * 解析后的文档，namespaces 为声明过的 (前缀, uri)
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct XmlDocument {
+        pub namespaces: Vec<(String, String)>,
+        pub root: XmlElement,
+    }
+
+    /**
+     * 容错的二进制 xml 解析
+     * 不信任 chunk 中声明的大小，大小不合法时按结构推算下一个 chunk 的位置，推算不出时向后查找
+     * 字符串池损坏时使用空字符串，属性名优先使用资源 id 对应的系统属性名
+     */
+    pub fn walk(data: &[u8]) -> Result<XmlDocument, AxmlError> {
+        if data.is_empty() {
+            return Err(AxmlError::Empty);
+        }
+        if read_u16(data, 0) != Some(RES_XML_TYPE) {
+            return Err(AxmlError::NotBinaryXml);
+        }
+        let header_size = read_u16(data, 2).unwrap_or(8) as usize;
+        let mut offset = if header_size >= 8 && header_size < data.len() {
+            header_size
+        } else {
+            8
+        };
+
+        let mut pool = StringPool::default();
+        let mut resource_ids: Vec<u32> = Vec::new();
+        let mut namespaces: Vec<(String, String)> = Vec::new();
+        let mut stack: Vec<XmlElement> = Vec::new();
+        let mut root: Option<XmlElement> = None;
+
+        while offset + 8 <= data.len() && root.is_none() {
+            let chunk_type = read_u16(data, offset).unwrap_or(0);
+            let chunk_header_size = read_u16(data, offset + 2).unwrap_or(0) as usize;
+            let size = read_u32(data, offset + 4).unwrap_or(0) as usize;
+            let valid = size >= 8
+                && chunk_header_size >= 8
+                && chunk_header_size <= size
+                && offset + size <= data.len();
+            let declared_end = if valid { Some(offset + size) } else { None };
+            // 节点的扩展数据紧跟在 16 字节的节点头后
+            let ext = if valid && chunk_header_size >= NODE_HEADER_SIZE {
+                offset + chunk_header_size
+            } else {
+                offset + NODE_HEADER_SIZE
+            };
+
+            let next = match chunk_type {
+                RES_STRING_POOL_TYPE => {
+                    let end = declared_end.unwrap_or(data.len());
+                    let header = ChunkHeader {
+                        offset,
+                        chunk_type,
+                        header_size: if valid && chunk_header_size >= 28 {
+                            chunk_header_size as u16
+                        } else {
+                            STRING_POOL_HEADER_SIZE
+                        },
+                        size: (end - offset) as u32,
+                    };
+                    if let Some(string_pool) = StringPool::read(data, &header) {
+                        pool = string_pool;
+                    }
+                    declared_end
+                }
+                RES_XML_RESOURCE_MAP_TYPE => {
+                    let end = declared_end.unwrap_or(data.len());
+                    let start = offset + chunk_header_size.clamp(8, end - offset);
+                    resource_ids = (start..end)
+                        .step_by(4)
+                        .map_while(|position| read_u32(data, position))
+                        .collect();
+                    declared_end
+                }
+                RES_XML_START_NAMESPACE_TYPE | RES_XML_END_NAMESPACE_TYPE => {
+                    if chunk_type == RES_XML_START_NAMESPACE_TYPE {
+                        if let (Some(prefix), Some(uri)) = (read_u32(data, ext), read_u32(data, ext + 4)) {
+                            let prefix = pool.get(prefix).unwrap_or_default().to_string();
+                            let uri = pool.get(uri).unwrap_or_default().to_string();
+                            if !namespaces.contains(&(prefix.clone(), uri.clone())) {
+                                namespaces.push((prefix, uri));
+                            }
+                        }
+                    }
+                    declared_end.or(Some(ext + 8))
+                }
+                RES_XML_START_ELEMENT_TYPE => {
+                    let (element, element_end) = read_element(data, ext, &pool, &resource_ids);
+                    stack.push(element);
+                    declared_end.or(element_end)
+                }
+                RES_XML_END_ELEMENT_TYPE => {
+                    if let Some(element) = stack.pop() {
+                        match stack.last_mut() {
+                            Some(parent) => parent.children.push(element),
+                            None => root = Some(element),
+                        }
+                    }
+                    declared_end.or(Some(ext + 8))
+                }
+                RES_XML_CDATA_TYPE => {
+                    if let (Some(element), Some(text)) = (stack.last_mut(), read_u32(data, ext)) {
+                        element.text.push_str(pool.get(text).unwrap_or_default());
+                    }
+                    declared_end.or(Some(ext + 12))
+                }
+                _ => declared_end,
+            };
+
+            offset = match next {
+                Some(next) if next > offset => next,
+                _ => match find_next_chunk(data, offset + 4) {
+                    Some(next) => next,
+                    None => break,
+                },
+            };
+        }
+
+        // 文件被截断时，把未闭合的元素依次挂到父节点上
+        while let Some(element) = stack.pop() {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => root = Some(element),
+            }
+        }
+        let root = root.ok_or(AxmlError::NoRoot)?;
+        Ok(XmlDocument { namespaces, root })
+    }
+
+    /**
+     * 读取开始元素，返回元素和按属性数量推算出的 chunk 结束位置
+     */
+    fn read_element(
+        data: &[u8],
+        ext: usize,
+        pool: &StringPool,
+        resource_ids: &[u32],
+    ) -> (XmlElement, Option<usize>) {
+        let mut element = XmlElement {
+            tag: read_u32(data, ext + 4)
+                .and_then(|name| pool.get(name))
+                .unwrap_or_default()
+                .to_string(),
+            ..XmlElement::default()
+        };
+        let attribute_start = read_u16(data, ext + 8).unwrap_or(20) as usize;
+        let attribute_size = read_u16(data, ext + 10).unwrap_or(20) as usize;
+        let attribute_count = read_u16(data, ext + 12).unwrap_or(0) as usize;
+        let attribute_start = attribute_start.max(20);
+        let attribute_size = attribute_size.max(ATTRIBUTE_SIZE);
+
+        let mut position = ext + attribute_start;
+        for _ in 0..attribute_count {
+            match read_attribute(data, position, pool, resource_ids) {
+                Some(attribute) => element.attributes.push(attribute),
+                None => return (element, None),
+            }
+            position += attribute_size;
+        }
+        (element, Some(position))
+    }
+
+    fn read_attribute(
+        data: &[u8],
+        position: usize,
+        pool: &StringPool,
+        resource_ids: &[u32],
+    ) -> Option<XmlAttribute> {
+        let namespace = read_u32(data, position)?;
+        let name = read_u32(data, position + 4)?;
+        let raw_value = read_u32(data, position + 8)?;
+        let data_type = read_u8(data, position + 15)?;
+        let value = read_u32(data, position + 16)?;
+
+        let resource_id = resource_ids.get(name as usize).copied().filter(|id| *id != 0);
+        let system_name = resource_id.and_then(attrs::attribute_name);
+        let mut namespace = pool.get(namespace).unwrap_or_default().to_string();
+        // 系统属性一定属于 android 命名空间，混淆工具可能改掉 uri
+        if system_name.is_some() || resource_id.is_some_and(|id| id >> 24 == 0x01) {
+            namespace = ANDROID_NAMESPACE.to_string();
+        }
+        let name = match system_name {
+            Some(system_name) => system_name.to_string(),
+            None => pool.get(name).unwrap_or_default().to_string(),
+        };
+        let raw_value = if raw_value == NO_INDEX {
+            None
+        } else {
+            pool.get(raw_value).map(|value| value.to_string())
+        };
+        let raw_value = match (raw_value, data_type) {
+            (None, TYPE_STRING) => pool.get(value).map(|value| value.to_string()),
+            (raw_value, _) => raw_value,
+        };
+
+        Some(XmlAttribute {
+            namespace,
+            name,
+            resource_id,
+            raw_value,
+            data_type,
+            data: value,
+        })
+    }
+
+    /**
+     * 从 from 开始按 4 字节对齐查找下一个看起来合法的节点 chunk
+     */
+    fn find_next_chunk(data: &[u8], from: usize) -> Option<usize> {
+        let from = (from + 3) & !3;
+        (from..data.len().saturating_sub(8)).step_by(4).find(|offset| {
+            let chunk_type = read_u16(data, *offset).unwrap_or(0);
+            let header_size = read_u16(data, offset + 2).unwrap_or(0) as usize;
+            let size = read_u32(data, offset + 4).unwrap_or(0) as usize;
+            let node = matches!(
+                chunk_type,
+                RES_XML_START_NAMESPACE_TYPE
+                    | RES_XML_END_NAMESPACE_TYPE
+                    | RES_XML_START_ELEMENT_TYPE
+                    | RES_XML_END_ELEMENT_TYPE
+                    | RES_XML_CDATA_TYPE
+            );
+            (node && header_size == NODE_HEADER_SIZE && size >= NODE_HEADER_SIZE + 8)
+                || (chunk_type == RES_XML_RESOURCE_MAP_TYPE && header_size == 8 && size >= 8)
+        })
+    }
+}
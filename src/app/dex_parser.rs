@@ -0,0 +1,595 @@
+pub mod dex {
+    use std::collections::hash_map::DefaultHasher;
+    use std::fmt;
+    use std::hash::{Hash, Hasher};
+
+    use crate::app::res_chunk::chunk::{read_u16, read_u32};
+
+    const HEADER_SIZE: usize = 0x70;
+    const CLASS_DEF_SIZE: usize = 32;
+    const TRY_ITEM_SIZE: usize = 8;
+    const NO_INDEX: u32 = 0xFFFFFFFF;
+    // encoded_value 中数组和注解的最大嵌套层数，防止构造的文件耗尽栈
+    const MAX_ENCODED_DEPTH: usize = 32;
+
+    /**
+     * dex 解析错误
+     */
+    #[derive(Debug)]
+    pub enum DexError {
+        // 不是以 dex\n 开头
+        NotDex,
+        // 表的位置超出文件范围
+        Truncated(&'static str),
+        // 数量或者长度溢出，或者超出剩余的字节
+        Invalid(&'static str),
+    }
+
+    impl fmt::Display for DexError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                DexError::NotDex => write!(f, "not a dex file"),
+                DexError::Truncated(section) => write!(f, "dex file is truncated at {}", section),
+                DexError::Invalid(section) => write!(f, "dex file has an invalid {}", section),
+            }
+        }
+    }
+
+    impl std::error::Error for DexError {}
+
+    /**
+     * dex 中定义的一个类
+     * size 为只属于这个类的字节：class_def，class_data，方法的 code_item 和静态字段初始值，
+     * 字符串，类型，方法引用等共享的表不计算在内
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct DexClass {
+        // java 形式的类名，例如 com.example.Foo$Bar
+        pub name: String,
+        pub superclass: String,
+        pub size: u64,
+        pub methods: u32,
+        pub fields: u32,
+        // 结构哈希：字段和方法的访问标志，寄存器数和指令的操作码序列，不包含字符串，类型和方法的索引，
+        // 不同 dex 或者不同包名下相同的代码哈希相同
+        pub body_hash: u64,
+        // 所有方法的指令长度，单位为 16 位
+        pub code_units: u32,
+    }
+
+    #[derive(Debug, Clone, Default)]
+    pub struct DexFile {
+        pub size: u64,
+        pub classes: Vec<DexClass>,
+    }
+
+    impl DexFile {
+        /**
+         * 所有类自身的字节之和，用于按比例分摊共享的表
+         */
+        pub fn class_bytes(&self) -> u64 {
+            self.classes.iter().map(|class| class.size).sum()
+        }
+    }
+
+    /**
+     * 解析 dex 中的类定义，计算每个类占用的字节
+     */
+    pub fn parse(data: &[u8]) -> Result<DexFile, DexError> {
+        if data.len() < HEADER_SIZE || !data.starts_with(b"dex\n") {
+            return Err(DexError::NotDex);
+        }
+        let header = |offset| read_u32(data, offset).ok_or(DexError::Truncated("header"));
+        let string_ids_size = header(0x38)? as usize;
+        let string_ids_off = header(0x3C)? as usize;
+        let type_ids_size = header(0x40)? as usize;
+        let type_ids_off = header(0x44)? as usize;
+        let class_defs_size = header(0x60)? as usize;
+        let class_defs_off = header(0x64)? as usize;
+
+        let class_defs_end = class_defs_size
+            .checked_mul(CLASS_DEF_SIZE)
+            .and_then(|size| size.checked_add(class_defs_off))
+            .ok_or(DexError::Invalid("class_defs"))?;
+        if class_defs_end > data.len() {
+            return Err(DexError::Truncated("class_defs"));
+        }
+        let tables = Tables {
+            data,
+            string_ids_size,
+            string_ids_off,
+            type_ids_size,
+            type_ids_off,
+        };
+
+        let mut classes = Vec::with_capacity(class_defs_size);
+        for i in 0..class_defs_size {
+            let offset = class_defs_off + i * CLASS_DEF_SIZE;
+            let field = |index: usize| read_u32(data, offset + index * 4).unwrap_or(0);
+            let class_idx = field(0);
+            let superclass_idx = field(2);
+            let class_data_off = field(6) as usize;
+            let static_values_off = field(7) as usize;
+
+            let mut class = DexClass {
+                name: tables.type_name(class_idx).unwrap_or_default(),
+                superclass: tables.type_name(superclass_idx).unwrap_or_default(),
+                size: CLASS_DEF_SIZE as u64,
+                ..DexClass::default()
+            };
+            if class_data_off != 0 {
+                let class_data = read_class_data(data, class_data_off)?;
+                class.size += class_data.size as u64;
+                class.methods = class_data.methods.len() as u32;
+                class.fields = class_data.fields.len() as u32;
+                let mut hasher = DefaultHasher::new();
+                class_data.fields.hash(&mut hasher);
+                for (access_flags, code_off) in &class_data.methods {
+                    access_flags.hash(&mut hasher);
+                    if *code_off == 0 {
+                        continue;
+                    }
+                    class.size += code_item_size(data, *code_off)
+                        .ok_or(DexError::Truncated("code_item"))? as u64;
+                    class.code_units += hash_code_item(data, *code_off, &mut hasher)
+                        .ok_or(DexError::Truncated("code_item"))?;
+                }
+                class.body_hash = hasher.finish();
+            }
+            if static_values_off != 0 {
+                class.size += encoded_array_size(data, static_values_off, 0).unwrap_or(0) as u64;
+            }
+            classes.push(class);
+        }
+
+        Ok(DexFile {
+            size: data.len() as u64,
+            classes,
+        })
+    }
+
+    /**
+     * Lcom/example/Foo; 转换成 com.example.Foo，数组和基本类型原样返回
+     */
+    pub fn descriptor_to_name(descriptor: &str) -> String {
+        match descriptor.strip_prefix('L').and_then(|name| name.strip_suffix(';')) {
+            Some(name) => name.replace('/', "."),
+            None => descriptor.to_string(),
+        }
+    }
+
+    struct Tables<'a> {
+        data: &'a [u8],
+        string_ids_size: usize,
+        string_ids_off: usize,
+        type_ids_size: usize,
+        type_ids_off: usize,
+    }
+
+    impl Tables<'_> {
+        fn type_name(&self, type_idx: u32) -> Option<String> {
+            if type_idx == NO_INDEX || type_idx as usize >= self.type_ids_size {
+                return None;
+            }
+            let descriptor_idx = read_u32(self.data, self.type_ids_off + type_idx as usize * 4)?;
+            self.string(descriptor_idx).map(|descriptor| descriptor_to_name(&descriptor))
+        }
+
+        fn string(&self, string_idx: u32) -> Option<String> {
+            if string_idx as usize >= self.string_ids_size {
+                return None;
+            }
+            let offset = read_u32(self.data, self.string_ids_off + string_idx as usize * 4)? as usize;
+            // utf16 长度之后是以 0 结尾的 MUTF-8
+            let (_, len) = read_uleb128(self.data, offset)?;
+            let start = offset + len;
+            let end = start + self.data.get(start..)?.iter().position(|byte| *byte == 0)?;
+            Some(String::from_utf8_lossy(&self.data[start..end]).to_string())
+        }
+    }
+
+    struct ClassData {
+        size: usize,
+        // 字段的访问标志
+        fields: Vec<u32>,
+        // (访问标志，code_off)
+        methods: Vec<(u32, usize)>,
+    }
+
+    fn read_class_data(data: &[u8], offset: usize) -> Result<ClassData, DexError> {
+        let mut position = offset;
+        let next = |position: &mut usize| {
+            let (value, len) =
+                read_uleb128(data, *position).ok_or(DexError::Truncated("class_data_item"))?;
+            *position += len;
+            Ok(value)
+        };
+        let static_fields = next(&mut position)?;
+        let instance_fields = next(&mut position)?;
+        let direct_methods = next(&mut position)?;
+        let virtual_methods = next(&mut position)?;
+        let field_count = static_fields
+            .checked_add(instance_fields)
+            .ok_or(DexError::Invalid("class_data_item"))? as usize;
+        let method_count = direct_methods
+            .checked_add(virtual_methods)
+            .ok_or(DexError::Invalid("class_data_item"))? as usize;
+        // 每个字段至少 2 字节，每个方法至少 3 字节，超出剩余的字节说明数量是错的
+        let remaining = data.len().saturating_sub(position);
+        if field_count > remaining / 2 || method_count > remaining / 3 {
+            return Err(DexError::Invalid("class_data_item"));
+        }
+
+        // field_idx_diff，access_flags
+        let mut fields = Vec::with_capacity(field_count);
+        for _ in 0..field_count {
+            next(&mut position)?;
+            fields.push(next(&mut position)?);
+        }
+        // method_idx_diff，access_flags，code_off
+        let mut methods = Vec::with_capacity(method_count);
+        for _ in 0..method_count {
+            next(&mut position)?;
+            let access_flags = next(&mut position)?;
+            let code_off = next(&mut position)? as usize;
+            methods.push((access_flags, code_off));
+        }
+        Ok(ClassData {
+            size: position - offset,
+            fields,
+            methods,
+        })
+    }
+
+    /**
+     * code_item 的大小：16 字节头，指令，对齐，try 表和异常处理表
+     */
+    fn code_item_size(data: &[u8], offset: usize) -> Option<usize> {
+        let tries_size = read_u16(data, offset + 6)? as usize;
+        let insns_size = read_u32(data, offset + 12)? as usize;
+        let mut position = insns_size.checked_mul(2)?.checked_add(offset + 16)?;
+        if tries_size == 0 {
+            return (position <= data.len()).then_some(position - offset);
+        }
+        if insns_size % 2 == 1 {
+            position += 2;
+        }
+        position += tries_size * TRY_ITEM_SIZE;
+        let (handlers, len) = read_uleb128(data, position)?;
+        position += len;
+        // 每个处理表至少 1 字节
+        if handlers as usize > data.len().saturating_sub(position) {
+            return None;
+        }
+        for _ in 0..handlers {
+            let (size, len) = read_sleb128(data, position)?;
+            position += len;
+            // type_idx，addr
+            for _ in 0..size.unsigned_abs().checked_mul(2)? {
+                position += read_uleb128(data, position)?.1;
+            }
+            // catch_all_addr
+            if size <= 0 {
+                position += read_uleb128(data, position)?.1;
+            }
+        }
+        Some(position - offset)
+    }
+
+    /**
+     * 把 code_item 的结构写入哈希，返回指令长度
+     * 只取操作码，索引类的操作数在不同 dex 中不同；switch 和数组数据的内容是常量，原样写入
+     */
+    fn hash_code_item(data: &[u8], offset: usize, hasher: &mut DefaultHasher) -> Option<u32> {
+        let registers = read_u16(data, offset)?;
+        let ins = read_u16(data, offset + 2)?;
+        let outs = read_u16(data, offset + 4)?;
+        let tries = read_u16(data, offset + 6)?;
+        let insns_size = read_u32(data, offset + 12)? as usize;
+        (registers, ins, outs, tries, insns_size).hash(hasher);
+        let insns = offset + 16;
+        let mut unit = 0;
+        while unit < insns_size {
+            let code = read_u16(data, insns + unit * 2)?;
+            let width = match code {
+                // packed-switch-payload：size，first_key，targets
+                0x0100 => 4 + read_u16(data, insns + unit * 2 + 2)? as usize * 2,
+                // sparse-switch-payload：size，keys，targets
+                0x0200 => 2 + read_u16(data, insns + unit * 2 + 2)? as usize * 4,
+                // fill-array-data-payload：element_width，size，data
+                0x0300 => {
+                    let element_width = read_u16(data, insns + unit * 2 + 2)? as usize;
+                    let size = read_u32(data, insns + unit * 2 + 4)? as usize;
+                    4 + size.checked_mul(element_width)?.div_ceil(2)
+                }
+                _ => instruction_width((code & 0xff) as u8),
+            };
+            match code {
+                0x0100 | 0x0200 | 0x0300 => {
+                    let end = (unit + width).min(insns_size);
+                    data.get(insns + unit * 2..insns + end * 2)?.hash(hasher);
+                }
+                _ => ((code & 0xff) as u8).hash(hasher),
+            }
+            unit += width;
+        }
+        Some(insns_size as u32)
+    }
+
+    /**
+     * 指令的长度，单位为 16 位
+     */
+    fn instruction_width(opcode: u8) -> usize {
+        match opcode {
+            0x02 | 0x05 | 0x08 | 0x13 | 0x15 | 0x16 | 0x19 | 0x1a | 0x1c | 0x1f | 0x20 | 0x22 | 0x23
+            | 0x29 => 2,
+            0x03 | 0x06 | 0x09 | 0x14 | 0x17 | 0x1b | 0x24 | 0x25 | 0x26 | 0x2a | 0x2b | 0x2c => 3,
+            0x18 => 5,
+            // cmp，if-test，if-testz
+            0x2d..=0x3d => 2,
+            // aget/aput，iget/iput，sget/sput
+            0x44..=0x6d => 2,
+            // invoke-kind，invoke-kind/range
+            0x6e..=0x72 | 0x74..=0x78 => 3,
+            // binop
+            0x90..=0xaf => 2,
+            // binop/lit16，binop/lit8
+            0xd0..=0xe2 => 2,
+            // invoke-polymorphic
+            0xfa | 0xfb => 4,
+            // invoke-custom
+            0xfc | 0xfd => 3,
+            // const-method-handle，const-method-type
+            0xfe | 0xff => 2,
+            _ => 1,
+        }
+    }
+
+    /**
+     * 静态字段初始值 encoded_array 的大小
+     */
+    fn encoded_array_size(data: &[u8], offset: usize, depth: usize) -> Option<usize> {
+        if depth > MAX_ENCODED_DEPTH {
+            return None;
+        }
+        let (size, len) = read_uleb128(data, offset)?;
+        let mut position = offset + len;
+        for _ in 0..size {
+            position += encoded_value_size(data, position, depth)?;
+        }
+        Some(position - offset)
+    }
+
+    fn encoded_value_size(data: &[u8], offset: usize, depth: usize) -> Option<usize> {
+        let header = *data.get(offset)?;
+        let value_type = header & 0x1f;
+        let value_arg = (header >> 5) as usize;
+        let size = match value_type {
+            // ARRAY
+            0x1c => 1 + encoded_array_size(data, offset + 1, depth + 1)?,
+            // ANNOTATION：type_idx，size，(name_idx，value)*
+            0x1d => {
+                if depth >= MAX_ENCODED_DEPTH {
+                    return None;
+                }
+                let mut position = offset + 1;
+                position += read_uleb128(data, position)?.1;
+                let (size, len) = read_uleb128(data, position)?;
+                position += len;
+                for _ in 0..size {
+                    position += read_uleb128(data, position)?.1;
+                    position += encoded_value_size(data, position, depth + 1)?;
+                }
+                position - offset
+            }
+            // NULL，BOOLEAN 没有数据
+            0x1e | 0x1f => 1,
+            _ => 1 + value_arg + 1,
+        };
+        Some(size)
+    }
+
+    /**
+     * 返回值和占用的字节数
+     */
+    pub fn read_uleb128(data: &[u8], offset: usize) -> Option<(u32, usize)> {
+        let mut result: u32 = 0;
+        for i in 0..5 {
+            let byte = *data.get(offset + i)?;
+            result |= ((byte & 0x7f) as u32) << (i * 7);
+            if byte & 0x80 == 0 {
+                return Some((result, i + 1));
+            }
+        }
+        None
+    }
+
+    pub fn read_sleb128(data: &[u8], offset: usize) -> Option<(i32, usize)> {
+        let mut result: i32 = 0;
+        for i in 0..5 {
+            let byte = *data.get(offset + i)?;
+            result |= ((byte & 0x7f) as i32) << (i * 7);
+            if byte & 0x80 == 0 {
+                let shift = (i + 1) * 7;
+                if shift < 32 && byte & 0x40 != 0 {
+                    result |= -1 << shift;
+                }
+                return Some((result, i + 1));
+            }
+        }
+        None
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{parse, read_sleb128, read_uleb128, DexError, CLASS_DEF_SIZE, HEADER_SIZE, NO_INDEX};
+
+        fn uleb(mut value: u32) -> Vec<u8> {
+            let mut out = Vec::new();
+            loop {
+                let byte = (value & 0x7f) as u8;
+                value >>= 7;
+                if value == 0 {
+                    out.push(byte);
+                    return out;
+                }
+                out.push(byte | 0x80);
+            }
+        }
+
+        fn words(values: &[u32]) -> Vec<u8> {
+            values.iter().flat_map(|value| value.to_le_bytes()).collect()
+        }
+
+        /**
+         * 父类都是 java.lang.Object 的 dex，classes 为 (类名, 方法数, 字段数)
+         * 每个方法有 insns 个 return-void，返回 dex 和每个类的 class_data 偏移
+         */
+        fn dex(classes: &[(&str, u32, u32)], insns: u32) -> (Vec<u8>, Vec<usize>) {
+            let mut strings: Vec<String> = classes
+                .iter()
+                .map(|(name, _, _)| format!("L{};", name.replace('.', "/")))
+                .collect();
+            strings.push("Ljava/lang/Object;".to_string());
+            let object = (strings.len() - 1) as u32;
+            let string_ids_off = HEADER_SIZE;
+            let type_ids_off = string_ids_off + strings.len() * 4;
+            let class_defs_off = type_ids_off + strings.len() * 4;
+            let data_off = class_defs_off + classes.len() * CLASS_DEF_SIZE;
+
+            let mut data = Vec::new();
+            let mut string_offsets = Vec::new();
+            for value in &strings {
+                string_offsets.push((data_off + data.len()) as u32);
+                data.extend(uleb(value.len() as u32));
+                data.extend_from_slice(value.as_bytes());
+                data.push(0);
+            }
+            let mut class_defs = Vec::new();
+            let mut class_data_offsets = Vec::new();
+            for (index, (_, methods, fields)) in classes.iter().enumerate() {
+                let mut code_offsets = Vec::new();
+                for _ in 0..*methods {
+                    while !(data_off + data.len()).is_multiple_of(4) {
+                        data.push(0);
+                    }
+                    code_offsets.push((data_off + data.len()) as u32);
+                    data.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 0]);
+                    data.extend(words(&[0, insns]));
+                    (0..insns).for_each(|_| data.extend_from_slice(&[0x0e, 0x00]));
+                }
+                let class_data_off = data_off + data.len();
+                class_data_offsets.push(class_data_off);
+                [*fields, 0, *methods, 0].iter().for_each(|count| data.extend(uleb(*count)));
+                for field in 0..*fields {
+                    data.extend(uleb(field.min(1)));
+                    data.extend(uleb(1));
+                }
+                for (method, code_off) in code_offsets.iter().enumerate() {
+                    data.extend(uleb((method as u32).min(1)));
+                    data.extend(uleb(1));
+                    data.extend(uleb(*code_off));
+                }
+                class_defs.extend(words(&[index as u32, 1, object, 0, NO_INDEX, 0, class_data_off as u32, 0]));
+            }
+
+            let type_ids: Vec<u32> = (0..strings.len() as u32).collect();
+            let body = [words(&string_offsets), words(&type_ids), class_defs, data].concat();
+            let mut header = vec![0u8; HEADER_SIZE];
+            header[..8].copy_from_slice(b"dex\n035\0");
+            let file_size = (HEADER_SIZE + body.len()) as u32;
+            let fields = [
+                (0x20, file_size),
+                (0x24, HEADER_SIZE as u32),
+                (0x38, strings.len() as u32),
+                (0x3C, string_ids_off as u32),
+                (0x40, strings.len() as u32),
+                (0x44, type_ids_off as u32),
+                (0x60, classes.len() as u32),
+                (0x64, class_defs_off as u32),
+            ];
+            for (offset, value) in fields {
+                header[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            ([header, body].concat(), class_data_offsets)
+        }
+
+        #[test]
+        fn parses_classes_and_sizes() {
+            let (data, _) = dex(&[("com.a.Foo", 2, 1), ("com.b.Bar", 2, 1), ("com.a.Empty", 0, 0)], 3);
+            let file = parse(&data).unwrap();
+            assert_eq!(file.size, data.len() as u64);
+            let names: Vec<&str> = file.classes.iter().map(|class| class.name.as_str()).collect();
+            assert_eq!(names, vec!["com.a.Foo", "com.b.Bar", "com.a.Empty"]);
+            let foo = &file.classes[0];
+            assert_eq!(foo.superclass, "java.lang.Object");
+            assert_eq!((foo.methods, foo.fields, foo.code_units), (2, 1, 6));
+            // class_def，14 字节的 class_data 和两个 16 + 3 * 2 字节的 code_item
+            assert_eq!(foo.size, 32 + 14 + 2 * 22);
+            // 包名不同结构相同时哈希相同
+            assert_eq!(foo.body_hash, file.classes[1].body_hash);
+            assert_ne!(foo.body_hash, file.classes[2].body_hash);
+            assert_eq!(file.class_bytes(), file.classes.iter().map(|class| class.size).sum::<u64>());
+        }
+
+        #[test]
+        fn rejects_non_dex_and_truncated_files() {
+            assert!(matches!(parse(b"PK\x03\x04"), Err(DexError::NotDex)));
+            let (data, _) = dex(&[("com.a.Foo", 2, 1)], 3);
+            for len in 0..data.len() {
+                // 截断的文件可能仍能解析出类名为空的类，只要求不 panic
+                let _ = parse(&data[..len]);
+            }
+            assert!(matches!(parse(&data[..HEADER_SIZE]), Err(DexError::Truncated("class_defs"))));
+        }
+
+        #[test]
+        fn rejects_overflowing_class_data_counts() {
+            let (data, offsets) = dex(&[("com.a.Foo", 1, 1)], 1);
+            // static_fields + instance_fields 超出 u32
+            let mut fields = data.clone();
+            fields.splice(offsets[0]..offsets[0] + 2, [uleb(u32::MAX), uleb(1)].concat());
+            assert!(matches!(parse(&fields), Err(DexError::Invalid("class_data_item"))));
+            // 方法数大于剩余的字节
+            let mut methods = data.clone();
+            methods.splice(offsets[0] + 2..offsets[0] + 4, [uleb(0x1000_0000), uleb(0)].concat());
+            assert!(matches!(parse(&methods), Err(DexError::Invalid("class_data_item"))));
+        }
+
+        #[test]
+        fn rejects_oversized_code_items() {
+            let (mut data, _) = dex(&[("com.a.Foo", 1, 0)], 1);
+            // registers_size 为 1，insns_size 为 1 的 code_item 头
+            let header = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0];
+            let code_off = data.windows(16).position(|window| window == header).unwrap();
+            data[code_off + 12..code_off + 16].copy_from_slice(&u32::MAX.to_le_bytes());
+            assert!(matches!(parse(&data), Err(DexError::Truncated("code_item"))));
+        }
+
+        #[test]
+        fn deeply_nested_static_values_are_ignored() {
+            let (mut data, _) = dex(&[("com.a.Foo", 0, 1)], 0);
+            // 一个元素的数组套一个元素的数组，远超嵌套上限
+            let static_values_off = data.len() as u32;
+            data.push(1);
+            for _ in 0..100_000 {
+                data.extend_from_slice(&[0x1c, 1]);
+            }
+            data.push(0x1e);
+            let class_def = u32::from_le_bytes(data[0x64..0x68].try_into().unwrap()) as usize;
+            data[class_def + 28..class_def + 32].copy_from_slice(&static_values_off.to_le_bytes());
+            let file = parse(&data).unwrap();
+            assert_eq!(file.classes[0].size, 32 + 6);
+        }
+
+        #[test]
+        fn reads_leb128() {
+            assert_eq!(read_uleb128(&[0xe5, 0x8e, 0x26], 0), Some((624485, 3)));
+            assert_eq!(read_sleb128(&[0x7f], 0), Some((-1, 1)));
+            assert_eq!(read_sleb128(&[0x80, 0x7f], 0), Some((-128, 2)));
+            // 没有结束的字节
+            assert_eq!(read_uleb128(&[0x80, 0x80], 0), None);
+            assert_eq!(read_uleb128(&[0xff; 8], 0), None);
+        }
+    }
+}
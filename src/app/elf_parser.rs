@@ -0,0 +1,744 @@
+pub mod elf {
+    use std::collections::HashMap;
+    use std::fmt;
+
+    use crate::app::res_chunk::chunk::{read_u16, read_u32};
+
+    const ELF_MAGIC: &[u8] = b"\x7fELF";
+    const CLASS_32: u8 = 1;
+    const CLASS_64: u8 = 2;
+    const DATA_LSB: u8 = 1;
+
+    const PT_LOAD: u32 = 1;
+    const PT_DYNAMIC: u32 = 2;
+    const PT_NOTE: u32 = 4;
+
+    const DT_NULL: u64 = 0;
+    const DT_NEEDED: u64 = 1;
+    const DT_STRTAB: u64 = 5;
+    const DT_SONAME: u64 = 14;
+
+    const NT_GNU_BUILD_ID: u32 = 3;
+
+    const SHT_SYMTAB: u32 = 2;
+    const SHT_NOBITS: u32 = 8;
+    const SHT_DYNSYM: u32 = 11;
+
+    const STT_OBJECT: u8 = 1;
+    const STT_FUNC: u8 = 2;
+    const STB_LOCAL: u8 = 0;
+    const STV_DEFAULT: u8 = 0;
+
+    /**
+     * ELF 解析错误
+     */
+    #[derive(Debug)]
+    pub enum ElfError {
+        // 不是以 \x7fELF 开头
+        NotElf,
+        // 大端或者未知的位数，Android 上的库都是小端
+        Unsupported(&'static str),
+        // 头部或者表的位置超出文件范围
+        Truncated(&'static str),
+    }
+
+    impl fmt::Display for ElfError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ElfError::NotElf => write!(f, "not an elf file"),
+                ElfError::Unsupported(what) => write!(f, "unsupported elf {}", what),
+                ElfError::Truncated(section) => write!(f, "elf file is truncated at {}", section),
+            }
+        }
+    }
+
+    impl std::error::Error for ElfError {}
+
+    /**
+     * 一个共享库的标识：DT_SONAME，GNU build-id 和依赖的库
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ElfFile {
+        // 32 或者 64
+        pub bits: u8,
+        // e_machine，例如 40 为 ARM，183 为 AArch64
+        pub machine: u16,
+        pub soname: Option<String>,
+        // 十六进制的 build-id
+        pub build_id: Option<String>,
+        // DT_NEEDED
+        pub needed: Vec<String>,
+        // 节头，去掉了节头的库为空
+        pub sections: Vec<ElfSection>,
+        // .symtab 和 .dynsym 中定义的有大小的函数和变量，同名的只保留一个
+        pub symbols: Vec<ElfSymbol>,
+    }
+
+    /**
+     * 一个节，size 为在文件中占用的字节，.bss 之类不占文件空间的为 0
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ElfSection {
+        pub name: String,
+        pub size: u64,
+    }
+
+    /**
+     * 一个符号，exported 为 .dynsym 中默认可见的全局符号
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ElfSymbol {
+        pub name: String,
+        pub size: u64,
+        pub function: bool,
+        pub exported: bool,
+    }
+
+    impl ElfFile {
+        pub fn machine_name(&self) -> String {
+            machine_name(self.machine)
+        }
+
+        /**
+         * 没有 .symtab 和 .debug_ 节的库视为已经 strip
+         */
+        pub fn is_stripped(&self) -> bool {
+            !self
+                .sections
+                .iter()
+                .any(|section| section.name == ".symtab" || section.name.starts_with(".debug_"))
+        }
+
+        pub fn has_symtab(&self) -> bool {
+            self.sections.iter().any(|section| section.name == ".symtab")
+        }
+    }
+
+    /**
+     * e_machine 对应的名称，未知的返回数字
+     */
+    pub fn machine_name(machine: u16) -> String {
+        match machine {
+            3 => "x86".to_string(),
+            8 => "MIPS".to_string(),
+            40 => "ARM".to_string(),
+            62 => "x86_64".to_string(),
+            183 => "AArch64".to_string(),
+            243 => "RISC-V".to_string(),
+            _ => machine.to_string(),
+        }
+    }
+
+    pub fn is_elf(data: &[u8]) -> bool {
+        data.starts_with(ELF_MAGIC)
+    }
+
+    /**
+     * 程序头，只保留用到的字段
+     */
+    struct Segment {
+        kind: u32,
+        offset: u64,
+        vaddr: u64,
+        filesz: u64,
+    }
+
+    /**
+     * 节头，只保留用到的字段
+     */
+    struct SectionHeader {
+        name: u32,
+        kind: u32,
+        offset: u64,
+        size: u64,
+        link: u32,
+        entsize: u64,
+    }
+
+    struct Reader<'a> {
+        data: &'a [u8],
+        is_64: bool,
+    }
+
+    impl Reader<'_> {
+        fn u16(&self, offset: usize) -> Option<u16> {
+            read_u16(self.data, offset)
+        }
+
+        fn u32(&self, offset: usize) -> Option<u32> {
+            read_u32(self.data, offset)
+        }
+
+        // 64 位文件读 8 字节，32 位文件读 4 字节
+        fn word(&self, offset: usize) -> Option<u64> {
+            if self.is_64 {
+                let low = read_u32(self.data, offset)? as u64;
+                let high = read_u32(self.data, offset.checked_add(4)?)? as u64;
+                Some(high << 32 | low)
+            } else {
+                read_u32(self.data, offset).map(|value| value as u64)
+            }
+        }
+
+        fn word_size(&self) -> usize {
+            if self.is_64 {
+                8
+            } else {
+                4
+            }
+        }
+
+        // 基址加偏移，溢出或超出文件的返回 None，之后再加上头部字段的偏移不会溢出
+        fn position(&self, base: u64, offset: u64) -> Option<usize> {
+            let position = usize::try_from(base.checked_add(offset)?).ok()?;
+            (position <= self.data.len()).then_some(position)
+        }
+
+        fn c_string(&self, offset: usize) -> Option<String> {
+            let bytes = self.data.get(offset..)?;
+            let end = bytes.iter().position(|byte| *byte == 0)?;
+            Some(String::from_utf8_lossy(&bytes[..end]).to_string())
+        }
+    }
+
+    /**
+     * 解析 ELF 头，程序头中的 PT_DYNAMIC 和 PT_NOTE
+     * 只依赖程序头，去掉了节头的库也可以解析；节头和符号表是可选的，损坏时忽略
+     */
+    pub fn parse(data: &[u8]) -> Result<ElfFile, ElfError> {
+        if !is_elf(data) {
+            return Err(ElfError::NotElf);
+        }
+        let is_64 = match data.get(4).copied() {
+            Some(CLASS_32) => false,
+            Some(CLASS_64) => true,
+            _ => return Err(ElfError::Unsupported("class")),
+        };
+        if data.get(5).copied() != Some(DATA_LSB) {
+            return Err(ElfError::Unsupported("byte order"));
+        }
+        let reader = Reader { data, is_64 };
+        let truncated = || ElfError::Truncated("header");
+        let machine = reader.u16(18).ok_or_else(truncated)?;
+        // e_phoff，e_phentsize，e_phnum 在 32 位和 64 位中的位置不同
+        let (phoff, phentsize, phnum) = if is_64 {
+            (reader.word(32), reader.u16(54), reader.u16(56))
+        } else {
+            (reader.word(28), reader.u16(42), reader.u16(44))
+        };
+        let phoff = phoff.ok_or_else(truncated)?;
+        let phentsize = phentsize.ok_or_else(truncated)? as u64;
+        let phnum = phnum.ok_or_else(truncated)? as usize;
+
+        let mut segments = Vec::with_capacity(phnum);
+        for i in 0..phnum {
+            let segment = reader
+                .position(phoff, i as u64 * phentsize)
+                .and_then(|offset| read_segment(&reader, offset))
+                .ok_or(ElfError::Truncated("program headers"))?;
+            segments.push(segment);
+        }
+
+        let mut elf = ElfFile {
+            bits: if is_64 { 64 } else { 32 },
+            machine,
+            ..ElfFile::default()
+        };
+        for segment in segments.iter().filter(|segment| segment.kind == PT_NOTE) {
+            if let Some(build_id) = read_build_id(&reader, segment) {
+                elf.build_id = Some(build_id);
+                break;
+            }
+        }
+        if let Some(dynamic) = segments.iter().find(|segment| segment.kind == PT_DYNAMIC) {
+            read_dynamic(&reader, dynamic, &segments, &mut elf)?;
+        }
+        if let Some(headers) = read_section_headers(&reader) {
+            read_sections(&reader, &headers, &mut elf);
+        }
+        Ok(elf)
+    }
+
+    fn read_section_headers(reader: &Reader) -> Option<Vec<SectionHeader>> {
+        // e_shoff，e_shentsize，e_shnum 在 32 位和 64 位中的位置不同
+        let (shoff, shentsize, shnum) = if reader.is_64 {
+            (reader.word(40)?, reader.u16(58)?, reader.u16(60)?)
+        } else {
+            (reader.word(32)?, reader.u16(46)?, reader.u16(48)?)
+        };
+        if shoff == 0 || shnum == 0 {
+            return None;
+        }
+        let mut headers = Vec::with_capacity(shnum as usize);
+        for i in 0..shnum as u64 {
+            let offset = reader.position(shoff, i * shentsize as u64)?;
+            let header = if reader.is_64 {
+                SectionHeader {
+                    name: reader.u32(offset)?,
+                    kind: reader.u32(offset + 4)?,
+                    offset: reader.word(offset + 24)?,
+                    size: reader.word(offset + 32)?,
+                    link: reader.u32(offset + 40)?,
+                    entsize: reader.word(offset + 56)?,
+                }
+            } else {
+                SectionHeader {
+                    name: reader.u32(offset)?,
+                    kind: reader.u32(offset + 4)?,
+                    offset: reader.word(offset + 16)?,
+                    size: reader.word(offset + 20)?,
+                    link: reader.u32(offset + 24)?,
+                    entsize: reader.word(offset + 36)?,
+                }
+            };
+            headers.push(header);
+        }
+        Some(headers)
+    }
+
+    /**
+     * 节名来自 e_shstrndx 指向的字符串表，符号名来自符号表 sh_link 指向的字符串表
+     */
+    fn read_sections(reader: &Reader, headers: &[SectionHeader], elf: &mut ElfFile) {
+        let shstrndx = if reader.is_64 { reader.u16(62) } else { reader.u16(50) };
+        let names = shstrndx.and_then(|index| headers.get(index as usize));
+        for header in headers.iter().skip(1) {
+            let name = names
+                .and_then(|names| reader.position(names.offset, header.name as u64))
+                .and_then(|offset| reader.c_string(offset))
+                .unwrap_or_default();
+            let size = if header.kind == SHT_NOBITS { 0 } else { header.size };
+            elf.sections.push(ElfSection { name, size });
+        }
+
+        let mut symbols: Vec<ElfSymbol> = Vec::new();
+        let mut seen = HashMap::new();
+        // 先读 .dynsym，导出的符号以它为准
+        for kind in [SHT_DYNSYM, SHT_SYMTAB] {
+            for header in headers.iter().filter(|header| header.kind == kind) {
+                let Some(strings) = headers.get(header.link as usize) else {
+                    continue;
+                };
+                for symbol in read_symbols(reader, header, strings, kind == SHT_DYNSYM) {
+                    match seen.get(&symbol.name) {
+                        Some(index) => {
+                            let known: &mut ElfSymbol = &mut symbols[*index];
+                            known.size = known.size.max(symbol.size);
+                        }
+                        None => {
+                            seen.insert(symbol.name.clone(), symbols.len());
+                            symbols.push(symbol);
+                        }
+                    }
+                }
+            }
+        }
+        elf.symbols = symbols;
+    }
+
+    fn read_symbols(reader: &Reader, header: &SectionHeader, strings: &SectionHeader, dynamic: bool) -> Vec<ElfSymbol> {
+        let entry_size = if reader.is_64 { 24 } else { 16 };
+        let entry_size = (header.entsize as usize).max(entry_size);
+        let count = header.size / entry_size as u64;
+        let mut symbols = Vec::new();
+        for i in 0..count {
+            let Some(offset) = reader.position(header.offset, i * entry_size as u64) else {
+                break;
+            };
+            // st_name，st_info，st_other，st_shndx，st_size 在 32 位和 64 位中的位置不同
+            let fields = if reader.is_64 {
+                (
+                    reader.u32(offset),
+                    reader.data.get(offset + 4).copied(),
+                    reader.data.get(offset + 5).copied(),
+                    reader.u16(offset + 6),
+                    reader.word(offset + 16),
+                )
+            } else {
+                (
+                    reader.u32(offset),
+                    reader.data.get(offset + 12).copied(),
+                    reader.data.get(offset + 13).copied(),
+                    reader.u16(offset + 14),
+                    reader.u32(offset + 8).map(|size| size as u64),
+                )
+            };
+            let (Some(name), Some(info), Some(other), Some(shndx), Some(size)) = fields else {
+                break;
+            };
+            let kind = info & 0xf;
+            if shndx == 0 || size == 0 || (kind != STT_FUNC && kind != STT_OBJECT) {
+                continue;
+            }
+            let Some(name) = reader
+                .position(strings.offset, name as u64)
+                .and_then(|offset| reader.c_string(offset))
+            else {
+                continue;
+            };
+            symbols.push(ElfSymbol {
+                name,
+                size,
+                function: kind == STT_FUNC,
+                exported: dynamic && info >> 4 != STB_LOCAL && other & 0x3 == STV_DEFAULT,
+            });
+        }
+        symbols
+    }
+
+    fn read_segment(reader: &Reader, offset: usize) -> Option<Segment> {
+        if reader.is_64 {
+            Some(Segment {
+                kind: reader.u32(offset)?,
+                offset: reader.word(offset + 8)?,
+                vaddr: reader.word(offset + 16)?,
+                filesz: reader.word(offset + 32)?,
+            })
+        } else {
+            Some(Segment {
+                kind: reader.u32(offset)?,
+                offset: reader.word(offset + 4)?,
+                vaddr: reader.word(offset + 8)?,
+                filesz: reader.word(offset + 16)?,
+            })
+        }
+    }
+
+    /**
+     * 遍历 note，找到 GNU 的 NT_GNU_BUILD_ID
+     */
+    fn read_build_id(reader: &Reader, segment: &Segment) -> Option<String> {
+        let align = |value: usize| (value + 3) & !3;
+        let mut position = reader.position(segment.offset, 0)?;
+        let end = segment.offset.checked_add(segment.filesz)?.min(reader.data.len() as u64) as usize;
+        while position + 12 <= end {
+            let name_size = reader.u32(position)? as usize;
+            let desc_size = reader.u32(position + 4)? as usize;
+            let kind = reader.u32(position + 8)?;
+            let name_start = position + 12;
+            let desc_start = name_start + align(name_size);
+            if kind == NT_GNU_BUILD_ID && reader.data.get(name_start..name_start + name_size)? == b"GNU\0" {
+                let desc = reader.data.get(desc_start..desc_start + desc_size)?;
+                return Some(desc.iter().map(|byte| format!("{:02x}", byte)).collect());
+            }
+            position = desc_start + align(desc_size);
+        }
+        None
+    }
+
+    /**
+     * 读取动态段中的 DT_SONAME 和 DT_NEEDED，字符串表的地址为虚拟地址，需要按 PT_LOAD 换算成文件偏移
+     */
+    fn read_dynamic(reader: &Reader, dynamic: &Segment, segments: &[Segment], elf: &mut ElfFile) -> Result<(), ElfError> {
+        let entry_size = reader.word_size() * 2;
+        let mut strtab = None;
+        let mut soname = None;
+        let mut needed = Vec::new();
+        let truncated = || ElfError::Truncated("dynamic");
+        let mut position = reader.position(dynamic.offset, 0).ok_or_else(truncated)?;
+        let end = dynamic.offset.checked_add(dynamic.filesz).ok_or_else(truncated)?;
+        while (position + entry_size) as u64 <= end {
+            let tag = reader.word(position).ok_or_else(truncated)?;
+            let value = reader.word(position + reader.word_size()).ok_or_else(truncated)?;
+            match tag {
+                DT_NULL => break,
+                DT_NEEDED => needed.push(value),
+                DT_STRTAB => strtab = Some(value),
+                DT_SONAME => soname = Some(value),
+                _ => {}
+            }
+            position += entry_size;
+        }
+        let Some(strtab) = strtab.and_then(|vaddr| file_offset(segments, vaddr)) else {
+            return Ok(());
+        };
+        let string = |offset: u64| reader.position(strtab, offset).and_then(|offset| reader.c_string(offset));
+        elf.soname = soname.and_then(string);
+        elf.needed = needed.into_iter().filter_map(string).collect();
+        Ok(())
+    }
+
+    fn file_offset(segments: &[Segment], vaddr: u64) -> Option<u64> {
+        segments
+            .iter()
+            .filter(|segment| segment.kind == PT_LOAD)
+            .find(|segment| {
+                vaddr >= segment.vaddr && segment.vaddr.checked_add(segment.filesz).is_some_and(|end| vaddr < end)
+            })
+            .and_then(|segment| (vaddr - segment.vaddr).checked_add(segment.offset))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{
+            parse, ElfError, NT_GNU_BUILD_ID, PT_DYNAMIC, PT_LOAD, PT_NOTE, SHT_DYNSYM, SHT_NOBITS, SHT_SYMTAB,
+            STT_FUNC, STT_OBJECT,
+        };
+
+        const PROGRAM_HEADERS: usize = 64;
+        const NOTE: usize = PROGRAM_HEADERS + 3 * 56;
+        const DYNSTR: usize = NOTE + 20;
+        const DYNAMIC: usize = 280;
+        const SHT_STRTAB: u32 = 3;
+        const STB_GLOBAL: u8 = 1;
+
+        fn put_u16(data: &mut [u8], offset: usize, value: u16) {
+            data[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+        }
+
+        fn put_u32(data: &mut [u8], offset: usize, value: u32) {
+            data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        }
+
+        fn put_u64(data: &mut [u8], offset: usize, value: u64) {
+            data[offset..offset + 8].copy_from_slice(&value.to_le_bytes());
+        }
+
+        /**
+         * 只有程序头的 64 位 AArch64 共享库：PT_LOAD 从文件开头映射，
+         * PT_NOTE 中是 GNU build-id deadbeef，PT_DYNAMIC 中是 DT_SONAME libfoo.so 和 DT_NEEDED liblog.so
+         */
+        fn elf64() -> Vec<u8> {
+            let mut data = vec![0u8; DYNAMIC + 4 * 16];
+            data[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+            put_u16(&mut data, 16, 3);
+            put_u16(&mut data, 18, 183);
+            put_u64(&mut data, 32, PROGRAM_HEADERS as u64);
+            put_u16(&mut data, 52, 64);
+            put_u16(&mut data, 54, 56);
+            put_u16(&mut data, 56, 3);
+            let segments = [
+                (PT_LOAD, 0, 0x1000),
+                (PT_NOTE, NOTE as u64, 20),
+                (PT_DYNAMIC, DYNAMIC as u64, 4 * 16),
+            ];
+            for (index, (kind, offset, size)) in segments.into_iter().enumerate() {
+                let header = PROGRAM_HEADERS + index * 56;
+                put_u32(&mut data, header, kind);
+                put_u64(&mut data, header + 8, offset);
+                put_u64(&mut data, header + 16, offset);
+                put_u64(&mut data, header + 32, size);
+            }
+            put_u32(&mut data, NOTE, 4);
+            put_u32(&mut data, NOTE + 4, 4);
+            put_u32(&mut data, NOTE + 8, NT_GNU_BUILD_ID);
+            data[NOTE + 12..NOTE + 20].copy_from_slice(b"GNU\0\xde\xad\xbe\xef");
+            data[DYNSTR..DYNSTR + 21].copy_from_slice(b"\0libfoo.so\0liblog.so\0");
+            // DT_STRTAB，DT_SONAME，DT_NEEDED，DT_NULL
+            let dynamic = [(5, DYNSTR as u64), (14, 1), (1, 11), (0, 0)];
+            for (index, (tag, value)) in dynamic.into_iter().enumerate() {
+                put_u64(&mut data, DYNAMIC + index * 16, tag);
+                put_u64(&mut data, DYNAMIC + index * 16 + 8, value);
+            }
+            data
+        }
+
+        /**
+         * 在 elf64 后面加上符号表和节头
+         * .dynsym 中导出 foo_init，.symtab 中还有局部变量 table 和没有大小的 empty，.bss 不占文件空间
+         */
+        fn with_sections(mut data: Vec<u8>) -> Vec<u8> {
+            let strtab = data.len();
+            data.extend_from_slice(b"\0foo_init\0table\0empty\0");
+            let shstrtab = data.len();
+            data.extend_from_slice(b"\0.dynsym\0.symtab\0.strtab\0.shstrtab\0.bss\0");
+            while !data.len().is_multiple_of(8) {
+                data.push(0);
+            }
+            // (st_name，st_info，st_size)
+            let symbol = |data: &mut Vec<u8>, name: u32, info: u8, size: u64| {
+                let start = data.len();
+                data.resize(start + 24, 0);
+                put_u32(data, start, name);
+                data[start + 4] = info;
+                put_u16(data, start + 6, 1);
+                put_u64(data, start + 16, size);
+            };
+            let dynsym = data.len();
+            symbol(&mut data, 0, 0, 0);
+            symbol(&mut data, 1, STB_GLOBAL << 4 | STT_FUNC, 16);
+            let symtab = data.len();
+            symbol(&mut data, 0, 0, 0);
+            symbol(&mut data, 1, STB_GLOBAL << 4 | STT_FUNC, 16);
+            symbol(&mut data, 10, STT_OBJECT, 64);
+            symbol(&mut data, 16, STT_OBJECT, 0);
+
+            // (sh_name，sh_type，sh_offset，sh_size，sh_link，sh_entsize)
+            let sections = [
+                (0, 0, 0, 0, 0, 0),
+                (1, SHT_DYNSYM, dynsym, 48, 3, 24),
+                (9, SHT_SYMTAB, symtab, 96, 3, 24),
+                (17, SHT_STRTAB, strtab, 23, 0, 0),
+                (25, SHT_STRTAB, shstrtab, 40, 0, 0),
+                (35, SHT_NOBITS, 0, 4096, 0, 0),
+            ];
+            let shoff = data.len();
+            for (name, kind, offset, size, link, entsize) in sections {
+                let start = data.len();
+                data.resize(start + 64, 0);
+                put_u32(&mut data, start, name);
+                put_u32(&mut data, start + 4, kind);
+                put_u64(&mut data, start + 24, offset as u64);
+                put_u64(&mut data, start + 32, size);
+                put_u32(&mut data, start + 40, link);
+                put_u64(&mut data, start + 56, entsize);
+            }
+            put_u64(&mut data, 40, shoff as u64);
+            put_u16(&mut data, 58, 64);
+            put_u16(&mut data, 60, sections.len() as u16);
+            put_u16(&mut data, 62, 4);
+            data
+        }
+
+        #[test]
+        fn parses_dynamic_and_build_id() {
+            let elf = parse(&elf64()).unwrap();
+            assert_eq!(elf.bits, 64);
+            assert_eq!(elf.machine_name(), "AArch64");
+            assert_eq!(elf.soname.as_deref(), Some("libfoo.so"));
+            assert_eq!(elf.needed, vec!["liblog.so"]);
+            assert_eq!(elf.build_id.as_deref(), Some("deadbeef"));
+            // 没有节头时视为已经 strip
+            assert!(elf.sections.is_empty());
+            assert!(elf.is_stripped());
+        }
+
+        #[test]
+        fn rejects_other_files() {
+            assert!(matches!(parse(b"PK\x03\x04"), Err(ElfError::NotElf)));
+            let mut class = elf64();
+            class[4] = 3;
+            assert!(matches!(parse(&class), Err(ElfError::Unsupported("class"))));
+            let mut big_endian = elf64();
+            big_endian[5] = 2;
+            assert!(matches!(parse(&big_endian), Err(ElfError::Unsupported("byte order"))));
+        }
+
+        #[test]
+        fn truncated_file_does_not_panic() {
+            let data = elf64();
+            for len in 0..data.len() {
+                let _ = parse(&data[..len]);
+            }
+            assert!(matches!(parse(&data[..20]), Err(ElfError::Truncated("header"))));
+            assert!(matches!(parse(&data[..PROGRAM_HEADERS + 56]), Err(ElfError::Truncated("program headers"))));
+            assert!(matches!(parse(&data[..DYNAMIC + 8]), Err(ElfError::Truncated("dynamic"))));
+        }
+
+        #[test]
+        fn corrupt_notes_have_no_build_id() {
+            for field in [NOTE, NOTE + 4] {
+                let mut data = elf64();
+                put_u32(&mut data, field, u32::MAX);
+                let elf = parse(&data).unwrap();
+                assert_eq!(elf.build_id, None);
+                assert_eq!(elf.soname.as_deref(), Some("libfoo.so"));
+            }
+            // 不是 GNU 的 note
+            let mut vendor = elf64();
+            vendor[NOTE + 12] = b'X';
+            assert_eq!(parse(&vendor).unwrap().build_id, None);
+        }
+
+        #[test]
+        fn dynamic_strings_outside_the_file_are_dropped() {
+            let mut data = elf64();
+            put_u64(&mut data, DYNAMIC + 16 + 8, 0x7fff_ffff);
+            let elf = parse(&data).unwrap();
+            assert_eq!(elf.soname, None);
+            assert_eq!(elf.needed, vec!["liblog.so"]);
+        }
+
+        #[test]
+        fn parses_sections_and_symbols() {
+            let elf = parse(&with_sections(elf64())).unwrap();
+            let sections: Vec<(&str, u64)> = elf.sections.iter().map(|section| (section.name.as_str(), section.size)).collect();
+            assert_eq!(
+                sections,
+                vec![(".dynsym", 48), (".symtab", 96), (".strtab", 23), (".shstrtab", 40), (".bss", 0)]
+            );
+            assert!(elf.has_symtab());
+            assert!(!elf.is_stripped());
+            let symbols: Vec<(&str, u64, bool, bool)> = elf
+                .symbols
+                .iter()
+                .map(|symbol| (symbol.name.as_str(), symbol.size, symbol.function, symbol.exported))
+                .collect();
+            assert_eq!(symbols, vec![("foo_init", 16, true, true), ("table", 64, false, false)]);
+        }
+
+        #[test]
+        fn rejects_overflowing_header_offsets() {
+            let mut phoff = elf64();
+            put_u64(&mut phoff, 32, u64::MAX - 8);
+            assert!(matches!(parse(&phoff), Err(ElfError::Truncated("program headers"))));
+            // 第二个程序头的偏移溢出
+            let mut phentsize = elf64();
+            put_u64(&mut phentsize, 32, u64::MAX - 56);
+            assert!(matches!(parse(&phentsize), Err(ElfError::Truncated("program headers"))));
+            // PT_DYNAMIC 的偏移和大小溢出
+            for (field, value) in [(8, u64::MAX - 4), (32, u64::MAX)] {
+                let mut dynamic = elf64();
+                put_u64(&mut dynamic, PROGRAM_HEADERS + 2 * 56 + field, value);
+                assert!(matches!(parse(&dynamic), Err(ElfError::Truncated("dynamic"))));
+            }
+        }
+
+        #[test]
+        fn overflowing_segment_offsets_are_ignored() {
+            // PT_NOTE 的偏移和大小溢出
+            for (field, value) in [(8, u64::MAX - 4), (32, u64::MAX)] {
+                let mut note = elf64();
+                put_u64(&mut note, PROGRAM_HEADERS + 56 + field, value);
+                let elf = parse(&note).unwrap();
+                assert_eq!(elf.build_id, None);
+                assert_eq!(elf.soname.as_deref(), Some("libfoo.so"));
+            }
+            // PT_LOAD 的地址加大小溢出，字符串表的地址找不到对应的文件偏移
+            let mut load = elf64();
+            put_u64(&mut load, PROGRAM_HEADERS + 16, u64::MAX - 4);
+            let elf = parse(&load).unwrap();
+            assert_eq!(elf.soname, None);
+            assert!(elf.needed.is_empty());
+            // DT_STRTAB 加字符串偏移溢出
+            let mut strtab = elf64();
+            put_u64(&mut strtab, DYNAMIC + 16 + 8, u64::MAX);
+            assert_eq!(parse(&strtab).unwrap().soname, None);
+        }
+
+        #[test]
+        fn overflowing_section_offsets_are_ignored() {
+            let data = with_sections(elf64());
+            let shoff = u64::from_le_bytes(data[40..48].try_into().unwrap()) as usize;
+            // e_shoff 溢出时没有节和符号
+            let mut headers = data.clone();
+            put_u64(&mut headers, 40, u64::MAX - 8);
+            let elf = parse(&headers).unwrap();
+            assert!(elf.sections.is_empty() && elf.symbols.is_empty());
+            assert_eq!(elf.build_id.as_deref(), Some("deadbeef"));
+            // .symtab 的偏移溢出，只剩 .dynsym 中的符号
+            let mut symtab = data.clone();
+            put_u64(&mut symtab, shoff + 2 * 64 + 24, u64::MAX - 8);
+            let names: Vec<String> = parse(&symtab).unwrap().symbols.into_iter().map(|symbol| symbol.name).collect();
+            assert_eq!(names, vec!["foo_init"]);
+            // 节名偏移溢出时节名为空
+            let mut name = data.clone();
+            put_u32(&mut name, shoff + 64, u32::MAX);
+            let elf = parse(&name).unwrap();
+            assert_eq!(elf.sections[0].name, "");
+            // .strtab 的偏移溢出，所有符号都没有名称
+            let mut strings = data.clone();
+            put_u64(&mut strings, shoff + 3 * 64 + 24, u64::MAX);
+            assert!(parse(&strings).unwrap().symbols.is_empty());
+        }
+
+        #[test]
+        fn truncated_sections_do_not_panic() {
+            let data = with_sections(elf64());
+            for len in DYNAMIC + 64..data.len() {
+                let elf = parse(&data[..len]).unwrap();
+                assert_eq!(elf.soname.as_deref(), Some("libfoo.so"));
+            }
+        }
+    }
+}
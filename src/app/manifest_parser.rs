@@ -1,104 +1,184 @@
 pub mod parser {
-    use xml::reader::XmlEvent;
-    use xml::EventReader;
     use std::io::Read;
     use base64::{engine::general_purpose::STANDARD, Engine as _};
 
+    use crate::app::arsc_parser::arsc::{self, ResourceTable};
+    use crate::app::apk_info::{ApkParsedInfo, ManifestComponent};
     use crate::app::android_xml::axml;
-    use crate::app::apk_info::ApkParsedInfo;
+    use crate::app::axml_walker::walker::{XmlElement, ANDROID_NAMESPACE};
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::apk_source::source::ApkSource;
 
-    pub async fn parse(path: &str) -> Option<ApkParsedInfo> {
-        let file = std::fs::File::open(path).unwrap();
-        let mut file_content: Vec<u8> = Vec::new();
+    pub async fn parse(source: &ApkSource) -> Result<ApkParsedInfo, ApkError> {
+        read(source)
+    }
+
+    /**
+     * 读取 apk 或 aar 中的清单文件和图标
+     */
+    pub fn read(source: &ApkSource) -> Result<ApkParsedInfo, ApkError> {
+        let file = source.open()?;
+        let mut archive = zip::ZipArchive::new(file)?;
         let mut icon = String::new();
-        let mut archive = zip::ZipArchive::new(file).unwrap();
 
-        for i in 0..archive.len() {
-            let mut in_file = archive.by_index(i).unwrap();
-            if in_file.name() == "res/drawable-xxxhdpi-v4/ic_launcher.png" {
-                let mut f_content: Vec<u8> = Vec::new();
-                in_file.read_to_end(&mut f_content).unwrap();
-                icon =  STANDARD.encode(&f_content);
-                break;
-            }
+        if let Ok(mut in_file) = archive.by_name("res/drawable-xxxhdpi-v4/ic_launcher.png") {
+            let mut f_content: Vec<u8> = Vec::new();
+            in_file.read_to_end(&mut f_content)?;
+            icon = STANDARD.encode(&f_content);
         }
-        for i in 0..archive.len() {
-            let mut inner_file = archive.by_index(i).unwrap();
-            if inner_file.name() == "AndroidManifest.xml" {
-                inner_file.read_to_end(&mut file_content).unwrap();
-                break;
+
+        let mut file_content: Vec<u8> = Vec::new();
+        match archive.by_name("AndroidManifest.xml") {
+            Ok(mut inner_file) => {
+                inner_file.read_to_end(&mut file_content)?;
             }
+            Err(zip::result::ZipError::FileNotFound) => return Err(ApkError::MissingManifest),
+            Err(err) => return Err(err.into()),
         }
 
-        let xml = axml::extract_xml(file_content);
+        // 属性中的资源引用通过 resources.arsc 还原成名称，aar 中没有
+        let resources = match archive.by_name("resources.arsc") {
+            Ok(mut inner_file) => {
+                let mut arsc_content: Vec<u8> = Vec::new();
+                inner_file.read_to_end(&mut arsc_content)?;
+                arsc::parse(&arsc_content)
+            }
+            Err(_) => None,
+        };
 
-        parse_to_info(xml, icon)
+        parse_manifest(&file_content, icon, resources.as_ref())
+    }
 
+    /**
+     * apk 中是二进制 xml，aar 中是文本 xml
+     */
+    fn parse_manifest(content: &[u8], icon: String, resources: Option<&ResourceTable>) -> Result<ApkParsedInfo, ApkError> {
+        let document = if axml::is_text_xml(content) {
+            axml::parse_text_document(content)?
+        } else {
+            axml::parse_document(content)?
+        };
+        Ok(element_to_info(&document.root, icon, resources))
     }
 
-    fn parse_to_info(content: String, icon: String) -> Option<ApkParsedInfo> {
+    /**
+     * 从清单文件的元素树中读取信息
+     * 系统属性按 android 命名空间 + 属性名精确匹配，package 没有命名空间
+     */
+    fn element_to_info(root: &XmlElement, icon: String, resources: Option<&ResourceTable>) -> ApkParsedInfo {
         let mut apk_info = ApkParsedInfo::new();
-
         apk_info.icon = icon;
 
-        let reader = EventReader::from_str(content.as_str());
-        for e in reader {
-            match e {
-                Ok(XmlEvent::StartElement {
-                    name,
-                    attributes,
-                    namespace: _,
-                }) => match name.local_name.as_str() {
-                    "manifest" => {
-                        for attribute in attributes {
-                            let attr = attribute.name.to_string();
-
-                            if attr.contains("versionCode") {
-                                apk_info.version_code = attribute.value;
-                            } else if attr.contains("versionName") {
-                                apk_info.version_name = attribute.value;
-                            } else if attr.contains("compileSdkVersionCodename") {
-                                apk_info.compile_sdk_version_code_name = attribute.value;
-                            } else if attr.contains("compileSdkVersion") {
-                                apk_info.compile_sdk_version = attribute.value;
-                            } else if attr.contains("package") {
-                                apk_info.package_name = attribute.value;
-                            }
-                        }
+        let android = |element: &XmlElement, name: &str| {
+            element
+                .attribute(ANDROID_NAMESPACE, name)
+                .map(|attribute| attribute.format_value(resources))
+                .unwrap_or_default()
+        };
+
+        apk_info.package_name = root
+            .attribute("", "package")
+            .map(|attribute| attribute.format_value(resources))
+            .unwrap_or_default();
+        apk_info.version_code = android(root, "versionCode");
+        apk_info.version_name = android(root, "versionName");
+        apk_info.compile_sdk_version = android(root, "compileSdkVersion");
+        apk_info.compile_sdk_version_code_name = android(root, "compileSdkVersionCodename");
+
+        collect_attributes(&mut apk_info, root, resources);
+        for child in &root.children {
+            match child.tag.as_str() {
+                "uses-sdk" => {
+                    apk_info.min_sdk_version = android(child, "minSdkVersion");
+                    apk_info.target_sdk_version = android(child, "targetSdkVersion");
+                    collect_attributes(&mut apk_info, child, resources);
+                }
+                "uses-permission" | "uses-permission-sdk-23" => {
+                    if let Some(name) = child.attribute(ANDROID_NAMESPACE, "name") {
+                        apk_info.permissions.push(name.format_value(resources));
                     }
-
-                    "uses-sdk" => {
-                        for attribute in attributes {
-                            let attr = attribute.name.to_string();
-
-                            if attr.contains("minSdkVersion") {
-                                apk_info.min_sdk_version = attribute.value;
-                            } else if attr.contains("targetSdkVersion") {
-                                apk_info.target_sdk_version = attribute.value;
-                            }
-                        }
+                }
+                "uses-feature" => {
+                    // glEsVersion 形式的特性没有 name
+                    let name = match child.attribute(ANDROID_NAMESPACE, "name") {
+                        Some(name) => name.format_value(resources),
+                        None => match child.attribute(ANDROID_NAMESPACE, "glEsVersion") {
+                            Some(version) => format!("glEsVersion {}", version.format_value(resources)),
+                            None => continue,
+                        },
+                    };
+                    let required = android(child, "required");
+                    if required == "false" {
+                        apk_info.features.push(format!("{} (optional)", name));
+                    } else {
+                        apk_info.features.push(name);
                     }
-
-                    "uses-permission" => {
-                        for attribute in attributes {
-                            if attribute.name.to_string().contains("name") {
-                                apk_info.permissions.push(attribute.value)
-                            }
+                }
+                "application" => {
+                    collect_attributes(&mut apk_info, child, resources);
+                    for component in &child.children {
+                        if let Some(component) = read_component(component, &apk_info.package_name, resources) {
+                            apk_info.components.push(component);
                         }
                     }
-
-                    _ => {}
-                },
-
-                Err(_) => {
-                    return None;
                 }
-
                 _ => {}
             }
         }
 
+        apk_info
+    }
+
+    /**
+     * 元素上 android 命名空间的属性，application 的 name 等类名不补全包名
+     * 引用资源的属性同时记录默认配置下的值
+     */
+    fn collect_attributes(apk_info: &mut ApkParsedInfo, element: &XmlElement, resources: Option<&ResourceTable>) {
+        for attribute in &element.attributes {
+            if attribute.namespace != ANDROID_NAMESPACE {
+                continue;
+            }
+            let name = format!("{}:{}", element.tag, attribute.name);
+            let value = attribute.format_value(resources);
+            let resolved = attribute.resolved_value(resources);
+            if resolved != value {
+                apk_info.resolved_attributes.insert(name.clone(), resolved);
+            }
+            apk_info.attributes.insert(name, value);
+        }
+    }
 
-        Some(apk_info)
+    fn read_component(element: &XmlElement, package_name: &str, resources: Option<&ResourceTable>) -> Option<ManifestComponent> {
+        if !matches!(
+            element.tag.as_str(),
+            "activity" | "activity-alias" | "service" | "receiver" | "provider"
+        ) {
+            return None;
+        }
+        let name = element.attribute(ANDROID_NAMESPACE, "name")?.format_value(resources);
+        let name = if name.starts_with('.') {
+            format!("{}{}", package_name, name)
+        } else if !name.contains('.') && !package_name.is_empty() {
+            format!("{}.{}", package_name, name)
+        } else {
+            name
+        };
+        // 按资源的值判断，解析不出值的资源引用可能在某些配置下为 true，按导出处理
+        let exported = match element.attribute(ANDROID_NAMESPACE, "exported") {
+            Some(exported) => {
+                let exported = exported.resolved_value(resources);
+                exported == "true" || exported.starts_with('@')
+            }
+            None => element.children.iter().any(|child| child.tag == "intent-filter"),
+        };
+        Some(ManifestComponent {
+            kind: element.tag.clone(),
+            name,
+            exported,
+            permission: element
+                .attribute(ANDROID_NAMESPACE, "permission")
+                .map(|permission| permission.resolved_value(resources))
+                .unwrap_or_default(),
+        })
     }
 }
@@ -1,3 +1,10 @@
+pub mod android_attrs;
 pub mod android_xml;
 pub mod apk_info;
 pub mod manifest_parser;
+pub mod res_chunk;
+pub mod arsc_parser;
+pub mod axml_walker;
+pub mod dex_parser;
+pub mod proguard_mapping;
+pub mod elf_parser;
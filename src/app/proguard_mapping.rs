@@ -0,0 +1,44 @@
+pub mod mapping {
+    use std::collections::HashMap;
+
+    /**
+     * proguard / r8 的 mapping.txt，只保存类名的映射
+     * 类的行为 `com.example.Foo -> a.b:`，成员的行缩进，注释以 # 开头
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ProguardMapping {
+        // 混淆后的类名 -> 原始类名
+        classes: HashMap<String, String>,
+    }
+
+    impl ProguardMapping {
+        pub fn parse(text: &str) -> ProguardMapping {
+            let classes = text
+                .lines()
+                .filter(|line| !line.starts_with([' ', '\t', '#']))
+                .filter_map(|line| line.trim_end().strip_suffix(':'))
+                .filter_map(|line| line.split_once(" -> "))
+                .map(|(original, obfuscated)| (obfuscated.trim().to_string(), original.trim().to_string()))
+                .collect();
+            ProguardMapping { classes }
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.classes.is_empty()
+        }
+
+        /**
+         * 原始类名，没有映射时返回 None
+         */
+        pub fn original(&self, obfuscated: &str) -> Option<&str> {
+            self.classes.get(obfuscated).map(|name| name.as_str())
+        }
+
+        /**
+         * 原始类名，没有映射时返回原名
+         */
+        pub fn deobfuscate<'a>(&'a self, name: &'a str) -> &'a str {
+            self.original(name).unwrap_or(name)
+        }
+    }
+}
@@ -0,0 +1,256 @@
+pub mod chunk {
+    pub const RES_STRING_POOL_TYPE: u16 = 0x0001;
+    pub const RES_TABLE_TYPE: u16 = 0x0002;
+    pub const RES_XML_TYPE: u16 = 0x0003;
+    pub const RES_XML_START_NAMESPACE_TYPE: u16 = 0x0100;
+    pub const RES_XML_END_NAMESPACE_TYPE: u16 = 0x0101;
+    pub const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+    pub const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+    pub const RES_XML_CDATA_TYPE: u16 = 0x0104;
+    pub const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+    pub const RES_TABLE_PACKAGE_TYPE: u16 = 0x0200;
+    pub const RES_TABLE_TYPE_TYPE: u16 = 0x0201;
+
+    const UTF8_FLAG: u32 = 1 << 8;
+
+    /**
+     * 资源文件中的 chunk 头
+     * 所有二进制 xml 和 resources.arsc 都由 chunk 组成
+     */
+    #[derive(Debug, Clone, Copy)]
+    pub struct ChunkHeader {
+        pub offset: usize,
+        pub chunk_type: u16,
+        pub header_size: u16,
+        pub size: u32,
+    }
+
+    impl ChunkHeader {
+        /**
+         * 读取 offset 位置的 chunk 头，越界或者大小不合法时返回 None
+         */
+        pub fn read(data: &[u8], offset: usize) -> Option<ChunkHeader> {
+            let chunk_type = read_u16(data, offset)?;
+            let header_size = read_u16(data, offset + 2)?;
+            let size = read_u32(data, offset + 4)?;
+            if (header_size as u32) < 8 || size < header_size as u32 {
+                return None;
+            }
+            Some(ChunkHeader {
+                offset,
+                chunk_type,
+                header_size,
+                size,
+            })
+        }
+
+        pub fn body_start(&self) -> usize {
+            self.offset + self.header_size as usize
+        }
+
+        pub fn end(&self) -> usize {
+            self.offset + self.size as usize
+        }
+    }
+
+    /**
+     * 字符串池
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct StringPool {
+        pub strings: Vec<String>,
+    }
+
+    impl StringPool {
+        /**
+         * 解析字符串池，单个字符串解析失败时使用空字符串代替
+         */
+        pub fn read(data: &[u8], header: &ChunkHeader) -> Option<StringPool> {
+            let start = header.offset;
+            let string_count = read_u32(data, start + 8)? as usize;
+            let flags = read_u32(data, start + 16)?;
+            let strings_start = read_u32(data, start + 20)? as usize;
+            let utf8 = flags & UTF8_FLAG != 0;
+            let end = header.end().min(data.len());
+
+            // 每个偏移 4 字节，数量不合理时按 chunk 大小截断
+            let offsets_start = header.body_start();
+            let string_count = string_count.min(end.saturating_sub(offsets_start) / 4);
+            let mut strings = Vec::with_capacity(string_count);
+            for i in 0..string_count {
+                let offset = read_u32(data, offsets_start + i * 4)? as usize;
+                let position = start + strings_start + offset;
+                let value = if position < end {
+                    if utf8 {
+                        read_utf8_string(&data[..end], position)
+                    } else {
+                        read_utf16_string(&data[..end], position)
+                    }
+                } else {
+                    None
+                };
+                strings.push(value.unwrap_or_default());
+            }
+
+            Some(StringPool { strings })
+        }
+
+        pub fn get(&self, index: u32) -> Option<&str> {
+            self.strings.get(index as usize).map(|value| value.as_str())
+        }
+    }
+
+    fn read_utf8_string(data: &[u8], offset: usize) -> Option<String> {
+        // 先是字符数，再是字节数，长度大于 0x7f 时占两个字节
+        let (_, offset) = read_utf8_length(data, offset)?;
+        let (len, offset) = read_utf8_length(data, offset)?;
+        let bytes = data.get(offset..offset + len)?;
+        Some(String::from_utf8_lossy(bytes).to_string())
+    }
+
+    fn read_utf8_length(data: &[u8], offset: usize) -> Option<(usize, usize)> {
+        let first = *data.get(offset)? as usize;
+        if first & 0x80 != 0 {
+            let second = *data.get(offset + 1)? as usize;
+            Some((((first & 0x7f) << 8) | second, offset + 2))
+        } else {
+            Some((first, offset + 1))
+        }
+    }
+
+    fn read_utf16_string(data: &[u8], offset: usize) -> Option<String> {
+        // 长度大于 0x7fff 时占两个 u16
+        let first = read_u16(data, offset)? as usize;
+        let (len, offset) = if first & 0x8000 != 0 {
+            let second = read_u16(data, offset + 2)? as usize;
+            (((first & 0x7fff) << 16) | second, offset + 4)
+        } else {
+            (first, offset + 2)
+        };
+        let bytes = data.get(offset..offset + len * 2)?;
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        Some(String::from_utf16_lossy(&units))
+    }
+
+    pub fn read_u8(data: &[u8], offset: usize) -> Option<u8> {
+        data.get(offset).copied()
+    }
+
+    pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+        let bytes = data.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+        let bytes = data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{ChunkHeader, StringPool, RES_STRING_POOL_TYPE, UTF8_FLAG};
+
+        fn chunk(chunk_type: u16, header: &[u8], body: &[u8]) -> Vec<u8> {
+            let header_size = 8 + header.len();
+            let mut data = Vec::new();
+            data.extend_from_slice(&chunk_type.to_le_bytes());
+            data.extend_from_slice(&(header_size as u16).to_le_bytes());
+            data.extend_from_slice(&((header_size + body.len()) as u32).to_le_bytes());
+            data.extend_from_slice(header);
+            data.extend_from_slice(body);
+            data
+        }
+
+        // 字符串池，utf8 为 false 时使用 utf16
+        fn string_pool(strings: &[&str], utf8: bool) -> Vec<u8> {
+            let mut offsets = Vec::new();
+            let mut bytes = Vec::new();
+            for value in strings {
+                offsets.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+                if utf8 {
+                    bytes.push(value.chars().count() as u8);
+                    bytes.push(value.len() as u8);
+                    bytes.extend_from_slice(value.as_bytes());
+                    bytes.push(0);
+                } else {
+                    let units: Vec<u16> = value.encode_utf16().collect();
+                    bytes.extend_from_slice(&(units.len() as u16).to_le_bytes());
+                    units.iter().for_each(|unit| bytes.extend_from_slice(&unit.to_le_bytes()));
+                    bytes.extend_from_slice(&[0, 0]);
+                }
+            }
+            let flags = if utf8 { UTF8_FLAG } else { 0 };
+            let strings_start = 28 + offsets.len() as u32;
+            let header: Vec<u8> = [strings.len() as u32, 0, flags, strings_start, 0]
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect();
+            chunk(RES_STRING_POOL_TYPE, &header, &[offsets, bytes].concat())
+        }
+
+        fn read_pool(data: &[u8]) -> Option<StringPool> {
+            StringPool::read(data, &ChunkHeader::read(data, 0)?)
+        }
+
+        #[test]
+        fn reads_utf8_and_utf16_pools() {
+            for utf8 in [true, false] {
+                let data = string_pool(&["app_name", "名称", ""], utf8);
+                let pool = read_pool(&data).unwrap();
+                assert_eq!(pool.strings, vec!["app_name", "名称", ""]);
+                assert_eq!(pool.get(1), Some("名称"));
+                assert_eq!(pool.get(3), None);
+            }
+        }
+
+        #[test]
+        fn rejects_invalid_chunk_headers() {
+            let data = string_pool(&["a"], true);
+            assert!(ChunkHeader::read(&data[..7], 0).is_none());
+            assert!(ChunkHeader::read(&data, data.len()).is_none());
+            // header_size 小于 8
+            let mut small = data.clone();
+            small[2] = 4;
+            assert!(ChunkHeader::read(&small, 0).is_none());
+            // size 小于 header_size
+            let mut short = data.clone();
+            short[4..8].copy_from_slice(&4u32.to_le_bytes());
+            assert!(ChunkHeader::read(&short, 0).is_none());
+        }
+
+        #[test]
+        fn bogus_string_count_is_capped_by_the_chunk() {
+            let mut data = string_pool(&["a", "b"], true);
+            data[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+            let pool = read_pool(&data).unwrap();
+            assert!(pool.strings.len() < 16);
+            assert_eq!(pool.get(0), Some("a"));
+        }
+
+        #[test]
+        fn strings_past_the_end_are_empty() {
+            for utf8 in [true, false] {
+                let mut data = string_pool(&["abc"], utf8);
+                // 偏移指向 chunk 之外
+                data[28..32].copy_from_slice(&0x7fff_0000u32.to_le_bytes());
+                assert_eq!(read_pool(&data).unwrap().strings, vec![""]);
+                // 长度超出 chunk
+                let mut data = string_pool(&["abc"], utf8);
+                data[32] = 0x7f;
+                data[33] = 0x7f;
+                assert_eq!(read_pool(&data).unwrap().strings, vec![""]);
+            }
+        }
+
+        #[test]
+        fn truncated_pool_does_not_panic() {
+            let data = string_pool(&["app_name", "名称"], false);
+            for len in 0..data.len() {
+                let _ = read_pool(&data[..len]);
+            }
+        }
+    }
+}
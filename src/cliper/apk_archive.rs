@@ -0,0 +1,655 @@
+pub mod archive {
+    use std::collections::{HashMap, HashSet};
+    use std::io::Read;
+
+    use serde::Serialize;
+    use zip::ZipArchive;
+
+    use crate::app::apk_info::ApkParsedInfo;
+    use crate::app::arsc_parser::arsc::{self, ResourceTable};
+    use crate::app::dex_parser::dex;
+    use crate::app::elf_parser::elf;
+    use crate::app::proguard_mapping::mapping::ProguardMapping;
+    use crate::app::manifest_parser::parser;
+    use crate::cliper::apk_cliper::size_reader::{self, Entries, PackageKind};
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::apk_source::source::ApkSource;
+    use crate::cliper::cliper_info::CliperInfo;
+    use crate::cliper::size_data::SizeData;
+
+    /**
+     * 一个 apk 文件，所有分析都从这里开始
+     * 只负责计算，结果可以输出成表格，csv 或者 json
+     */
+    #[derive(Debug, Clone)]
+    pub struct ApkArchive {
+        source: ApkSource,
+    }
+
+    /**
+     * md5 相同的一组文件
+     */
+    #[derive(Debug, Clone, Serialize)]
+    pub struct DuplicateGroup {
+        pub md5: String,
+        pub files: Vec<CliperInfo>,
+        // 所有文件的原始大小之和
+        pub size: u64,
+    }
+
+    /**
+     * 一个类的大小
+     * dex 中的类按自身字节占 dex 的比例分摊 dex 的原始大小和下载大小，共享的表也分摊在内，
+     * aar 和 jar 中的类就是 class 文件的大小
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct ClassSize {
+        // 有 mapping.txt 时为原始类名
+        pub name: String,
+        // 混淆后的类名，没有混淆时与 name 相同
+        pub obfuscated_name: String,
+        // 所在的 dex 或者 jar
+        pub container: String,
+        pub size: u64,
+        pub download: u64,
+        // 类自身在 dex 中的字节数，不随同一个 dex 中其他类的变化而变化，class 文件为文件大小
+        pub own_size: u64,
+        pub methods: u32,
+        pub fields: u32,
+        // dex 中类的结构哈希，见 DexClass::body_hash，class 文件为 0
+        pub body_hash: u64,
+        pub code_units: u32,
+    }
+
+    /**
+     * 重复的类：同一个类名定义在多个 dex 中，或者不同类名的类结构完全相同
+     */
+    #[derive(Debug, Clone, Serialize)]
+    pub struct DuplicateClassGroup {
+        pub kind: DuplicateClassKind,
+        pub classes: Vec<ClassSize>,
+        // 保留一份后可以节省的下载大小
+        pub wasted: u64,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub enum DuplicateClassKind {
+        // 类名相同
+        Descriptor,
+        // 类名不同，字段，方法和指令结构相同
+        Body,
+    }
+
+    /**
+     * 压缩包中的一个 ELF 共享库，按文件头识别，不要求在 lib/ 下或者以 .so 结尾
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct NativeLibrary {
+        pub path: String,
+        // lib/<abi>/ 下的库为目录名，其它位置为空
+        pub abi: String,
+        pub machine: String,
+        pub soname: String,
+        pub build_id: String,
+        pub size: u64,
+        pub download: u64,
+        pub md5: String,
+    }
+
+    impl NativeLibrary {
+        /**
+         * 两个库能否互相替代：lib/ 下不同 abi 目录的库各自加载，其余按 e_machine 判断
+         */
+        fn interchangeable(&self, other: &NativeLibrary) -> bool {
+            if !self.abi.is_empty() && !other.abi.is_empty() {
+                return self.abi == other.abi;
+            }
+            self.machine == other.machine
+        }
+    }
+
+    /**
+     * 按内容，DT_SONAME 或者 build-id 连在一起的一组库
+     * 只有内容或者 build-id 相同的副本可以删除，只有 DT_SONAME 相同的可能是不同的版本，需要人工确认
+     */
+    #[derive(Debug, Clone, Serialize)]
+    pub struct DuplicateNativeGroup {
+        // 匹配的依据，例如 [Content, SoName]
+        pub matches: Vec<NativeMatch>,
+        pub libraries: Vec<NativeLibrary>,
+        // 与 libraries 一一对应
+        pub actions: Vec<NativeAction>,
+        // 删除 Drop 的副本后可以节省的下载大小
+        pub wasted: u64,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+    pub enum NativeMatch {
+        // 字节完全相同
+        Content,
+        // DT_SONAME 相同
+        SoName,
+        // GNU build-id 相同
+        BuildId,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub enum NativeAction {
+        // 保留的库
+        Keep,
+        // 与保留的库内容或者 build-id 相同，可以删除
+        Drop,
+        // 与保留的库只有 DT_SONAME 相同，同名的不同版本会互相冲突，需要确认后处理
+        Review,
+    }
+
+    /**
+     * 两个 apk 的差异，diff 字段为新文件减去旧文件的下载大小
+     */
+    #[derive(Debug, Serialize)]
+    pub struct ApkDiff {
+        pub added: Vec<CliperInfo>,
+        pub removed: Vec<CliperInfo>,
+        pub updated: Vec<CliperInfo>,
+        pub old_summary: SizeData,
+        pub new_summary: SizeData,
+    }
+
+    impl ApkArchive {
+        /**
+         * 打开本地的 apk，文件不存在或者不是 zip 时返回错误
+         */
+        pub fn open(path: &str) -> Result<ApkArchive, ApkError> {
+            ApkArchive::from_source(ApkSource::File(path.to_string()))
+        }
+
+        /**
+         * 从任意来源打开 apk，例如内存，标准输入或者 http 地址
+         */
+        pub fn from_source(source: ApkSource) -> Result<ApkArchive, ApkError> {
+            ZipArchive::new(source.open()?)?;
+            Ok(ApkArchive { source })
+        }
+
+        pub fn source(&self) -> &ApkSource {
+            &self.source
+        }
+
+        /**
+         * 压缩包的类型：apk，aar 或者 jar
+         */
+        pub fn kind(&self) -> Result<PackageKind, ApkError> {
+            Ok(PackageKind::of(&ZipArchive::new(self.source.open()?)?))
+        }
+
+        /**
+         * class 级别的大小
+         * aar 读取 classes.jar 和 libs/ 下的 jar，路径为 classes.jar!/com/example/Foo.class，
+         * jar 直接读取其中的 class，apk 返回空列表
+         */
+        pub fn class_entries(&self) -> Result<Vec<CliperInfo>, ApkError> {
+            let archive = ZipArchive::new(self.source.open()?)?;
+            match PackageKind::of(&archive) {
+                PackageKind::Apk => Ok(Vec::new()),
+                PackageKind::Jar => Ok(self
+                    .entries()?
+                    .filter(|entry| entry.as_ref().map_or(true, is_class))
+                    .collect::<Result<Vec<_>, _>>()?),
+                PackageKind::Aar => {
+                    let jars: Vec<String> = archive
+                        .file_names()
+                        .filter(|name| *name == "classes.jar" || (name.starts_with("libs/") && name.ends_with(".jar")))
+                        .map(|name| name.to_string())
+                        .collect();
+                    let mut classes = Vec::new();
+                    for jar in jars {
+                        let source = ApkSource::nested(self.source.clone(), &jar)?;
+                        for entry in size_reader::entries(&source, false)? {
+                            let mut entry = entry?;
+                            if !is_class(&entry) {
+                                continue;
+                            }
+                            entry.file_path = format!("{}!/{}", jar, entry.file_path);
+                            classes.push(entry);
+                        }
+                    }
+                    Ok(classes)
+                }
+            }
+        }
+
+        /**
+         * 逐个读取文件信息
+         */
+        pub fn entries(&self) -> Result<Entries, ApkError> {
+            size_reader::entries(&self.source, false)
+        }
+
+        /**
+         * 逐个读取文件信息并计算 md5，空文件会被跳过
+         */
+        pub fn entries_with_md5(&self) -> Result<Entries, ApkError> {
+            size_reader::entries(&self.source, true)
+        }
+
+        /**
+         * 所有类的大小，apk 解析 dex，aar 和 jar 读取 class 文件
+         * 同一个压缩包或者目录下有 mapping.txt 时类名还原为混淆前的名称
+         */
+        pub fn classes(&self) -> Result<Vec<ClassSize>, ApkError> {
+            self.classes_with_mapping(None)
+        }
+
+        /**
+         * 同 classes，mapping 不为空时使用指定的 mapping.txt，不再查找同目录下的
+         */
+        pub fn classes_with_mapping(&self, mapping: Option<&ApkSource>) -> Result<Vec<ClassSize>, ApkError> {
+            let mapping = match mapping {
+                Some(mapping) => read_mapping_file(mapping)?,
+                None => read_mapping(&self.source)?,
+            };
+            let mut classes = Vec::new();
+            if self.kind()? != PackageKind::Apk {
+                for entry in self.class_entries()? {
+                    let obfuscated_name = class_file_name(&entry.file_path);
+                    let container = entry.file_path.split_once("!/").map(|(jar, _)| jar.to_string());
+                    classes.push(ClassSize {
+                        name: mapping.deobfuscate(&obfuscated_name).to_string(),
+                        obfuscated_name,
+                        container: container.unwrap_or_else(|| self.source.name()),
+                        size: entry.size,
+                        download: entry.download,
+                        own_size: entry.size,
+                        ..ClassSize::default()
+                    });
+                }
+                return Ok(classes);
+            }
+            size_reader::visit_detail_info_with_content(
+                &self.source,
+                |info| info.file_ext == ".dex",
+                |info, content| {
+                    let dex_file = dex::parse(&content)?;
+                    let class_bytes = dex_file.class_bytes().max(1);
+                    for class in dex_file.classes {
+                        classes.push(ClassSize {
+                            name: mapping.deobfuscate(&class.name).to_string(),
+                            container: info.file_path.clone(),
+                            size: info.size * class.size / class_bytes,
+                            download: info.download * class.size / class_bytes,
+                            own_size: class.size,
+                            methods: class.methods,
+                            fields: class.fields,
+                            body_hash: class.body_hash,
+                            code_units: class.code_units,
+                            obfuscated_name: class.name,
+                        });
+                    }
+                    Ok(())
+                },
+            )?;
+            Ok(classes)
+        }
+
+        /**
+         * 重复的类，按浪费的下载大小从大到小排序
+         * 同名的类只有第一个 dex 中的会被加载；结构相同的类只比较指令不少于 min_code_units 的，
+         * 避免空构造函数之类的小类被误判
+         */
+        pub fn duplicate_classes(&self, min_code_units: u32) -> Result<Vec<DuplicateClassGroup>, ApkError> {
+            let classes = self.classes()?;
+            Ok(group_duplicate_classes(classes, min_code_units))
+        }
+
+        /**
+         * 所有 ELF 共享库，包括放在 assets/ 下或者改了扩展名的
+         */
+        pub fn native_libraries(&self) -> Result<Vec<NativeLibrary>, ApkError> {
+            let libraries = self.parsed_native_libraries()?;
+            Ok(libraries.into_iter().map(|(library, _)| library).collect())
+        }
+
+        /**
+         * 同 native_libraries，同时返回解析出的节和符号
+         */
+        pub fn parsed_native_libraries(&self) -> Result<Vec<(NativeLibrary, elf::ElfFile)>, ApkError> {
+            let mut libraries = Vec::new();
+            size_reader::visit_detail_info_with_content(
+                &self.source,
+                |info| !is_never_native(info),
+                |info, content| {
+                    if !elf::is_elf(&content) {
+                        return Ok(());
+                    }
+                    // 无法解析的库仍然可以按内容比较
+                    let parsed = elf::parse(&content).unwrap_or_default();
+                    let library = NativeLibrary {
+                        abi: lib_abi(&info.file_path).unwrap_or_default().to_string(),
+                        machine: if parsed.machine == 0 { String::new() } else { parsed.machine_name() },
+                        soname: parsed.soname.clone().unwrap_or_default(),
+                        build_id: parsed.build_id.clone().unwrap_or_default(),
+                        size: info.size,
+                        download: info.download,
+                        md5: format!("{:x}", md5::compute(&content)),
+                        path: info.file_path,
+                    };
+                    libraries.push((library, parsed));
+                    Ok(())
+                },
+            )?;
+            Ok(libraries)
+        }
+
+        /**
+         * 重复的共享库，按浪费的下载大小从大到小排序
+         */
+        pub fn duplicate_native_libraries(&self) -> Result<Vec<DuplicateNativeGroup>, ApkError> {
+            Ok(group_duplicate_native_libraries(self.native_libraries()?))
+        }
+
+        /**
+         * 按分类汇总的下载大小和压缩包结构开销
+         */
+        pub fn summary(&self) -> Result<SizeData, ApkError> {
+            size_reader::read_size(&self.source)
+        }
+
+        /**
+         * 内容相同的文件，按总大小从大到小排序
+         */
+        pub fn duplicates(&self) -> Result<Vec<DuplicateGroup>, ApkError> {
+            let entries = self.entries_with_md5()?.collect::<Result<Vec<_>, _>>()?;
+            Ok(group_duplicates(entries))
+        }
+
+        /**
+         * 解析根目录下的 resources.arsc，aar 和 jar 中没有时返回 None
+         */
+        pub fn resources(&self) -> Result<Option<ResourceTable>, ApkError> {
+            let mut table = None;
+            size_reader::visit_detail_info_with_content(
+                &self.source,
+                |info| info.file_path == "resources.arsc",
+                |_, content| {
+                    table = arsc::parse(&content);
+                    Ok(())
+                },
+            )?;
+            Ok(table)
+        }
+
+        /**
+         * 清单文件中的包名，版本号，sdk 版本和权限
+         */
+        pub fn manifest(&self) -> Result<ApkParsedInfo, ApkError> {
+            parser::read(&self.source)
+        }
+    }
+
+    /**
+     * 把类按类名和结构哈希分组，只保留有重复的组
+     */
+    pub fn group_duplicate_classes(classes: Vec<ClassSize>, min_code_units: u32) -> Vec<DuplicateClassGroup> {
+        let mut by_name: HashMap<&str, Vec<&ClassSize>> = HashMap::new();
+        let mut by_body: HashMap<u64, Vec<&ClassSize>> = HashMap::new();
+        for class in &classes {
+            by_name.entry(class.obfuscated_name.as_str()).or_default().push(class);
+            if class.body_hash != 0 && class.code_units >= min_code_units {
+                by_body.entry(class.body_hash).or_default().push(class);
+            }
+        }
+        let group = |kind, classes: Vec<&ClassSize>| {
+            let classes: Vec<ClassSize> = classes.into_iter().cloned().collect();
+            // 保留第一个，其余都是浪费
+            let wasted = classes.iter().skip(1).map(|class| class.download).sum();
+            DuplicateClassGroup { kind, classes, wasted }
+        };
+
+        let mut groups: Vec<DuplicateClassGroup> = by_name
+            .into_values()
+            .filter(|classes| classes.len() > 1)
+            .map(|classes| group(DuplicateClassKind::Descriptor, classes))
+            .collect();
+        for classes in by_body.into_values() {
+            // 同名的副本已经在上面统计过，每个类名只保留一个
+            let mut names = HashSet::new();
+            let classes: Vec<&ClassSize> = classes
+                .into_iter()
+                .filter(|class| names.insert(class.obfuscated_name.as_str()))
+                .collect();
+            if classes.len() > 1 {
+                groups.push(group(DuplicateClassKind::Body, classes));
+            }
+        }
+        groups.sort_by(|a, b| b.wasted.cmp(&a.wasted).then(a.classes[0].name.cmp(&b.classes[0].name)));
+        groups
+    }
+
+    /**
+     * 把共享库按内容，DT_SONAME 和 build-id 连成组，只保留有可删除或者需要确认的副本的组
+     * 每组优先保留 lib/ 下的库，其次是下载大小更小的
+     * 与已保留的库可以互相替代并且内容或者 build-id 相同的副本可以删除，只有 DT_SONAME 相同的需要确认
+     */
+    pub fn group_duplicate_native_libraries(libraries: Vec<NativeLibrary>) -> Vec<DuplicateNativeGroup> {
+        let mut parents: Vec<usize> = (0..libraries.len()).collect();
+        let mut matches: Vec<Vec<NativeMatch>> = vec![Vec::new(); libraries.len()];
+        // 内容或者 build-id 相同的库，(i, j) 中 i < j
+        let mut identical: HashSet<(usize, usize)> = HashSet::new();
+        for i in 0..libraries.len() {
+            for j in i + 1..libraries.len() {
+                let (a, b) = (&libraries[i], &libraries[j]);
+                if !a.interchangeable(b) {
+                    continue;
+                }
+                let mut found = Vec::new();
+                if a.md5 == b.md5 {
+                    found.push(NativeMatch::Content);
+                }
+                if !a.soname.is_empty() && a.soname == b.soname {
+                    found.push(NativeMatch::SoName);
+                }
+                if !a.build_id.is_empty() && a.build_id == b.build_id {
+                    found.push(NativeMatch::BuildId);
+                }
+                if found.is_empty() {
+                    continue;
+                }
+                if found.iter().any(|kind| *kind != NativeMatch::SoName) {
+                    identical.insert((i, j));
+                }
+                let (root_a, root_b) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                parents[root_b] = root_a;
+                matches[i].extend(found);
+            }
+        }
+
+        let mut components: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..libraries.len() {
+            let root = find_root(&mut parents, i);
+            components.entry(root).or_default().push(i);
+        }
+        let mut groups = Vec::new();
+        for mut members in components.into_values().filter(|members| members.len() > 1) {
+            members.sort_by(|a, b| {
+                let (a, b) = (&libraries[*a], &libraries[*b]);
+                a.abi
+                    .is_empty()
+                    .cmp(&b.abi.is_empty())
+                    .then(a.download.cmp(&b.download))
+                    .then(a.path.cmp(&b.path))
+            });
+            let mut kept: Vec<usize> = Vec::new();
+            let mut actions = Vec::new();
+            for index in &members {
+                let library = &libraries[*index];
+                let same = |keep: &usize| {
+                    let pair = if *keep < *index { (*keep, *index) } else { (*index, *keep) };
+                    libraries[*keep].interchangeable(library) && identical.contains(&pair)
+                };
+                let action = if kept.iter().any(same) {
+                    NativeAction::Drop
+                } else if kept.iter().any(|keep| libraries[*keep].interchangeable(library)) {
+                    NativeAction::Review
+                } else {
+                    NativeAction::Keep
+                };
+                // 需要确认的库仍然在包中，后面与它相同的副本也可以删除
+                if action != NativeAction::Drop {
+                    kept.push(*index);
+                }
+                actions.push(action);
+            }
+            if actions.iter().all(|action| *action == NativeAction::Keep) {
+                continue;
+            }
+            let mut found: Vec<NativeMatch> = Vec::new();
+            for kind in [NativeMatch::Content, NativeMatch::SoName, NativeMatch::BuildId] {
+                if members.iter().any(|index| matches[*index].contains(&kind)) {
+                    found.push(kind);
+                }
+            }
+            let libraries: Vec<NativeLibrary> = members.iter().map(|index| libraries[*index].clone()).collect();
+            let wasted = libraries
+                .iter()
+                .zip(&actions)
+                .filter(|(_, action)| **action == NativeAction::Drop)
+                .map(|(library, _)| library.download)
+                .sum();
+            groups.push(DuplicateNativeGroup {
+                matches: found,
+                libraries,
+                actions,
+                wasted,
+            });
+        }
+        groups.sort_by(|a, b| b.wasted.cmp(&a.wasted).then(a.libraries[0].path.cmp(&b.libraries[0].path)));
+        groups
+    }
+
+    fn find_root(parents: &mut [usize], index: usize) -> usize {
+        let mut root = index;
+        while parents[root] != root {
+            root = parents[root];
+        }
+        parents[index] = root;
+        root
+    }
+
+    /**
+     * lib/arm64-v8a/libfoo.so 返回 arm64-v8a
+     */
+    fn lib_abi(path: &str) -> Option<&str> {
+        let rest = path.strip_prefix("lib/")?;
+        let (abi, name) = rest.split_once('/')?;
+        (!name.is_empty()).then_some(abi)
+    }
+
+    /**
+     * 一定不是共享库的文件，跳过以免解压
+     */
+    fn is_never_native(info: &CliperInfo) -> bool {
+        matches!(
+            info.file_ext.as_str(),
+            ".dex" | ".arsc" | ".xml" | ".png" | ".webp" | ".jpg" | ".class" | ".kotlin_builtins"
+        ) || info.file_path.starts_with("META-INF/")
+    }
+
+    fn read_mapping(source: &ApkSource) -> Result<ProguardMapping, ApkError> {
+        let Some(mapping) = source.mapping()? else {
+            return Ok(ProguardMapping::default());
+        };
+        read_mapping_file(&mapping)
+    }
+
+    fn read_mapping_file(mapping: &ApkSource) -> Result<ProguardMapping, ApkError> {
+        let mut text = String::new();
+        mapping.open()?.read_to_string(&mut text)?;
+        Ok(ProguardMapping::parse(&text))
+    }
+
+    /**
+     * com/example/Foo.class 转换成 com.example.Foo
+     */
+    fn class_file_name(path: &str) -> String {
+        let path = path.rsplit("!/").next().unwrap_or(path);
+        path.strip_suffix(".class").unwrap_or(path).replace('/', ".")
+    }
+
+    fn is_class(entry: &CliperInfo) -> bool {
+        entry.file_ext == ".class"
+    }
+
+    /**
+     * 把带 md5 的文件信息按 md5 分组，只保留有重复的组
+     */
+    pub fn group_duplicates(entries: Vec<CliperInfo>) -> Vec<DuplicateGroup> {
+        let mut md5_map: HashMap<String, Vec<CliperInfo>> = HashMap::new();
+        for cliper_info in entries {
+            md5_map.entry(cliper_info.md5.clone()).or_default().push(cliper_info);
+        }
+        let mut groups: Vec<DuplicateGroup> = md5_map
+            .into_iter()
+            // 过滤 md5 值只有一个文件的情况
+            .filter(|(_, files)| files.len() > 1)
+            .map(|(md5, files)| DuplicateGroup {
+                md5,
+                size: files.iter().map(|file| file.size).sum(),
+                files,
+            })
+            .collect();
+        groups.sort_by_key(|b| std::cmp::Reverse(b.size));
+        groups
+    }
+
+    /**
+     * 对比两个 apk，old 为旧的文件，new 为新的文件
+     * 文件按下载大小从大到小排序
+     */
+    pub fn diff(old: &ApkArchive, new: &ApkArchive) -> Result<ApkDiff, ApkError> {
+        let mut old_files = old.entries()?.collect::<Result<Vec<_>, _>>()?;
+        let mut new_files = new.entries()?.collect::<Result<Vec<_>, _>>()?;
+        old_files.sort_by_key(|b| std::cmp::Reverse(b.download));
+        new_files.sort_by_key(|b| std::cmp::Reverse(b.download));
+
+        let old_map: HashMap<&str, &CliperInfo> = old_files
+            .iter()
+            .map(|file| (file.file_path.as_str(), file))
+            .collect();
+        let new_paths: HashSet<&str> = new_files.iter().map(|file| file.file_path.as_str()).collect();
+
+        let mut added = Vec::new();
+        let mut updated = Vec::new();
+        for file in &new_files {
+            match old_map.get(file.file_path.as_str()) {
+                None => {
+                    let mut result = file.clone();
+                    result.diff = file.download as i64;
+                    added.push(result);
+                }
+                Some(old_file) if old_file.download != file.download => {
+                    let mut result = file.clone();
+                    result.diff = file.download as i64 - old_file.download as i64;
+                    updated.push(result);
+                }
+                Some(_) => {}
+            }
+        }
+        let removed = old_files
+            .iter()
+            .filter(|file| !new_paths.contains(file.file_path.as_str()))
+            .map(|file| {
+                let mut result = file.clone();
+                result.diff = -(file.download as i64);
+                result
+            })
+            .collect();
+
+        Ok(ApkDiff {
+            added,
+            removed,
+            updated,
+            old_summary: old.summary()?,
+            new_summary: new.summary()?,
+        })
+    }
+}
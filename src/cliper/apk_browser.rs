@@ -0,0 +1,688 @@
+pub mod browser {
+    use std::cmp::Ordering;
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::path::Path;
+
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::style::{Color, Modifier, Style};
+    use ratatui::text::{Line, Span};
+    use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState, Wrap};
+    use ratatui::{DefaultTerminal, Frame};
+    use regex::Regex;
+
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::cliper_info::CliperInfo;
+    use crate::cliper::size_data::SizeData;
+
+    // 翻页时移动的行数
+    const PAGE: usize = 20;
+    // 占比条的宽度
+    const BAR_WIDTH: usize = 10;
+
+    /**
+     * browse 的参数
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct BrowseOptions {
+        // 显示在标题中的文件名
+        pub title: String,
+        // 初始的过滤条件，格式见 Query::parse
+        pub query: String,
+        // 按 e 导出当前视图的 csv 路径
+        pub csv_path: String,
+    }
+
+    /**
+     * 过滤条件，与 detail 的 --filter-* 含义相同
+     */
+    #[derive(Debug, Default)]
+    pub struct Query {
+        path: Option<String>,
+        ext: Option<String>,
+        file_type: Option<String>,
+        regex: Option<Regex>,
+        size: u64,
+        // 不带前缀的词，忽略大小写匹配路径中的任意位置
+        words: Vec<String>,
+    }
+
+    impl Query {
+        /**
+         * 空格分隔的条件：path:前缀 ext:后缀 type:类型 re:正则 size:最小下载字节，其余的词匹配路径中的任意位置
+         * 正则中不能包含空格
+         */
+        pub fn parse(text: &str) -> Result<Query, String> {
+            let mut query = Query::default();
+            for word in text.split_whitespace() {
+                if let Some(path) = word.strip_prefix("path:") {
+                    query.path = Some(path.to_string());
+                } else if let Some(ext) = word.strip_prefix("ext:") {
+                    query.ext = Some(ext.to_string());
+                } else if let Some(file_type) = word.strip_prefix("type:") {
+                    query.file_type = Some(file_type.to_string());
+                } else if let Some(pattern) = word.strip_prefix("re:") {
+                    let regex = Regex::new(pattern).map_err(|e| format!("re:{}: {}", pattern, e))?;
+                    query.regex = Some(regex);
+                } else if let Some(size) = word.strip_prefix("size:") {
+                    query.size = size.parse().map_err(|_| format!("size:{} is not a number", size))?;
+                } else {
+                    query.words.push(word.to_lowercase());
+                }
+            }
+            Ok(query)
+        }
+
+        /**
+         * 由 browse 的 --filter-* 参数生成条件文本，空的参数不生成
+         */
+        pub fn text(path: &str, ext: &str, file_type: &str, regex: &str, size: u64) -> String {
+            let mut words = Vec::new();
+            for (prefix, value) in [("path:", path), ("ext:", ext), ("type:", file_type), ("re:", regex)] {
+                if !value.is_empty() {
+                    words.push(format!("{}{}", prefix, value));
+                }
+            }
+            if size > 0 {
+                words.push(format!("size:{}", size));
+            }
+            words.join(" ")
+        }
+
+        pub fn matches(&self, info: &CliperInfo) -> bool {
+            let path = info.file_path.as_str();
+            let lower = path.to_lowercase();
+            self.path.as_ref().is_none_or(|prefix| path.starts_with(prefix.as_str()))
+                && self.ext.as_ref().is_none_or(|ext| path.ends_with(ext.as_str()))
+                && self
+                    .file_type
+                    .as_ref()
+                    .is_none_or(|file_type| info.file_type.eq_ignore_ascii_case(file_type))
+                && self.regex.as_ref().is_none_or(|regex| regex.is_match(path))
+                && info.download >= self.size
+                && self.words.iter().all(|word| lower.contains(word.as_str()))
+        }
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum SortKey {
+        Download,
+        Size,
+        Ratio,
+        Name,
+    }
+
+    impl SortKey {
+        fn next(self) -> SortKey {
+            match self {
+                SortKey::Download => SortKey::Size,
+                SortKey::Size => SortKey::Ratio,
+                SortKey::Ratio => SortKey::Name,
+                SortKey::Name => SortKey::Download,
+            }
+        }
+
+        fn name(self) -> &'static str {
+            match self {
+                SortKey::Download => "download",
+                SortKey::Size => "size",
+                SortKey::Ratio => "ratio",
+                SortKey::Name => "name",
+            }
+        }
+    }
+
+    /**
+     * 目录树中的一个节点，nodes[0] 为根目录，目录的 path 以 / 结尾
+     */
+    #[derive(Debug, Clone, Default)]
+    struct Node {
+        name: String,
+        path: String,
+        parent: Option<usize>,
+        children: Vec<usize>,
+        // 文件在 entries 中的位置，目录为 None
+        entry: Option<usize>,
+        depth: usize,
+        files: usize,
+        size: u64,
+        download: u64,
+    }
+
+    impl Node {
+        fn is_folder(&self) -> bool {
+            self.entry.is_none()
+        }
+
+        // 下载大小占原始大小的比例，越接近 100% 压缩效果越差
+        fn ratio(&self) -> f64 {
+            if self.size == 0 {
+                return 0.0;
+            }
+            self.download as f64 * 100.0 / self.size as f64
+        }
+    }
+
+    /**
+     * 由满足条件的文件生成目录树，目录的大小为其中所有文件之和
+     */
+    fn build_tree(entries: &[CliperInfo], matched: &[usize]) -> Vec<Node> {
+        let mut nodes = vec![Node::default()];
+        let mut folders: HashMap<String, usize> = HashMap::new();
+        for index in matched {
+            let info = &entries[*index];
+            let parts: Vec<&str> = info.file_path.split('/').filter(|part| !part.is_empty()).collect();
+            let Some((file_name, dirs)) = parts.split_last() else {
+                continue;
+            };
+            let mut parent = 0;
+            let mut path = String::new();
+            for dir in dirs {
+                path.push_str(dir);
+                path.push('/');
+                parent = match folders.get(&path) {
+                    Some(folder) => *folder,
+                    None => {
+                        let folder = nodes.len();
+                        nodes.push(Node {
+                            name: dir.to_string(),
+                            path: path.clone(),
+                            parent: Some(parent),
+                            depth: nodes[parent].depth + 1,
+                            ..Node::default()
+                        });
+                        nodes[parent].children.push(folder);
+                        folders.insert(path.clone(), folder);
+                        folder
+                    }
+                };
+            }
+            let file = nodes.len();
+            nodes.push(Node {
+                name: file_name.to_string(),
+                path: info.file_path.clone(),
+                parent: Some(parent),
+                entry: Some(*index),
+                depth: nodes[parent].depth + 1,
+                files: 1,
+                size: info.size,
+                download: info.download,
+                ..Node::default()
+            });
+            nodes[parent].children.push(file);
+            let mut folder = Some(parent);
+            while let Some(current) = folder {
+                nodes[current].files += 1;
+                nodes[current].size += info.size;
+                nodes[current].download += info.download;
+                folder = nodes[current].parent;
+            }
+        }
+        nodes
+    }
+
+    struct App<'a> {
+        entries: &'a [CliperInfo],
+        // md5 相同的文件
+        duplicates: HashMap<&'a str, Vec<usize>>,
+        nodes: Vec<Node>,
+        // 当前显示的节点，按展开状态和排序方式从树中展开
+        rows: Vec<usize>,
+        expanded: HashSet<String>,
+        state: TableState,
+        sort: SortKey,
+        reverse: bool,
+        query: String,
+        // 正在编辑过滤条件，Esc 恢复为 query_before_edit
+        editing: bool,
+        query_before_edit: String,
+        message: String,
+        options: &'a BrowseOptions,
+        quit: bool,
+    }
+
+    /**
+     * 全屏浏览压缩包中的文件，退出时恢复终端
+     */
+    pub fn run(entries: &[CliperInfo], options: &BrowseOptions) -> Result<(), ApkError> {
+        // 初始条件不合法时不进入界面，与 detail 的 --filter-regex 一致
+        Query::parse(&options.query).map_err(ApkError::InvalidFilter)?;
+        let mut app = App::new(entries, options);
+        let mut terminal = ratatui::try_init()?;
+        let result = app.run(&mut terminal);
+        ratatui::restore();
+        result
+    }
+
+    impl<'a> App<'a> {
+        fn new(entries: &'a [CliperInfo], options: &'a BrowseOptions) -> App<'a> {
+            let mut duplicates: HashMap<&str, Vec<usize>> = HashMap::new();
+            for (index, info) in entries.iter().enumerate() {
+                if !info.md5.is_empty() {
+                    duplicates.entry(info.md5.as_str()).or_default().push(index);
+                }
+            }
+            let mut app = App {
+                entries,
+                duplicates,
+                nodes: Vec::new(),
+                rows: Vec::new(),
+                expanded: HashSet::new(),
+                state: TableState::default().with_selected(Some(0)),
+                sort: SortKey::Download,
+                reverse: false,
+                query: options.query.clone(),
+                editing: false,
+                query_before_edit: String::new(),
+                message: String::new(),
+                options,
+                quit: false,
+            };
+            app.apply_query();
+            app
+        }
+
+        fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), ApkError> {
+            while !self.quit {
+                terminal.draw(|frame| self.draw(frame))?;
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press {
+                        self.handle_key(key);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /**
+         * 按过滤条件重新生成目录树，条件不合法时保留当前的树并显示错误
+         */
+        fn apply_query(&mut self) {
+            let query = match Query::parse(&self.query) {
+                Ok(query) => query,
+                Err(error) => {
+                    self.message = error;
+                    return;
+                }
+            };
+            self.message.clear();
+            let matched: Vec<usize> = (0..self.entries.len())
+                .filter(|index| query.matches(&self.entries[*index]))
+                .collect();
+            let selected = self.selected().map(|node| node.path.clone());
+            self.nodes = build_tree(self.entries, &matched);
+            self.refresh_rows(selected);
+        }
+
+        /**
+         * 按展开状态和排序方式重新生成显示的行，尽量保持选中的节点
+         */
+        fn refresh_rows(&mut self, selected: Option<String>) {
+            let mut rows = Vec::new();
+            self.push_rows(0, &mut rows);
+            self.rows = rows;
+            let index = selected
+                .and_then(|path| self.rows.iter().position(|row| self.nodes[*row].path == path))
+                .or(self.state.selected())
+                .unwrap_or(0);
+            self.state.select(Some(index.min(self.rows.len().saturating_sub(1))));
+        }
+
+        fn push_rows(&self, node: usize, rows: &mut Vec<usize>) {
+            let mut children = self.nodes[node].children.clone();
+            children.sort_by(|a, b| self.compare(&self.nodes[*a], &self.nodes[*b]));
+            for child in children {
+                rows.push(child);
+                if self.nodes[child].is_folder() && self.expanded.contains(&self.nodes[child].path) {
+                    self.push_rows(child, rows);
+                }
+            }
+        }
+
+        // 大小从大到小，名称从小到大，reverse 时反过来
+        fn compare(&self, a: &Node, b: &Node) -> Ordering {
+            let order = match self.sort {
+                SortKey::Download => b.download.cmp(&a.download),
+                SortKey::Size => b.size.cmp(&a.size),
+                SortKey::Ratio => b.ratio().total_cmp(&a.ratio()),
+                SortKey::Name => a.name.cmp(&b.name),
+            }
+            .then(a.name.cmp(&b.name));
+            if self.reverse {
+                order.reverse()
+            } else {
+                order
+            }
+        }
+
+        fn selected(&self) -> Option<&Node> {
+            let row = self.rows.get(self.state.selected()?)?;
+            self.nodes.get(*row)
+        }
+
+        fn handle_key(&mut self, key: KeyEvent) {
+            if self.editing {
+                match key.code {
+                    KeyCode::Enter => self.editing = false,
+                    KeyCode::Esc => {
+                        self.editing = false;
+                        self.query = self.query_before_edit.clone();
+                        self.apply_query();
+                    }
+                    KeyCode::Backspace => {
+                        self.query.pop();
+                        self.apply_query();
+                    }
+                    KeyCode::Char(c) => {
+                        self.query.push(c);
+                        self.apply_query();
+                    }
+                    _ => {}
+                }
+                return;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+                KeyCode::PageDown => self.move_by(PAGE as isize),
+                KeyCode::PageUp => self.move_by(-(PAGE as isize)),
+                KeyCode::Home | KeyCode::Char('g') => self.state.select(Some(0)),
+                KeyCode::End | KeyCode::Char('G') => self.state.select(Some(self.rows.len().saturating_sub(1))),
+                KeyCode::Right | KeyCode::Char('l') => self.expand(),
+                KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+                KeyCode::Enter | KeyCode::Char(' ') => self.toggle(),
+                KeyCode::Char('/') => {
+                    self.editing = true;
+                    self.query_before_edit = self.query.clone();
+                }
+                KeyCode::Char('s') => {
+                    self.sort = self.sort.next();
+                    self.refresh_selected();
+                }
+                KeyCode::Char('r') => {
+                    self.reverse = !self.reverse;
+                    self.refresh_selected();
+                }
+                KeyCode::Char('a') => {
+                    self.expanded = self
+                        .nodes
+                        .iter()
+                        .skip(1)
+                        .filter(|node| node.is_folder())
+                        .map(|node| node.path.clone())
+                        .collect();
+                    self.refresh_selected();
+                }
+                KeyCode::Char('c') => {
+                    self.expanded.clear();
+                    self.refresh_selected();
+                }
+                KeyCode::Char('e') => {
+                    self.message = match self.export() {
+                        Ok(()) => format!("Output: {}", self.options.csv_path),
+                        Err(error) => format!("export failed: {}", error),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        fn move_by(&mut self, offset: isize) {
+            if self.rows.is_empty() {
+                return;
+            }
+            let current = self.state.selected().unwrap_or(0) as isize;
+            let index = (current + offset).clamp(0, self.rows.len() as isize - 1);
+            self.state.select(Some(index as usize));
+        }
+
+        fn refresh_selected(&mut self) {
+            let selected = self.selected().map(|node| node.path.clone());
+            self.refresh_rows(selected);
+        }
+
+        // 展开目录，已经展开时移到第一个子节点
+        fn expand(&mut self) {
+            let Some(node) = self.selected() else {
+                return;
+            };
+            if !node.is_folder() {
+                return;
+            }
+            if self.expanded.insert(node.path.clone()) {
+                self.refresh_selected();
+            } else {
+                self.move_by(1);
+            }
+        }
+
+        // 收起目录，文件或者已经收起的目录移到父目录
+        fn collapse(&mut self) {
+            let Some(node) = self.selected() else {
+                return;
+            };
+            let (path, parent) = (node.path.clone(), node.parent);
+            if node.is_folder() && self.expanded.remove(&path) {
+                self.refresh_selected();
+                return;
+            }
+            if let Some(parent) = parent.filter(|parent| *parent != 0) {
+                let index = self.rows.iter().position(|row| *row == parent);
+                self.state.select(index);
+            }
+        }
+
+        fn toggle(&mut self) {
+            let Some(node) = self.selected() else {
+                return;
+            };
+            if !node.is_folder() {
+                return;
+            }
+            let path = node.path.clone();
+            if !self.expanded.remove(&path) {
+                self.expanded.insert(path);
+            }
+            self.refresh_selected();
+        }
+
+        /**
+         * 导出当前显示的行，目录和文件都导出，顺序与界面一致
+         */
+        fn export(&self) -> Result<(), ApkError> {
+            let output = &self.options.csv_path;
+            if let Some(parent) = Path::new(output).parent() {
+                if !parent.exists() {
+                    fs::create_dir_all(parent)?;
+                }
+            }
+            let mut writer = csv::Writer::from_path(output)?;
+            writer.write_record(["Path", "Kind", "Files", "Size", "Download", "Ratio", "Type", "MD5", "Duplicates"])?;
+            for row in &self.rows {
+                let node = &self.nodes[*row];
+                let info = node.entry.map(|entry| &self.entries[entry]);
+                writer.write_record([
+                    node.path.clone(),
+                    if node.is_folder() { "Folder" } else { "File" }.to_string(),
+                    node.files.to_string(),
+                    node.size.to_string(),
+                    node.download.to_string(),
+                    format!("{:.2}%", node.ratio()),
+                    info.map(|info| info.file_type.clone()).unwrap_or_default(),
+                    info.map(|info| info.md5.clone()).unwrap_or_default(),
+                    self.duplicates_of(node).len().to_string(),
+                ])?;
+            }
+            writer.flush()?;
+            Ok(())
+        }
+
+        // 与文件内容相同的其他文件
+        fn duplicates_of(&self, node: &Node) -> Vec<&'a CliperInfo> {
+            let Some(entry) = node.entry else {
+                return Vec::new();
+            };
+            let entries = self.entries;
+            self.duplicates
+                .get(entries[entry].md5.as_str())
+                .map(|indexes| {
+                    indexes
+                        .iter()
+                        .filter(|index| **index != entry)
+                        .map(|index| &entries[*index])
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        fn draw(&mut self, frame: &mut Frame) {
+            let [header, body, filter, help] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Min(3),
+                Constraint::Length(1),
+                Constraint::Length(1),
+            ])
+            .areas(frame.area());
+            let [tree, details] =
+                Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(body);
+
+            let root = &self.nodes[0];
+            let title = format!(
+                " cliper browse  {}  files {}/{}  download {}  size {}  sort {}{}",
+                self.options.title,
+                root.files,
+                self.entries.len(),
+                SizeData::format_size(root.download),
+                SizeData::format_size(root.size),
+                self.sort.name(),
+                if self.reverse { " ↑" } else { " ↓" }
+            );
+            frame.render_widget(
+                Paragraph::new(title).style(Style::default().add_modifier(Modifier::BOLD)),
+                header,
+            );
+
+            let total = root.download.max(1);
+            let rows = self.rows.iter().map(|row| {
+                let node = &self.nodes[*row];
+                let marker = match (node.is_folder(), self.expanded.contains(&node.path)) {
+                    (false, _) => "  ",
+                    (true, true) => "▾ ",
+                    (true, false) => "▸ ",
+                };
+                let name = format!("{}{}{}", "  ".repeat(node.depth - 1), marker, node.name);
+                let share = node.download as f64 / total as f64;
+                let filled = (share * BAR_WIDTH as f64).round() as usize;
+                let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled.min(BAR_WIDTH)));
+                let style = if node.is_folder() {
+                    Style::default().fg(Color::Cyan)
+                } else {
+                    Style::default()
+                };
+                Row::new(vec![
+                    Cell::from(name),
+                    Cell::from(SizeData::format_size(node.download)),
+                    Cell::from(SizeData::format_size(node.size)),
+                    Cell::from(format!("{} {:>5.1}%", bar, share * 100.0)),
+                ])
+                .style(style)
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Min(20),
+                    Constraint::Length(10),
+                    Constraint::Length(10),
+                    Constraint::Length(BAR_WIDTH as u16 + 7),
+                ],
+            )
+            .header(Row::new(vec!["Name", "Download", "Size", "Share"]).style(Style::default().add_modifier(Modifier::BOLD)))
+            .block(Block::bordered().title(" Files "))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(table, tree, &mut self.state);
+
+            frame.render_widget(
+                Paragraph::new(self.details())
+                    .block(Block::bordered().title(" Details "))
+                    .wrap(Wrap { trim: false }),
+                details,
+            );
+
+            let filter_line = if self.editing {
+                Line::from(vec![
+                    Span::styled(" Filter: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(format!("{}█", self.query)),
+                    Span::styled(format!("  {}", self.message), Style::default().fg(Color::Red)),
+                ])
+            } else {
+                Line::from(vec![
+                    Span::styled(" Filter: ", Style::default().fg(Color::Yellow)),
+                    Span::raw(self.query.clone()),
+                    Span::styled(format!("  {}", self.message), Style::default().fg(Color::Green)),
+                ])
+            };
+            frame.render_widget(Paragraph::new(filter_line), filter);
+            let help_text = if self.editing {
+                " path: ext: type: re: size:, other words match anywhere in the path  enter done  esc cancel"
+            } else {
+                " ↑↓ move  ←→ fold  enter toggle  a/c expand/collapse all  / filter  s sort  r reverse  e export csv  q quit"
+            };
+            frame.render_widget(
+                Paragraph::new(help_text).style(Style::default().fg(Color::DarkGray)),
+                help,
+            );
+        }
+
+        /**
+         * 选中节点的详情，文件显示类型，压缩比例，md5 和内容相同的文件
+         */
+        fn details(&self) -> Vec<Line<'static>> {
+            let Some(node) = self.selected() else {
+                return vec![Line::from("No files match the filter")];
+            };
+            let mut lines = vec![
+                Line::from(Span::styled(node.path.clone(), Style::default().add_modifier(Modifier::BOLD))),
+                Line::from(""),
+            ];
+            let mut field = |name: &str, value: String| {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{:<10}", name), Style::default().fg(Color::Yellow)),
+                    Span::raw(value),
+                ]));
+            };
+            let total = self.nodes[0].download.max(1);
+            field("Size", format!("{} ({})", SizeData::format_size(node.size), node.size));
+            field("Download", format!("{} ({})", SizeData::format_size(node.download), node.download));
+            field("Ratio", format!("{:.2}%", node.ratio()));
+            field("Share", format!("{:.2}%", node.download as f64 * 100.0 / total as f64));
+            let Some(entry) = node.entry else {
+                field("Files", node.files.to_string());
+                return lines;
+            };
+            let info = &self.entries[entry];
+            field("Type", info.file_type.clone());
+            field("Ext", info.file_ext.clone());
+            field("MD5", info.md5.clone());
+            let duplicates = self.duplicates_of(node);
+            lines.push(Line::from(""));
+            if duplicates.is_empty() {
+                lines.push(Line::from("No duplicates"));
+            } else {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "{} duplicates, {} wasted",
+                        duplicates.len(),
+                        SizeData::format_size(duplicates.iter().map(|info| info.download).sum())
+                    ),
+                    Style::default().fg(Color::Red),
+                )));
+                for duplicate in duplicates {
+                    lines.push(Line::from(format!("  {}", duplicate.file_path)));
+                }
+            }
+            lines
+        }
+    }
+}
@@ -1,47 +1,121 @@
 pub mod size_reader {
-    use std::fs::File;
+    use std::fmt;
     use std::io::Read;
     use zip::read::ZipArchive;
     use std::path::Path;
     use md5;
+    use serde::Serialize;
 
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::apk_source::source::{ApkSource, ReadSeek};
     use crate::cliper::size_data::SizeData;
     use crate::cliper::cliper_info::CliperInfo;
+    use crate::cliper::zip_layout::layout_reader::{self, EntrySpan};
 
-    pub fn read_size(filename: &str) -> zip::result::ZipResult<SizeData> {
-        let file = File::open(filename)?;
+    /**
+     * 压缩包的类型，决定文件的分类方式
+     */
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+    pub enum PackageKind {
+        #[default]
+        Apk,
+        // android 库：classes.jar，res/，assets/，jni/<abi>/，R.txt，proguard.txt
+        Aar,
+        // java 库：.class 和资源文件
+        Jar,
+    }
+
+    impl PackageKind {
+        /**
+         * 根据压缩包中的文件判断类型，与扩展名无关
+         */
+        pub fn detect<'a>(names: impl Iterator<Item = &'a str>) -> PackageKind {
+            let mut has_manifest = false;
+            let mut has_dex = false;
+            let mut has_class = false;
+            for name in names {
+                match name {
+                    "classes.jar" => return PackageKind::Aar,
+                    "AndroidManifest.xml" => has_manifest = true,
+                    "META-INF/MANIFEST.MF" => has_class = true,
+                    _ if name.ends_with(".dex") => has_dex = true,
+                    _ if name.ends_with(".class") => has_class = true,
+                    _ => {}
+                }
+            }
+            if has_class && !has_manifest && !has_dex {
+                PackageKind::Jar
+            } else {
+                PackageKind::Apk
+            }
+        }
+
+        pub fn of(archive: &ZipArchive<Box<dyn ReadSeek>>) -> PackageKind {
+            PackageKind::detect(archive.file_names())
+        }
+    }
+
+    impl fmt::Display for PackageKind {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                PackageKind::Apk => write!(f, "APK"),
+                PackageKind::Aar => write!(f, "AAR"),
+                PackageKind::Jar => write!(f, "JAR"),
+            }
+        }
+    }
+
+    pub fn read_size(source: &ApkSource) -> Result<SizeData, ApkError> {
+        let file = source.open()?;
         let mut archive = ZipArchive::new(file)?;
+        let kind = PackageKind::of(&archive);
 
         let mut file_info = SizeData::new();
 
-        file_info.file_name = String::from(filename);
+        file_info.file_name = source.name().to_string();
+        file_info.kind = kind;
+
+        let mut spans: Vec<EntrySpan> = Vec::new();
+        let mut central_directory_start: Option<u64> = None;
 
         for i in 0..archive.len() {
-            let file = archive.by_index(i)?;
+            let file = archive.by_index_raw(i)?;
             let name = file.name().to_string();
             let _size = file.size();
             let download = file.compressed_size();
 
-            if name.starts_with("assets/") || name.starts_with("base/assets/") {
-                file_info.asserts += download;
-            } else if name.starts_with("res/") || name.starts_with("base/res/") {
-                file_info.res += download;
-            } else if name.starts_with("resources.arsc") || name.ends_with("resources.arsc") {
-                file_info.res += download;
-            } else if name.starts_with("classes") || name.starts_with("base/dex/classes") {
-                file_info.code += download;
-            } else if name.starts_with("lib/") || name.starts_with("base/lib") {
-                file_info.native += download;
-            } else {
-                file_info.others += download;
+            spans.push(EntrySpan {
+                header_start: file.header_start(),
+                data_start: file.data_start(),
+                compressed_size: download,
+            });
+            central_directory_start = Some(
+                central_directory_start.map_or(file.central_header_start(), |start| {
+                    start.min(file.central_header_start())
+                }),
+            );
+
+            match read_type(kind, &name).as_str() {
+                "Assets" => file_info.asserts += download,
+                "Res" => file_info.res += download,
+                "Code" => file_info.code += download,
+                "Native" => file_info.native += download,
+                _ => file_info.others += download,
             }
         }
 
+        // 文件数据以外的 zip 结构开销，加上后总大小与文件长度一致
+        let mut reader = archive.into_inner();
+        file_info.overhead_detail =
+            layout_reader::read_overhead(&mut reader, &spans, central_directory_start)?;
+        file_info.overhead = file_info.overhead_detail.total();
+
         file_info.all = file_info.asserts
             + file_info.res
             + file_info.code
             + file_info.native
-            + file_info.others;
+            + file_info.others
+            + file_info.overhead;
 
         Ok(file_info)
     }
@@ -51,9 +125,9 @@ pub mod size_reader {
      * 路径，名称，压缩大小，原始大小，分类，文件类型，文件夹的路径
      */
     pub fn read_detail_info(
-        filepath: &str,
-    ) -> zip::result::ZipResult<Vec<CliperInfo>> {
-        return _read_detail_info(filepath, false);
+        source: &ApkSource,
+    ) -> Result<Vec<CliperInfo>, ApkError> {
+        _read_detail_info(source, false)
     }
 
     /**
@@ -61,71 +135,183 @@ pub mod size_reader {
      * 路径，名称，压缩大小，原始大小，分类，文件类型，文件夹的路径
      */
     pub fn read_detail_info_with_md5(
-        filepath: &str,
-    ) -> zip::result::ZipResult<Vec<CliperInfo>> {
-        return _read_detail_info(filepath, true);
+        source: &ApkSource,
+    ) -> Result<Vec<CliperInfo>, ApkError> {
+        _read_detail_info(source, true)
     }
 
     fn _read_detail_info(
-        filepath: &str, need_md5: bool
-    ) -> zip::result::ZipResult<Vec<CliperInfo>> {
-        let zip_file = File::open(filepath)?;
+        source: &ApkSource, need_md5: bool
+    ) -> Result<Vec<CliperInfo>, ApkError> {
+        entries(source, need_md5)?.collect()
+    }
 
+    /**
+     * 逐个读取文件信息，need_md5 为 true 时会解压文件计算 md5，并跳过空文件
+     */
+    pub fn entries(source: &ApkSource, need_md5: bool) -> Result<Entries, ApkError> {
+        let zip_file = source.open()?;
         // 读取apk文件,zip格式
+        let archive = ZipArchive::new(zip_file)?;
+        Ok(Entries {
+            kind: PackageKind::of(&archive),
+            archive,
+            index: 0,
+            need_md5,
+        })
+    }
+
+    /**
+     * 文件信息的迭代器，不打印任何内容，进度由调用方根据 len() 自行展示
+     */
+    pub struct Entries {
+        archive: ZipArchive<Box<dyn ReadSeek>>,
+        kind: PackageKind,
+        index: usize,
+        need_md5: bool,
+    }
+
+    impl Entries {
+        pub fn kind(&self) -> PackageKind {
+            self.kind
+        }
+
+        /**
+         * 压缩包中的文件总数，包括计算 md5 时会跳过的空文件
+         */
+        pub fn total(&self) -> usize {
+            self.archive.len()
+        }
+
+        /**
+         * 已经读取过的文件数
+         */
+        pub fn position(&self) -> usize {
+            self.index
+        }
+    }
+
+    impl Iterator for Entries {
+        type Item = Result<CliperInfo, ApkError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            while self.index < self.archive.len() {
+                let i = self.index;
+                self.index += 1;
+                let file = match self.archive.by_index(i) {
+                    Ok(file) => file,
+                    Err(e) => return Some(Err(e.into())),
+                };
+                let name = file.name().to_string();
+                let size = file.size();
+                let download = file.compressed_size();
+                let file_type = read_type(self.kind, &name);
+                let file_ext = read_file_ext(&name);
+                let path = Path::new(&name);
+                let file_folder = path
+                    .parent()
+                    .map(|parent| parent.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let file_name = read_file_name(&name);
+
+                let mut md5_result: String = String::new();
+                if self.need_md5 {
+                    // 过滤一些空的文件
+                    if _filter_md5_file(&file_name, download) {
+                        continue;
+                    }
+                    let mut buffer = Vec::new();
+                    let mut read_file = file;
+                    if let Err(e) = read_file.read_to_end(&mut buffer) {
+                        return Some(Err(e.into()));
+                    }
+
+                    let digest = md5::compute(&buffer);
+
+                    md5_result = format!("{:x}", digest)
+                }
+                return Some(Ok(create_cliper_item(
+                    i as u64,
+                    name,
+                    file_name,
+                    size,
+                    download,
+                    file_type,
+                    file_ext,
+                    file_folder,
+                    md5_result,
+                )));
+            }
+            None
+        }
+    }
+
+    /**
+     * 读取满足条件的文件信息和文件内容
+     * accept 返回 false 的文件不会被解压
+     */
+    pub fn read_detail_info_with_content<F>(
+        source: &ApkSource,
+        accept: F,
+    ) -> Result<Vec<(CliperInfo, Vec<u8>)>, ApkError>
+    where
+        F: Fn(&CliperInfo) -> bool,
+    {
+        let mut content_list: Vec<(CliperInfo, Vec<u8>)> = Vec::new();
+        visit_detail_info_with_content(source, accept, |info, content| {
+            content_list.push((info, content));
+            Ok(())
+        })?;
+        Ok(content_list)
+    }
+
+    /**
+     * 逐个读取满足条件的文件信息和文件内容，不会把所有文件同时保存在内存中
+     * accept 返回 false 的文件不会被解压
+     */
+    pub fn visit_detail_info_with_content<F, V>(
+        source: &ApkSource,
+        accept: F,
+        mut visit: V,
+    ) -> Result<(), ApkError>
+    where
+        F: Fn(&CliperInfo) -> bool,
+        V: FnMut(CliperInfo, Vec<u8>) -> Result<(), ApkError>,
+    {
+        let zip_file = source.open()?;
         let mut archive = ZipArchive::new(zip_file)?;
-        // 存放文件信息 
-        let mut cliper_info_list: Vec<CliperInfo> = Vec::new();
+        let kind = PackageKind::of(&archive);
 
         for i in 0..archive.len() {
-            // 在单行打印进度
-            print!("\r进度: {}/{}", i+1, archive.len());
-    
-            let file = archive.by_index(i)?;
+            let mut file = archive.by_index(i)?;
             let name = file.name().to_string();
-            let size = file.size();
-            let download = file.compressed_size();
-            let file_type = read_type(&name);
-            let file_ext = read_file_ext(&name);
             let path = Path::new(&name);
-            let file_folder = path.parent().unwrap().to_str().unwrap().to_string();
-            let file_name = read_file_name(&name);
-            
-            let mut md5_result: String = String::new();
-            if need_md5 {
-                // 过滤一些空的文件
-                if _filter_md5_file(&file_name, download) {
-                    continue;
-                    
-                }
-                let mut buffer = Vec::new();
-                let mut read_file = file;
-                read_file.read_to_end(&mut buffer).unwrap();
-
-                let digest = md5::compute(&buffer);
-
-                md5_result = format!("{:x}", digest)
-                
-            }
+            let file_folder = path
+                .parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .unwrap_or_default();
             let cliper_info = create_cliper_item(
                 i as u64,
-                name,
-                file_name,
-                size,
-                download,
-                file_type,
-                file_ext,
+                name.clone(),
+                read_file_name(&name),
+                file.size(),
+                file.compressed_size(),
+                read_type(kind, &name),
+                read_file_ext(&name),
                 file_folder,
-                md5_result,
+                String::new(),
             );
-            
-            cliper_info_list.push(cliper_info);
+            if !accept(&cliper_info) {
+                continue;
+            }
+            let mut buffer = Vec::new();
+            file.read_to_end(&mut buffer)?;
+            visit(cliper_info, buffer)?;
         }
 
-        Ok(cliper_info_list)
+        Ok(())
     }
 
-
-
     fn _filter_md5_file(file_name: &str, download: u64) -> bool {
         if file_name.ends_with(".webp") && download == 0 {
             return true;
@@ -148,19 +334,57 @@ pub mod size_reader {
         if file_name.ends_with(".jpg") && download == 0 {
             return true;
         }
-        return false;
+        false
     }
 
     /**
-     * 读取文件类型
+     * 读取文件类型，aar 和 jar 按各自的目录结构分类
      */
-    fn read_type(name: &str) -> String {
+    fn read_type(kind: PackageKind, name: &str) -> String {
+        match kind {
+            PackageKind::Apk => read_apk_type(name),
+            PackageKind::Aar => read_aar_type(name),
+            PackageKind::Jar => read_jar_type(name),
+        }
+    }
+
+    fn read_aar_type(name: &str) -> String {
+        let file_type = if name.starts_with("assets/") {
+            "Assets"
+        } else if name.starts_with("res/") || name == "R.txt" || name == "public.txt" {
+            "Res"
+        } else if name == "classes.jar" || (name.starts_with("libs/") && name.ends_with(".jar")) {
+            "Code"
+        } else if name.starts_with("jni/") {
+            "Native"
+        } else {
+            // AndroidManifest.xml，proguard.txt，lint.jar 等
+            "Others"
+        };
+        file_type.to_string()
+    }
+
+    fn read_jar_type(name: &str) -> String {
+        let file_type = if name.ends_with(".class") {
+            "Code"
+        } else if name.ends_with(".so") || name.ends_with(".dll") || name.ends_with(".dylib") {
+            "Native"
+        } else {
+            // META-INF 和 java 资源文件
+            "Others"
+        };
+        file_type.to_string()
+    }
+
+    fn read_apk_type(name: &str) -> String {
         let file_type;
         if name.starts_with("assets/") || name.starts_with("base/assets/") {
             file_type = "Assets".to_string();
-        } else if name.starts_with("res/") || name.starts_with("base/res/") {
-            file_type = "Res".to_string();
-        } else if name.starts_with("resources.arsc") || name.ends_with("resources.arsc") {
+        } else if name.starts_with("res/")
+            || name.starts_with("base/res/")
+            || name.starts_with("resources.arsc")
+            || name.ends_with("resources.arsc")
+        {
             file_type = "Res".to_string();
         } else if name.starts_with("classes") || name.starts_with("base/dex/classes") {
             file_type = "Code".to_string();
@@ -169,7 +393,7 @@ pub mod size_reader {
         } else {
             file_type = "Others".to_string();
         }
-        return file_type;
+        file_type
     }
 
     /**
@@ -187,7 +411,7 @@ pub mod size_reader {
             return file_ext;
         }
         file_ext = name[index..].to_string();
-        return file_ext;
+        file_ext
     }
 
     /**
@@ -207,6 +431,7 @@ pub mod size_reader {
     /**
      * 创建一个文件信息
      */
+    #[allow(clippy::too_many_arguments)]
     fn create_cliper_item(
         id: u64,
         file_path: String,
@@ -228,6 +453,6 @@ pub mod size_reader {
         cliper_info.file_ext = file_ext;
         cliper_info.file_folder = file_folder;
         cliper_info.md5 = md5;
-        return cliper_info;
+        cliper_info
     }
 }
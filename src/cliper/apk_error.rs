@@ -0,0 +1,125 @@
+use std::fmt;
+
+use crate::app::android_xml::axml::AxmlError;
+use crate::app::dex_parser::dex::DexError;
+use crate::cliper::size_history::history::HistoryError;
+
+/**
+ * 分析 apk 时的错误，每种错误对应一个退出码
+ */
+#[derive(Debug)]
+pub enum ApkError {
+    // 输入文件为空或者不存在
+    InvalidInput(String),
+    // 过滤条件不合法，例如无法编译的正则
+    InvalidFilter(String),
+    // 文件读写失败
+    Io(std::io::Error),
+    // 不是合法的 zip 文件或者文件已损坏
+    Zip(zip::result::ZipError),
+    // 压缩包中没有清单文件
+    MissingManifest,
+    // 清单文件无法解码
+    Manifest(AxmlError),
+    // dex 文件无法解析
+    Dex(DexError),
+    // 历史记录数据库无法打开或者读写
+    Database(HistoryError),
+}
+
+impl ApkError {
+    /**
+     * 进程退出码，0 为成功，1 保留给参数解析错误
+     */
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ApkError::InvalidInput(_) => 2,
+            ApkError::InvalidFilter(_) => 3,
+            ApkError::Io(_) => 4,
+            ApkError::Zip(_) => 5,
+            ApkError::MissingManifest => 6,
+            ApkError::Manifest(_) => 7,
+            ApkError::Dex(_) => 8,
+            ApkError::Database(_) => 9,
+        }
+    }
+}
+
+impl fmt::Display for ApkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApkError::InvalidInput(message) => write!(f, "invalid input: {}", message),
+            ApkError::InvalidFilter(message) => write!(f, "invalid filter: {}", message),
+            ApkError::Io(err) => write!(f, "failed to read or write file: {}", err),
+            ApkError::Zip(err) => write!(f, "corrupt or unsupported archive: {}", err),
+            ApkError::MissingManifest => write!(f, "AndroidManifest.xml not found in archive"),
+            ApkError::Manifest(err) => write!(f, "failed to decode AndroidManifest.xml: {}", err),
+            ApkError::Dex(err) => write!(f, "failed to parse dex: {}", err),
+            ApkError::Database(err) => write!(f, "failed to access size history: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ApkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApkError::Io(err) => Some(err),
+            ApkError::Zip(err) => Some(err),
+            ApkError::Manifest(err) => Some(err),
+            ApkError::Dex(err) => Some(err),
+            ApkError::Database(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ApkError {
+    fn from(err: std::io::Error) -> Self {
+        ApkError::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for ApkError {
+    fn from(err: zip::result::ZipError) -> Self {
+        match err {
+            zip::result::ZipError::Io(err) => ApkError::Io(err),
+            err => ApkError::Zip(err),
+        }
+    }
+}
+
+impl From<AxmlError> for ApkError {
+    fn from(err: AxmlError) -> Self {
+        ApkError::Manifest(err)
+    }
+}
+
+impl From<DexError> for ApkError {
+    fn from(err: DexError) -> Self {
+        ApkError::Dex(err)
+    }
+}
+
+impl From<redb::Error> for ApkError {
+    fn from(err: redb::Error) -> Self {
+        match err {
+            redb::Error::Io(err) => ApkError::Io(err),
+            err => ApkError::Database(HistoryError::Redb(err)),
+        }
+    }
+}
+
+impl From<HistoryError> for ApkError {
+    fn from(err: HistoryError) -> Self {
+        match err {
+            HistoryError::Redb(err) => err.into(),
+            err => ApkError::Database(err),
+        }
+    }
+}
+
+impl From<csv::Error> for ApkError {
+    fn from(err: csv::Error) -> Self {
+        ApkError::Io(err.into())
+    }
+}
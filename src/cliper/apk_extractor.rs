@@ -0,0 +1,49 @@
+pub mod extractor {
+    use std::path::{Component, Path, PathBuf};
+
+    use crate::app::android_xml::axml;
+    use crate::app::arsc_parser::arsc::ResourceTable;
+    use crate::cliper::cliper_info::CliperInfo;
+
+    /**
+     * 判断是否可能是二进制 xml：清单文件和 res 目录下的 xml
+     */
+    pub fn is_binary_xml(info: &CliperInfo) -> bool {
+        info.file_path.ends_with("AndroidManifest.xml")
+            || ((info.file_path.starts_with("res/") || info.file_path.starts_with("base/res/"))
+                && info.file_path.ends_with(".xml"))
+    }
+
+    /**
+     * 把二进制 xml 解码成文本，资源引用通过 resources.arsc 还原成名称
+     * 不是二进制 xml 或者解码失败时返回 None，调用方原样复制
+     */
+    pub fn decode_entry(
+        info: &CliperInfo,
+        content: &[u8],
+        resources: Option<&ResourceTable>,
+    ) -> Option<Vec<u8>> {
+        if !is_binary_xml(info) {
+            return None;
+        }
+        let document = axml::parse_document(content).ok()?;
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+        xml.push_str(&axml::format_document(&document, resources));
+        Some(xml.into_bytes())
+    }
+
+    /**
+     * 计算文件解压后的路径
+     * 压缩包中的路径可能包含 .. 或者绝对路径，这类文件不解压，返回 None
+     */
+    pub fn output_file(output_dir: &str, file_path: &str) -> Option<PathBuf> {
+        let relative = Path::new(file_path);
+        if relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+        {
+            return None;
+        }
+        Some(Path::new(output_dir).join(relative))
+    }
+}
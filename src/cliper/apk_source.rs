@@ -0,0 +1,668 @@
+pub mod source {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::fs::File;
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use zip::{CompressionMethod, ZipArchive};
+
+    use crate::cliper::apk_error::ApkError;
+
+    // http 按块读取并缓存，zip 的中央目录和单个文件通常只需要少量的块
+    const HTTP_BLOCK_SIZE: u64 = 64 * 1024;
+
+    pub trait ReadSeek: Read + Seek {}
+
+    impl<T: Read + Seek> ReadSeek for T {}
+
+    /**
+     * apk 的来源：本地文件，内存(包括标准输入)，任意 Read + Seek，支持 Range 请求的 http 地址，
+     * 或者另一个压缩包中的文件
+     * 每次分析都会通过 open 得到一个独立的读取位置
+     */
+    #[derive(Clone)]
+    pub enum ApkSource {
+        File(String),
+        Memory {
+            name: String,
+            data: Arc<Vec<u8>>,
+        },
+        Reader {
+            name: String,
+            reader: Arc<Mutex<Box<dyn ReadSeek + Send>>>,
+        },
+        Http {
+            url: String,
+            length: u64,
+            // 已经下载的字节数
+            fetched: Arc<AtomicU64>,
+        },
+        Nested {
+            outer: Box<ApkSource>,
+            // 在外层压缩包中的路径
+            path: String,
+            content: NestedContent,
+        },
+    }
+
+    /**
+     * 嵌套文件的内容，未压缩的直接读取外层压缩包中的区间，压缩过的解压到内存
+     */
+    #[derive(Clone)]
+    pub enum NestedContent {
+        Stored { offset: u64, length: u64 },
+        Inflated(Arc<Vec<u8>>),
+    }
+
+    impl fmt::Debug for ApkSource {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                ApkSource::File(path) => write!(f, "File({})", path),
+                ApkSource::Memory { name, data } => write!(f, "Memory({}, {} bytes)", name, data.len()),
+                ApkSource::Reader { name, .. } => write!(f, "Reader({})", name),
+                ApkSource::Http { url, length, .. } => write!(f, "Http({}, {} bytes)", url, length),
+                ApkSource::Nested { outer, path, .. } => write!(f, "Nested({:?}, {})", outer, path),
+            }
+        }
+    }
+
+    impl ApkSource {
+        /**
+         * 根据命令行的 --input 创建来源
+         * "-" 表示标准输入，http:// 或 https:// 开头的按 Range 请求读取，其余为本地文件
+         * 用 !/ 指定压缩包中的文件，例如 artifacts.zip!/app/release/app-release.apk，可以多层嵌套
+         * 最外层不是 apk 时自动查找其中的 apk
+         */
+        pub fn from_input(input: &str) -> Result<ApkSource, ApkError> {
+            let mut parts = input.split("!/");
+            let mut source = ApkSource::from_single_input(parts.next().unwrap_or_default())?;
+            for path in parts {
+                source = ApkSource::nested(source, path)?;
+            }
+            source.discover()
+        }
+
+        fn from_single_input(input: &str) -> Result<ApkSource, ApkError> {
+            if input.is_empty() {
+                return Err(ApkError::InvalidInput(
+                    "input file path is empty, please input the apk file: --input ./build/app.apk"
+                        .to_string(),
+                ));
+            }
+            if input == "-" {
+                let mut data = Vec::new();
+                io::stdin().lock().read_to_end(&mut data)?;
+                return Ok(ApkSource::memory("stdin", data));
+            }
+            if input.starts_with("http://") || input.starts_with("https://") {
+                return ApkSource::http(input);
+            }
+            if !Path::new(input).exists() {
+                return Err(ApkError::InvalidInput(format!(
+                    "{} not exists, please input the apk file: --input ./build/app.apk",
+                    input
+                )));
+            }
+            Ok(ApkSource::File(input.to_string()))
+        }
+
+        pub fn memory(name: &str, data: Vec<u8>) -> ApkSource {
+            ApkSource::Memory {
+                name: name.to_string(),
+                data: Arc::new(data),
+            }
+        }
+
+        /**
+         * 包装任意 Read + Seek，多次打开时共享同一个底层读取器，各自记录读取位置
+         */
+        pub fn from_reader<R: Read + Seek + Send + 'static>(name: &str, reader: R) -> ApkSource {
+            ApkSource::Reader {
+                name: name.to_string(),
+                reader: Arc::new(Mutex::new(Box::new(reader))),
+            }
+        }
+
+        /**
+         * 通过 Range 请求读取远程文件，服务器不支持 Range 时返回错误
+         */
+        pub fn http(url: &str) -> Result<ApkSource, ApkError> {
+            let response = ureq::get(url)
+                .set("Range", "bytes=0-0")
+                .call()
+                .map_err(http_error)?;
+            // Content-Range: bytes 0-0/12345
+            let length = response
+                .header("Content-Range")
+                .filter(|_| response.status() == 206)
+                .and_then(|range| range.rsplit('/').next())
+                .and_then(|length| length.trim().parse::<u64>().ok())
+                .ok_or_else(|| {
+                    ApkError::InvalidInput(format!("{} does not support range requests", url))
+                })?;
+            Ok(ApkSource::Http {
+                url: url.to_string(),
+                length,
+                fetched: Arc::new(AtomicU64::new(0)),
+            })
+        }
+
+        /**
+         * 压缩包中的文件，外层压缩包可以是任意来源
+         * 未压缩的文件不会复制，通过外层的读取器按区间读取
+         */
+        pub fn nested(outer: ApkSource, path: &str) -> Result<ApkSource, ApkError> {
+            let mut archive = ZipArchive::new(outer.open()?)?;
+            let mut file = archive.by_name(path).map_err(|_| {
+                ApkError::InvalidInput(format!("{} not found in {}", path, outer.name()))
+            })?;
+            let content = if file.compression() == CompressionMethod::Stored {
+                NestedContent::Stored {
+                    offset: file.data_start(),
+                    length: file.size(),
+                }
+            } else {
+                let mut data = Vec::with_capacity(file.size() as usize);
+                file.read_to_end(&mut data)?;
+                NestedContent::Inflated(Arc::new(data))
+            };
+            drop(file);
+            Ok(ApkSource::Nested {
+                outer: Box::new(outer),
+                path: path.to_string(),
+                content,
+            })
+        }
+
+        /**
+         * 压缩包中所有的 apk 路径
+         */
+        pub fn apk_entries(&self) -> Result<Vec<String>, ApkError> {
+            let archive = ZipArchive::new(self.open()?)?;
+            let entries = archive
+                .file_names()
+                .filter(|name| name.to_lowercase().ends_with(".apk"))
+                .map(|name| name.to_string())
+                .collect();
+            Ok(entries)
+        }
+
+        /**
+         * 自动查找 apk：有 AndroidManifest.xml 或者没有内嵌 apk 时返回自己，
+         * 只有一个内嵌 apk 时返回它，有多个时返回错误并列出所有路径
+         * 不是 zip 的来源原样返回，由后续的分析报告错误
+         */
+        pub fn discover(self) -> Result<ApkSource, ApkError> {
+            let archive = match ZipArchive::new(self.open()?) {
+                Ok(archive) => archive,
+                Err(_) => return Ok(self),
+            };
+            if archive.file_names().any(|name| name == "AndroidManifest.xml") {
+                return Ok(self);
+            }
+            let mut entries = self.apk_entries()?;
+            entries.sort();
+            match entries.len() {
+                0 => Ok(self),
+                1 => ApkSource::nested(self, &entries[0]),
+                _ => {
+                    let choices = entries
+                        .iter()
+                        .map(|entry| format!("  {}!/{}", self.name(), entry))
+                        .collect::<Vec<String>>()
+                        .join("\n");
+                    Err(ApkError::InvalidInput(format!(
+                        "{} contains {} apks, please choose one with --input:\n{}",
+                        self.name(),
+                        entries.len(),
+                        choices
+                    )))
+                }
+            }
+        }
+
+        /**
+         * 查找同一个压缩包或者同一个目录下的文件，例如 mapping.txt
+         * 嵌套来源优先选择与 apk 路径公共前缀最长的文件
+         */
+        pub fn sibling(&self, file_name: &str) -> Result<Option<ApkSource>, ApkError> {
+            match self {
+                ApkSource::File(path) => {
+                    let sibling = Path::new(path).with_file_name(file_name);
+                    if sibling.is_file() {
+                        return Ok(Some(ApkSource::File(sibling.display().to_string())));
+                    }
+                    Ok(None)
+                }
+                ApkSource::Nested { outer, path, .. } => {
+                    let archive = ZipArchive::new(outer.open()?)?;
+                    let best = archive
+                        .file_names()
+                        .filter(|name| name.rsplit('/').next() == Some(file_name))
+                        .max_by_key(|name| (common_dir_len(name, path), std::cmp::Reverse(name.len())))
+                        .map(|name| name.to_string());
+                    match best {
+                        Some(name) => Ok(Some(ApkSource::nested(outer.as_ref().clone(), &name)?)),
+                        None => Ok(None),
+                    }
+                }
+                _ => Ok(None),
+            }
+        }
+
+        /**
+         * 混淆的 mapping.txt
+         */
+        pub fn mapping(&self) -> Result<Option<ApkSource>, ApkError> {
+            self.sibling("mapping.txt")
+        }
+
+        /**
+         * 用于显示的名称，嵌套的文件显示为 outer!/path
+         */
+        pub fn name(&self) -> String {
+            match self {
+                ApkSource::File(path) => path.clone(),
+                ApkSource::Memory { name, .. } => name.clone(),
+                ApkSource::Reader { name, .. } => name.clone(),
+                ApkSource::Http { url, .. } => url.clone(),
+                ApkSource::Nested { outer, path, .. } => format!("{}!/{}", outer.name(), path),
+            }
+        }
+
+        /**
+         * 不带目录和扩展名的名称，用作默认的输出目录名
+         */
+        pub fn stem(&self) -> String {
+            let name = self.name();
+            let name = name.split(['?', '#']).next().unwrap_or_default();
+            Path::new(name)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| "extract".to_string())
+        }
+
+        /**
+         * 通过 http 读取时已经下载的字节数，其他来源返回 None
+         */
+        pub fn fetched_bytes(&self) -> Option<u64> {
+            match self {
+                ApkSource::Http { fetched, .. } => Some(fetched.load(Ordering::Relaxed)),
+                ApkSource::Nested { outer, .. } => outer.fetched_bytes(),
+                _ => None,
+            }
+        }
+
+        pub fn open(&self) -> Result<Box<dyn ReadSeek>, ApkError> {
+            let reader: Box<dyn ReadSeek> = match self {
+                ApkSource::File(path) => Box::new(io::BufReader::new(File::open(path)?)),
+                ApkSource::Memory { data, .. } => Box::new(Cursor::new(SharedBytes(data.clone()))),
+                ApkSource::Reader { reader, .. } => Box::new(SharedReader {
+                    reader: reader.clone(),
+                    position: 0,
+                }),
+                ApkSource::Http {
+                    url,
+                    length,
+                    fetched,
+                } => Box::new(HttpRangeReader {
+                    url: url.clone(),
+                    length: *length,
+                    position: 0,
+                    blocks: BTreeMap::new(),
+                    fetched: fetched.clone(),
+                }),
+                ApkSource::Nested { outer, content, .. } => match content {
+                    NestedContent::Stored { offset, length } => Box::new(SliceReader {
+                        reader: outer.open()?,
+                        offset: *offset,
+                        length: *length,
+                        position: 0,
+                    }),
+                    NestedContent::Inflated(data) => Box::new(Cursor::new(SharedBytes(data.clone()))),
+                },
+            };
+            Ok(reader)
+        }
+    }
+
+    struct SharedBytes(Arc<Vec<u8>>);
+
+    impl AsRef<[u8]> for SharedBytes {
+        fn as_ref(&self) -> &[u8] {
+            self.0.as_slice()
+        }
+    }
+
+    /**
+     * 共享的读取器，每次读取前先移动到自己的位置
+     */
+    struct SharedReader {
+        reader: Arc<Mutex<Box<dyn ReadSeek + Send>>>,
+        position: u64,
+    }
+
+    impl SharedReader {
+        fn lock(&self) -> io::Result<std::sync::MutexGuard<'_, Box<dyn ReadSeek + Send>>> {
+            self.reader
+                .lock()
+                .map_err(|_| io::Error::other("shared reader is poisoned"))
+        }
+    }
+
+    impl Read for SharedReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let position = self.position;
+            let mut reader = self.lock()?;
+            reader.seek(SeekFrom::Start(position))?;
+            let len = reader.read(buf)?;
+            drop(reader);
+            self.position += len as u64;
+            Ok(len)
+        }
+    }
+
+    impl Seek for SharedReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let position = self.lock()?.seek(pos_from(pos, self.position))?;
+            self.position = position;
+            Ok(position)
+        }
+    }
+
+    /**
+     * 相对当前位置的移动换算成绝对位置，底层读取器的位置可能已经被其他句柄改变
+     */
+    fn pos_from(pos: SeekFrom, current: u64) -> SeekFrom {
+        match pos {
+            SeekFrom::Current(offset) => SeekFrom::Start(current.saturating_add_signed(offset)),
+            pos => pos,
+        }
+    }
+
+    /**
+     * 外层读取器中 [offset, offset + length) 的区间
+     */
+    struct SliceReader {
+        reader: Box<dyn ReadSeek>,
+        offset: u64,
+        length: u64,
+        position: u64,
+    }
+
+    impl Read for SliceReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.position >= self.length {
+                return Ok(0);
+            }
+            let len = buf.len().min((self.length - self.position) as usize);
+            self.reader.seek(SeekFrom::Start(self.offset + self.position))?;
+            let len = self.reader.read(&mut buf[..len])?;
+            self.position += len as u64;
+            Ok(len)
+        }
+    }
+
+    impl Seek for SliceReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let position = match pos {
+                SeekFrom::Start(offset) => Some(offset),
+                SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+                SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            };
+            self.position = position.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")
+            })?;
+            Ok(self.position)
+        }
+    }
+
+    /**
+     * 两个压缩包内路径相同的目录部分的长度
+     */
+    fn common_dir_len(a: &str, b: &str) -> usize {
+        a.split('/')
+            .zip(b.split('/'))
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len() + 1)
+            .sum()
+    }
+
+    /**
+     * 按需通过 Range 请求读取远程文件，读取过的块缓存在内存中
+     */
+    struct HttpRangeReader {
+        url: String,
+        length: u64,
+        position: u64,
+        blocks: BTreeMap<u64, Vec<u8>>,
+        fetched: Arc<AtomicU64>,
+    }
+
+    impl HttpRangeReader {
+        /**
+         * 下载 [start, end) 范围内缺少的块，连续缺少的块合并成一次请求
+         */
+        fn fetch(&mut self, start: u64, end: u64) -> io::Result<()> {
+            let first = start / HTTP_BLOCK_SIZE;
+            let last = (end.min(self.length).max(start + 1) - 1) / HTTP_BLOCK_SIZE;
+            let mut block = first;
+            while block <= last {
+                if self.blocks.contains_key(&block) {
+                    block += 1;
+                    continue;
+                }
+                let mut missing_end = block;
+                while missing_end < last && !self.blocks.contains_key(&(missing_end + 1)) {
+                    missing_end += 1;
+                }
+                let range_start = block * HTTP_BLOCK_SIZE;
+                let range_end = ((missing_end + 1) * HTTP_BLOCK_SIZE).min(self.length);
+                let response = ureq::get(&self.url)
+                    .set("Range", &format!("bytes={}-{}", range_start, range_end - 1))
+                    .call()
+                    .map_err(io::Error::other)?;
+                if response.status() != 206 {
+                    return Err(io::Error::other(format!(
+                        "{} ignored the range request",
+                        self.url
+                    )));
+                }
+                let mut data = Vec::with_capacity((range_end - range_start) as usize);
+                response.into_reader().read_to_end(&mut data)?;
+                if data.len() as u64 != range_end - range_start {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("{} returned a short range", self.url),
+                    ));
+                }
+                self.fetched.fetch_add(data.len() as u64, Ordering::Relaxed);
+                for (index, chunk) in data.chunks(HTTP_BLOCK_SIZE as usize).enumerate() {
+                    self.blocks.insert(block + index as u64, chunk.to_vec());
+                }
+                block = missing_end + 1;
+            }
+            Ok(())
+        }
+    }
+
+    impl Read for HttpRangeReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.position >= self.length || buf.is_empty() {
+                return Ok(0);
+            }
+            let end = (self.position + buf.len() as u64).min(self.length);
+            self.fetch(self.position, end)?;
+            let mut written = 0;
+            while self.position < end {
+                let block = self.position / HTTP_BLOCK_SIZE;
+                let offset = (self.position % HTTP_BLOCK_SIZE) as usize;
+                let data = &self.blocks[&block][offset..];
+                let len = data.len().min((end - self.position) as usize);
+                buf[written..written + len].copy_from_slice(&data[..len]);
+                written += len;
+                self.position += len as u64;
+            }
+            Ok(written)
+        }
+    }
+
+    impl Seek for HttpRangeReader {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            let position = match pos {
+                SeekFrom::Start(offset) => Some(offset),
+                SeekFrom::End(offset) => self.length.checked_add_signed(offset),
+                SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            };
+            self.position = position.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "invalid seek to a negative position")
+            })?;
+            Ok(self.position)
+        }
+    }
+
+    fn http_error(err: ureq::Error) -> ApkError {
+        ApkError::Io(io::Error::other(err.to_string()))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::{BufRead, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+        use std::net::TcpListener;
+        use std::sync::{Arc, Mutex};
+        use std::thread;
+
+        use zip::write::FileOptions;
+        use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+        use super::{ApkSource, HTTP_BLOCK_SIZE};
+        use crate::cliper::apk_error::ApkError;
+
+        // 一个未压缩的大文件跨越多个块，再加上压缩过的清单文件
+        fn small_apk() -> Vec<u8> {
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+            writer.start_file("AndroidManifest.xml", FileOptions::default()).unwrap();
+            writer.write_all(&b"<manifest package=\"com.example\"/>".repeat(64)).unwrap();
+            writer.start_file("assets/data.bin", stored).unwrap();
+            let data: Vec<u8> = (0..HTTP_BLOCK_SIZE * 2 + 100).map(|i| (i * 31 % 251) as u8).collect();
+            writer.write_all(&data).unwrap();
+            writer.start_file("res/raw/tail.txt", stored).unwrap();
+            writer.write_all(b"tail").unwrap();
+            writer.finish().unwrap().into_inner()
+        }
+
+        /**
+         * 在本地端口提供文件，记录每个请求的 Range 头
+         * support_range 为 false 时忽略 Range，总是返回完整的文件
+         */
+        fn serve(data: Vec<u8>, support_range: bool) -> (String, Arc<Mutex<Vec<String>>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}/app.apk", listener.local_addr().unwrap());
+            let ranges = Arc::new(Mutex::new(Vec::new()));
+            let recorded = ranges.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut range = None;
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    loop {
+                        let mut line = String::new();
+                        if reader.read_line(&mut line).unwrap() == 0 || line.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("range") {
+                                range = Some(value.trim().to_string());
+                            }
+                        }
+                    }
+                    recorded.lock().unwrap().push(range.clone().unwrap_or_default());
+                    // bytes=start-end，end 包含在内
+                    let bounds = range.filter(|_| support_range).and_then(|range| {
+                        let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+                        Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+                    });
+                    let (status, body, content_range) = match bounds {
+                        Some((start, end)) => {
+                            let end = end.min(data.len() - 1);
+                            let content_range = format!("Content-Range: bytes {}-{}/{}\r\n", start, end, data.len());
+                            ("206 Partial Content", &data[start..=end], content_range)
+                        }
+                        None => ("200 OK", &data[..], String::new()),
+                    };
+                    let header = format!(
+                        "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}Connection: close\r\n\r\n",
+                        status,
+                        body.len(),
+                        content_range
+                    );
+                    stream.write_all(header.as_bytes()).unwrap();
+                    stream.write_all(body).unwrap();
+                }
+            });
+            (url, ranges)
+        }
+
+        #[test]
+        fn http_source_reads_the_same_bytes_as_a_local_read() {
+            let data = small_apk();
+            let (url, ranges) = serve(data.clone(), true);
+            let source = ApkSource::http(&url).unwrap();
+            let local = ApkSource::memory("app.apk", data.clone());
+
+            // 读取单个文件时只下载中央目录和文件所在的块
+            let mut remote_archive = ZipArchive::new(source.open().unwrap()).unwrap();
+            let mut tail = String::new();
+            remote_archive.by_name("res/raw/tail.txt").unwrap().read_to_string(&mut tail).unwrap();
+            assert_eq!(tail, "tail");
+            assert!(source.fetched_bytes().unwrap() < data.len() as u64);
+
+            // 每个文件的内容都与本地读取的相同
+            let mut local_archive = ZipArchive::new(local.open().unwrap()).unwrap();
+            assert_eq!(remote_archive.len(), local_archive.len());
+            for index in 0..local_archive.len() {
+                let mut expected = Vec::new();
+                local_archive.by_index(index).unwrap().read_to_end(&mut expected).unwrap();
+                let mut actual = Vec::new();
+                remote_archive.by_index(index).unwrap().read_to_end(&mut actual).unwrap();
+                assert_eq!(actual, expected);
+            }
+
+            // 跨块的任意区间和完整的文件
+            let mut reader = source.open().unwrap();
+            let start = HTTP_BLOCK_SIZE as usize - 10;
+            let mut buffer = vec![0; 40];
+            reader.seek(SeekFrom::Start(start as u64)).unwrap();
+            reader.read_exact(&mut buffer).unwrap();
+            assert_eq!(buffer, data[start..start + 40]);
+            let mut all = Vec::new();
+            reader.seek(SeekFrom::Start(0)).unwrap();
+            reader.read_to_end(&mut all).unwrap();
+            assert_eq!(all, data);
+
+            // 长度探测之后每个请求都是按块对齐的 Range 请求
+            let ranges = ranges.lock().unwrap();
+            assert_eq!(ranges[0], "bytes=0-0");
+            assert!(ranges.len() > 2);
+            for range in ranges.iter().skip(1) {
+                let (start, end) = range.strip_prefix("bytes=").unwrap().split_once('-').unwrap();
+                let (start, end) = (start.parse::<u64>().unwrap(), end.parse::<u64>().unwrap());
+                assert_eq!(start % HTTP_BLOCK_SIZE, 0, "{}", range);
+                assert!(start <= end && end < data.len() as u64, "{}", range);
+            }
+        }
+
+        #[test]
+        fn http_source_requires_range_support() {
+            let (url, _) = serve(small_apk(), false);
+            match ApkSource::http(&url) {
+                Err(ApkError::InvalidInput(message)) => assert!(message.contains("range"), "{}", message),
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+}
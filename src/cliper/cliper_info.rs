@@ -1,4 +1,6 @@
-#[derive(Debug)]
+use serde::Serialize;
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct CliperInfo {
     // | id | file Path | Name  | Size | Download | Type  | File Type | File Folder |
     pub id : u64,
@@ -28,18 +30,4 @@ impl CliperInfo {
             diff: 0,
         }
     }
-    pub fn clone(&self) -> CliperInfo {
-        CliperInfo {
-            id: self.id,
-            file_path: self.file_path.clone(),
-            name: self.name.clone(),
-            size: self.size,
-            download: self.download,
-            file_type: self.file_type.clone(),
-            file_ext: self.file_ext.clone(),
-            file_folder: self.file_folder.clone(),
-            md5: self.md5.clone(),
-            diff: self.diff,
-        }
-    }
 }
@@ -1,5 +1,7 @@
 use structopt::StructOpt;
 
+use crate::cliper::apk_error::ApkError;
+
 // Common options for the `cliper` tool.
 #[derive(Debug, StructOpt)]
 pub struct CommonOpts {
@@ -7,7 +9,7 @@ pub struct CommonOpts {
     #[structopt(short, long)]
     pub debug: bool,
     
-    /// Specify the input file path. Example: `--input ./build/app.apk`.
+    /// Specify the input file path, `-` for stdin, or an http(s) url supporting range requests. Use `!/` for an apk inside a zip. Example: `--input ./build/app.apk`.
     #[structopt(long)]
     pub input: String,
     
@@ -48,6 +50,171 @@ pub struct DetailOpts {
     pub limit: usize,
 }
 
+// Initial filters of the full-screen browser, they can be edited in the ui.
+#[derive(Debug, StructOpt)]
+pub struct BrowseOpts {
+    /// Filter by path within the package. Example: `--filter-path assets`.
+    #[structopt(long, default_value = "", help = "Filter by path. Example: `--filter-path assets`.")]
+    pub filter_path: String,
+
+    /// Filter by minimum file size in bytes. Example: `--filter-size 10000`.
+    #[structopt(long, default_value = "0", help = "Filter by minimum file size in bytes. Example: `--filter-size 10000`.")]
+    pub filter_size: u64,
+
+    /// Filter by file extension. Example: `--filter-ext .png`.
+    #[structopt(long, default_value = "", help = "Filter by file extension. Example: `--filter-ext .png`.")]
+    pub filter_ext: String,
+
+    /// Filter by type such as Code, Res, Native, Assets, Other. Example: `--filter-type Res`.
+    #[structopt(long, default_value = "", help = "Filter by type (Code, Res, Native, Assets, Other). Example: `--filter-type Res`.")]
+    pub filter_type: String,
+
+    /// Filter using a regular expression pattern. Example: `--filter-regex "^.*\\.png$"`.
+    #[structopt(long, default_value = "", help = "Filter using a regular expression pattern. Example: `--filter-regex \"^.*\\.png$\"`.")]
+    pub filter_regex: String,
+}
+
+// Options for the image optimization advisor.
+#[derive(Debug, StructOpt)]
+pub struct ImageOpts {
+    /// Quality of the lossy WebP estimate, 0-100. Example: `--quality 75`.
+    #[structopt(long, default_value = "75", help = "Quality of the lossy WebP estimate, 0-100. Example: `--quality 75`.")]
+    pub quality: f32,
+
+    /// Largest side in dp an image may have in its density bucket. Example: `--max-dp 480`.
+    #[structopt(long, default_value = "480", help = "Largest side in dp an image may have in its density bucket. Example: `--max-dp 480`.")]
+    pub max_dp: u32,
+
+    /// Write the smaller variant of each flagged image into the build path. Use `--extract` to activate.
+    #[structopt(long)]
+    pub extract: bool,
+
+    /// Allow lossy WebP as extracted variant, lossless variants only by default. Use `--lossy` to activate.
+    #[structopt(long)]
+    pub lossy: bool,
+
+    /// Minimum savings in bytes for an image to be extracted. Example: `--min-savings 1024`.
+    #[structopt(long, default_value = "0", help = "Minimum savings in bytes for an image to be extracted. Example: `--min-savings 1024`.")]
+    pub min_savings: u64,
+}
+
+impl ImageOpts {
+    /// WebP quality must be within 0-100, the encoder fails on anything else.
+    pub fn validate(&self) -> Result<(), ApkError> {
+        if !(0.0..=100.0).contains(&self.quality) {
+            return Err(ApkError::InvalidInput(format!(
+                "--quality must be between 0 and 100, got {}",
+                self.quality
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Options for the binary xml analysis under res/.
+#[derive(Debug, StructOpt)]
+pub struct XmlOpts {
+    /// Flag layouts nested deeper than this. Example: `--max-depth 10`.
+    #[structopt(long, default_value = "10", help = "Flag layouts nested deeper than this. Example: `--max-depth 10`.")]
+    pub max_depth: usize,
+
+    /// Flag vector drawables with a pathData longer than this. Example: `--max-path-length 1000`.
+    #[structopt(long, default_value = "1000", help = "Flag vector drawables with a pathData longer than this. Example: `--max-path-length 1000`.")]
+    pub max_path_length: usize,
+}
+
+// Options for the duplicate detection.
+#[derive(Debug, StructOpt)]
+pub struct SameOpts {
+    /// Find duplicate classes across dex files instead of duplicate files. Use `--code` to activate.
+    #[structopt(long, conflicts_with = "native")]
+    pub code: bool,
+
+    /// Find native libraries shipped more than once, by content, DT_SONAME or build-id. Use `--native` to activate.
+    #[structopt(long)]
+    pub native: bool,
+
+    /// Smallest class, in 16-bit code units, compared by structure in `--code` mode. Example: `--min-code-units 16`.
+    #[structopt(long, default_value = "16", help = "Smallest class, in 16-bit code units, compared by structure in `--code` mode. Example: `--min-code-units 16`.")]
+    pub min_code_units: u32,
+}
+
+// Options for the library attribution.
+#[derive(Debug, StructOpt)]
+pub struct LibraryOpts {
+    /// Extra attribution rules, one `prefix = Library` per line, `so:` prefix for native libraries. Example: `--rules ./libraries.txt`.
+    #[structopt(long, default_value = "", help = "Extra attribution rules, one `prefix = Library` per line, `so:` prefix for native libraries. Example: `--rules ./libraries.txt`.")]
+    pub rules: String,
+
+    /// Limit the number of output lines. Example: `--limit 10`.
+    #[structopt(long, default_value = "0", help = "Limit the number of output lines. Example: `--limit 10`.")]
+    pub limit: usize,
+}
+
+// Options for attributing classes to gradle modules.
+#[derive(Debug, StructOpt)]
+pub struct ModuleOpts {
+    /// Class to module map: json, csv of `module,class`, or a `./gradlew dependencies` report. Example: `--map ./build/modules.json`.
+    #[structopt(long, default_value = "", help = "Class to module map: json, csv of `module,class`, or a `./gradlew dependencies` report. Example: `--map ./build/modules.json`.")]
+    pub map: String,
+
+    /// Directory with the jar and aar of each dependency, used with a dependency report. Example: `--jars ./build/jars`.
+    #[structopt(long, default_value = "", help = "Directory with the jar and aar of each dependency, used with a dependency report. Example: `--jars ./build/jars`.")]
+    pub jars: String,
+}
+
+// Options for the output of `diff`.
+#[derive(Debug, StructOpt)]
+pub struct DiffOpts {
+    /// Output format: `table`, or `markdown` for a pull request comment. Example: `--format markdown`.
+    #[structopt(long, default_value = "table", possible_values = &["table", "markdown"], help = "Output format: `table`, or `markdown` for a pull request comment. Example: `--format markdown`.")]
+    pub format: String,
+
+    /// Number of rows listed per table in the markdown summary and the `--resources` and `--native` reports, 0 for all. Example: `--top 10`.
+    #[structopt(long, default_value = "10", help = "Number of rows listed per table in the markdown summary and the `--resources` and `--native` reports, 0 for all. Example: `--top 10`.")]
+    pub top: usize,
+
+    /// Hide files, resource entries, classes and symbols whose size changed by less than this many bytes in the markdown summary. Example: `--threshold 1024`.
+    #[structopt(long, default_value = "0", help = "Hide files, resource entries, classes and symbols whose size changed by less than this many bytes in the markdown summary. Example: `--threshold 1024`.")]
+    pub threshold: u64,
+
+    /// Compare the manifests instead of the files: permissions, components, features and attributes. Use `--manifest` to activate.
+    #[structopt(long, conflicts_with_all = &["resources", "dex", "native"])]
+    pub manifest: bool,
+
+    /// Compare the parsed resources.arsc: resource names, configurations, string pool and entry sizes. Use `--resources` to activate.
+    #[structopt(long, conflicts_with_all = &["manifest", "dex", "native"])]
+    pub resources: bool,
+
+    /// Compare the parsed dex files: added and removed classes and packages, method and byte deltas per package. Use `--dex` to activate.
+    #[structopt(long, conflicts_with_all = &["manifest", "resources", "native"])]
+    pub dex: bool,
+
+    /// Compare the changed `.so` files: section sizes, added, removed and grown symbols, lost stripping. Use `--native` to activate.
+    #[structopt(long, conflicts_with_all = &["manifest", "resources", "dex"])]
+    pub native: bool,
+
+    /// Mapping file of `--input` for `--dex`, the mapping.txt next to the apk by default. Example: `--mapping ./build/mapping.txt`.
+    #[structopt(long, default_value = "", help = "Mapping file of `--input` for `--dex`, the mapping.txt next to the apk by default. Example: `--mapping ./build/mapping.txt`.")]
+    pub mapping: String,
+
+    /// Mapping file of `--input-cmp` for `--dex`, the mapping.txt next to the apk by default. Example: `--mapping-cmp ./build/mapping2.txt`.
+    #[structopt(long, default_value = "", help = "Mapping file of `--input-cmp` for `--dex`, the mapping.txt next to the apk by default. Example: `--mapping-cmp ./build/mapping2.txt`.")]
+    pub mapping_cmp: String,
+
+    /// Limit the number of packages and classes listed by `--dex`. Example: `--limit 10`.
+    #[structopt(long, default_value = "0", help = "Limit the number of packages and classes listed by `--dex`. Example: `--limit 10`.")]
+    pub limit: usize,
+}
+
+// Options for the size history database.
+#[derive(Debug, StructOpt)]
+pub struct HistoryOpts {
+    /// Size history database, `<build path>/cliper.redb` by default. Example: `--db ./build/cliper.redb`.
+    #[structopt(long, default_value = "", help = "Size history database, `<build path>/cliper.redb` by default. Example: `--db ./build/cliper.redb`.")]
+    pub db: String,
+}
+
 /// Cliper: A package volume analysis tool.
 ///
 /// This tool helps you analyze the contents of your app's package file (APK, AAB, etc.).
@@ -62,13 +229,65 @@ pub struct DetailOpts {
 /// To get detailed information filtered by extension and size:
 ///     `./cliper detail --input ./build/app.apk --filter-ext .png --filter-size 10000 --filter-path assets`
 ///
+/// To browse the package in a full-screen terminal ui:
+///     `./cliper browse --input ./build/app.apk --filter-path res`
+///
 /// To find duplicate files:
 ///     `./cliper same --input ./build/app.apk`
+///
+/// To find duplicate classes across dex files:
+///     `./cliper same --input ./build/app.apk --code`
+///
+/// To find native libraries shipped more than once:
+///     `./cliper same --input ./build/app.apk --native`
 /// 
+/// To find badly optimized images:
+///     `./cliper images --input ./build/app.apk --quality 75 --limit 20`
+///
+/// To write the optimized images into the build path:
+///     `./cliper images --input ./build/app.apk --extract --lossy --min-savings 1024`
+///
+/// To analyze layouts, vector drawables and animations:
+///     `./cliper xml --input ./build/app.apk --max-depth 10 --max-path-length 1000`
+///
+/// To unpack the package with binary xml decoded:
+///     `./cliper extract --input ./build/app.apk --output-dir ./build/app --filter-path res/layout`
+///
+/// To rank the libraries by attributed bytes:
+///     `./cliper libraries --input ./build/app.apk --rules ./libraries.txt`
+///
+/// To attribute code bytes to gradle modules:
+///     `./cliper modules --input ./build/app.apk --map ./build/dependencies.txt --jars ./build/jars`
+///
+/// To record the sizes of a build into the history database:
+///     `./cliper record --input ./build/app.apk --label v1.2.0-3-gabc1234`
+///
+/// To show the size trend of the last 10 records:
+///     `./cliper trend --limit 10 --top 10`
+///
+/// To compare flavors or versions side by side:
+///     `./cliper compare --input ./build/free.apk --input ./build/pro.apk --top 10 --output-html`
+///     note: the first input is the baseline of the deltas.
+///
 /// To diff two package:
 ///     `./cliper diff --input ./build/app.apk --input-cmp ./build/app2.apk`
 ///     note: the input-cmp is the old package file path.
 ///
+/// To post the diff as a pull request comment:
+///     `./cliper diff --input ./build/app.apk --input-cmp ./build/app2.apk --format markdown --threshold 1024 > size.md`
+///
+/// To review the manifest changes, security relevant ones are flagged:
+///     `./cliper diff --input ./build/app.apk --input-cmp ./build/app2.apk --manifest`
+///
+/// To find out why resources.arsc grew:
+///     `./cliper diff --input ./build/app.apk --input-cmp ./build/app2.apk --resources --top 20`
+///
+/// To find out which packages made the code grow:
+///     `./cliper diff --input ./build/app.apk --input-cmp ./build/app2.apk --dex --mapping ./build/mapping.txt --mapping-cmp ./build/mapping2.txt --limit 20`
+///
+/// To find out why a native library grew:
+///     `./cliper diff --input ./build/app.apk --input-cmp ./build/app2.apk --native --top 20`
+///
 /// Getting Help:
 ///
 /// For a list of all commands and options, use:
@@ -92,20 +311,123 @@ pub enum Args {
         #[structopt(flatten)]
         detail: DetailOpts,
     },
+    /// Browse the package as a folder tree in a full-screen terminal ui with live filters.
+    Browse {
+        #[structopt(flatten)]
+        common: CommonOpts,
+        #[structopt(flatten)]
+        browse: BrowseOpts,
+    },
     /// Identify duplicate files within the package.
     Same {
         #[structopt(flatten)]
         common: CommonOpts,
+        #[structopt(flatten)]
+        same: SameOpts,
+    },
+    /// Estimate the savings of recompressing PNG/JPEG images and converting them to WebP.
+    Images {
+        #[structopt(flatten)]
+        common: CommonOpts,
+        #[structopt(flatten)]
+        detail: DetailOpts,
+        #[structopt(flatten)]
+        image: ImageOpts,
+    },
+    /// Analyze binary xml under res/: bytes per layout, vector drawable and animation.
+    Xml {
+        #[structopt(flatten)]
+        common: CommonOpts,
+        #[structopt(flatten)]
+        detail: DetailOpts,
+        #[structopt(flatten)]
+        xml: XmlOpts,
+    },
+    /// Unpack the package into a directory, binary xml is decoded to readable text.
+    Extract {
+        #[structopt(flatten)]
+        common: CommonOpts,
+        #[structopt(flatten)]
+        detail: DetailOpts,
+        /// Output directory, `<build path>/<input file name without extension>` by default. Example: `--output-dir ./build/app`.
+        #[structopt(long, default_value = "")]
+        output_dir: String,
     },
     /// Display package information such as package name, version code, and version name.
     Info {
         #[structopt(flatten)]
         common: CommonOpts,
     },
+    /// Rank the libraries in the package by attributed bytes, classes are attributed by package prefix.
+    Libraries {
+        #[structopt(flatten)]
+        common: CommonOpts,
+        #[structopt(flatten)]
+        library: LibraryOpts,
+    },
+    /// Attribute dex bytes to gradle modules and external artifacts with a class map from the build.
+    Modules {
+        #[structopt(flatten)]
+        common: CommonOpts,
+        #[structopt(flatten)]
+        module: ModuleOpts,
+        /// Limit the number of output lines. Example: `--limit 10`.
+        #[structopt(long, default_value = "0")]
+        limit: usize,
+    },
+    /// Append a size snapshot of the package to the history database.
+    Record {
+        #[structopt(flatten)]
+        common: CommonOpts,
+        #[structopt(flatten)]
+        history: HistoryOpts,
+        /// Label of the build, `git describe --tags --always --dirty` by default. Example: `--label v1.2.0`.
+        #[structopt(long, default_value = "")]
+        label: String,
+    },
+    /// Show the size growth over the last records of the history database.
+    Trend {
+        #[structopt(flatten)]
+        history: HistoryOpts,
+        /// Number of records to show, 0 for all. Example: `--limit 10`.
+        #[structopt(long, default_value = "10")]
+        limit: usize,
+        /// Number of folders listed as biggest movers. Example: `--top 10`.
+        #[structopt(long, default_value = "10")]
+        top: usize,
+        /// Enable output in CSV format. Use `--output-csv` to activate.
+        #[structopt(short, long)]
+        output_csv: bool,
+    },
+    /// Compare any number of package files side by side, deltas are against the first input.
+    Compare {
+        /// Enable debug mode. Use `-d` or `--debug` to activate.
+        #[structopt(short, long)]
+        debug: bool,
+        /// Input file paths, the first one is the baseline. Example: `--input ./build/free.apk --input ./build/pro.apk`.
+        #[structopt(long, required = true, number_of_values = 1)]
+        input: Vec<String>,
+        /// Number of largest folders taken from each package. Example: `--top 10`.
+        #[structopt(long, default_value = "10")]
+        top: usize,
+        /// Enable output in CSV format. Use `--output-csv` to activate.
+        #[structopt(short, long)]
+        output_csv: bool,
+        /// Enable output in JSON format. Use `--output-json` to activate.
+        #[structopt(long)]
+        output_json: bool,
+        /// Enable output in HTML format. Use `--output-html` to activate.
+        #[structopt(long)]
+        output_html: bool,
+    },
     /// Compare two package files and display the differences.
     Diff {
         #[structopt(flatten)]
         common: CommonOpts,
+        #[structopt(flatten)]
+        module: ModuleOpts,
+        #[structopt(flatten)]
+        output: DiffOpts,
         /// Specify the second input file path. Example: `--input_cmp ./build/app2.apk`.
         #[structopt(long)]
         input_cmp: String,
@@ -0,0 +1,156 @@
+pub mod matrix {
+    use std::collections::HashSet;
+
+    use serde::Serialize;
+
+    use crate::cliper::apk_archive::archive::ApkArchive;
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::size_history::history::{SizeSnapshot, CATEGORIES};
+
+    pub const CATEGORY: &str = "Category";
+    pub const FOLDER: &str = "Folder";
+
+    /**
+     * 矩阵中的一列，对应一个输入文件
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct MatrixColumn {
+        pub name: String,
+        pub file: String,
+        pub version: String,
+    }
+
+    /**
+     * 矩阵中的一行，values 与列一一对应，deltas 为每一列减去第一列
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct MatrixRow {
+        // Category 或者 Folder
+        pub section: String,
+        pub name: String,
+        pub values: Vec<u64>,
+        pub deltas: Vec<i64>,
+    }
+
+    /**
+     * 多个 apk 的分类大小和目录大小，大小都是下载大小
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct CompareMatrix {
+        pub columns: Vec<MatrixColumn>,
+        pub rows: Vec<MatrixRow>,
+    }
+
+    /**
+     * 并排对比多个 apk，第一个为基准
+     * 目录取每个 apk 最大的 top 个的并集，按各列中的最大值从大到小排序
+     */
+    pub fn compare(apks: &[ApkArchive], top: usize) -> Result<CompareMatrix, ApkError> {
+        let mut snapshots = Vec::with_capacity(apks.len());
+        for apk in apks {
+            snapshots.push(SizeSnapshot::from_archive(apk, "", 0)?);
+        }
+        let columns = apks
+            .iter()
+            .zip(&snapshots)
+            .map(|(apk, snapshot)| MatrixColumn {
+                name: apk.source().stem(),
+                file: apk.source().name(),
+                version: snapshot.version(),
+            })
+            .collect();
+
+        let mut rows = Vec::new();
+        for category in CATEGORIES {
+            let values = snapshots.iter().map(|snapshot| snapshot.category(category)).collect();
+            rows.push(matrix_row(CATEGORY, category, values));
+        }
+        let totals = snapshots.iter().map(|snapshot| snapshot.total).collect();
+        rows.push(matrix_row(CATEGORY, "All", totals));
+
+        let mut folders: Vec<&String> = Vec::new();
+        let mut seen = HashSet::new();
+        for snapshot in &snapshots {
+            let mut largest: Vec<(&String, &u64)> = snapshot.folders.iter().collect();
+            largest.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+            for (folder, _) in largest.into_iter().take(top) {
+                if seen.insert(folder) {
+                    folders.push(folder);
+                }
+            }
+        }
+        let mut folder_rows: Vec<MatrixRow> = folders
+            .into_iter()
+            .map(|folder| {
+                let values = snapshots
+                    .iter()
+                    .map(|snapshot| snapshot.folders.get(folder).copied().unwrap_or(0))
+                    .collect();
+                matrix_row(FOLDER, folder, values)
+            })
+            .collect();
+        folder_rows.sort_by(|a, b| {
+            let max = |row: &MatrixRow| row.values.iter().copied().max().unwrap_or(0);
+            max(b).cmp(&max(a)).then(a.name.cmp(&b.name))
+        });
+        rows.extend(folder_rows);
+        Ok(CompareMatrix { columns, rows })
+    }
+
+    fn matrix_row(section: &str, name: &str, values: Vec<u64>) -> MatrixRow {
+        let base = values.first().copied().unwrap_or(0) as i64;
+        MatrixRow {
+            section: section.to_string(),
+            name: name.to_string(),
+            deltas: values.iter().map(|value| *value as i64 - base).collect(),
+            values,
+        }
+    }
+
+    impl CompareMatrix {
+        /**
+         * 独立的 html 页面，增长标红，减少标绿
+         */
+        pub fn to_html(&self) -> String {
+            let mut html = String::new();
+            html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>cliper compare</title>\n");
+            html.push_str("<style>\n");
+            html.push_str("body { font-family: sans-serif; }\n");
+            html.push_str("table { border-collapse: collapse; }\n");
+            html.push_str("th, td { border: 1px solid #ccc; padding: 4px 8px; }\n");
+            html.push_str("td.size { text-align: right; }\n");
+            html.push_str(".grow { color: #c62828; }\n.shrink { color: #2e7d32; }\n");
+            html.push_str("</style>\n</head>\n<body>\n<table>\n<tr><th>Section</th><th>Name</th>");
+            for column in &self.columns {
+                html.push_str(&format!(
+                    "<th title=\"{}\">{}<br>{}</th>",
+                    escape(&column.file),
+                    escape(&column.name),
+                    escape(&column.version)
+                ));
+            }
+            html.push_str("</tr>\n");
+            for row in &self.rows {
+                html.push_str(&format!("<tr><td>{}</td><td>{}</td>", escape(&row.section), escape(&row.name)));
+                for (index, (value, delta)) in row.values.iter().zip(&row.deltas).enumerate() {
+                    html.push_str(&format!("<td class=\"size\">{}", value));
+                    if index > 0 && *delta != 0 {
+                        let class = if *delta > 0 { "grow" } else { "shrink" };
+                        html.push_str(&format!(" <span class=\"{}\">({:+})</span>", class, delta));
+                    }
+                    html.push_str("</td>");
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n</body>\n</html>\n");
+            html
+        }
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+    }
+}
@@ -0,0 +1,354 @@
+pub mod dex_compare {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use crate::cliper::apk_archive::archive::ClassSize;
+
+    // 没有包名的类
+    pub const DEFAULT_PACKAGE: &str = "(default)";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub enum ClassChangeKind {
+        Added,
+        Removed,
+        Changed,
+    }
+
+    /**
+     * 一个类的变化，大小为类自身在 dex 中的字节数
+     * 下载大小为 dex 中分摊后的大小，会随同一个 dex 中其他类的变化而变化，只用于展示
+     */
+    #[derive(Debug, Clone, Serialize)]
+    pub struct ClassChange {
+        pub kind: ClassChangeKind,
+        pub name: String,
+        pub package: String,
+        pub old_methods: u32,
+        pub new_methods: u32,
+        pub old_size: u64,
+        pub new_size: u64,
+        pub diff: i64,
+        pub old_download: u64,
+        pub new_download: u64,
+    }
+
+    impl ClassChange {
+        pub fn download_diff(&self) -> i64 {
+            self.new_download as i64 - self.old_download as i64
+        }
+    }
+
+    /**
+     * 一个包的变化，只统计直接在这个包下的类，不包含子包
+     * old_classes 为 0 的是新增的包，new_classes 为 0 的是删除的包
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct PackageChange {
+        pub package: String,
+        pub old_classes: usize,
+        pub new_classes: usize,
+        pub added_classes: usize,
+        pub removed_classes: usize,
+        pub old_methods: u32,
+        pub new_methods: u32,
+        pub old_size: u64,
+        pub new_size: u64,
+        pub diff: i64,
+        pub old_download: u64,
+        pub new_download: u64,
+    }
+
+    impl PackageChange {
+        pub fn method_diff(&self) -> i64 {
+            self.new_methods as i64 - self.old_methods as i64
+        }
+
+        pub fn download_diff(&self) -> i64 {
+            self.new_download as i64 - self.old_download as i64
+        }
+
+        pub fn status(&self) -> ClassChangeKind {
+            match (self.old_classes, self.new_classes) {
+                (0, _) => ClassChangeKind::Added,
+                (_, 0) => ClassChangeKind::Removed,
+                _ => ClassChangeKind::Changed,
+            }
+        }
+    }
+
+    /**
+     * 两个版本的 dex 对比，类按 mapping 还原后的名称对应
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct DexDiff {
+        pub old_classes: usize,
+        pub new_classes: usize,
+        pub old_methods: u32,
+        pub new_methods: u32,
+        pub old_size: u64,
+        pub new_size: u64,
+        pub old_download: u64,
+        pub new_download: u64,
+        pub old_packages: usize,
+        pub new_packages: usize,
+        // 按大小变化的绝对值从大到小排序，没有变化的包和类不返回
+        pub packages: Vec<PackageChange>,
+        pub classes: Vec<ClassChange>,
+    }
+
+    impl DexDiff {
+        pub fn count(&self, kind: ClassChangeKind) -> usize {
+            self.classes.iter().filter(|class| class.kind == kind).count()
+        }
+
+        pub fn count_packages(&self, kind: ClassChangeKind) -> usize {
+            self.packages.iter().filter(|package| package.status() == kind).count()
+        }
+    }
+
+    // 一个类的方法数，自身大小和分摊的下载大小
+    #[derive(Debug, Clone, Copy, Default)]
+    struct ClassItem {
+        methods: u32,
+        size: u64,
+        download: u64,
+    }
+
+    impl ClassItem {
+        // 下载大小是分摊的，不参与比较
+        fn same(&self, other: &ClassItem) -> bool {
+            self.methods == other.methods && self.size == other.size
+        }
+    }
+
+    // 同名的类可能定义在多个 dex 中，合并为一个
+    fn class_sizes(classes: &[ClassSize]) -> BTreeMap<&str, ClassItem> {
+        let mut sizes: BTreeMap<&str, ClassItem> = BTreeMap::new();
+        for class in classes {
+            let item = sizes.entry(class.name.as_str()).or_default();
+            item.methods += class.methods;
+            item.size += class.own_size;
+            item.download += class.download;
+        }
+        sizes
+    }
+
+    /**
+     * 类所在的包，com.example.Foo$Bar 的包为 com.example
+     */
+    pub fn package_name(class: &str) -> &str {
+        match class.rfind('.') {
+            Some(index) => &class[..index],
+            None => DEFAULT_PACKAGE,
+        }
+    }
+
+    /**
+     * 对比两个版本的类，old 为旧的文件，new 为新的文件
+     */
+    pub fn diff_classes(old: &[ClassSize], new: &[ClassSize]) -> DexDiff {
+        let old_sizes = class_sizes(old);
+        let new_sizes = class_sizes(new);
+        let mut packages: BTreeMap<&str, PackageChange> = BTreeMap::new();
+        let mut classes = Vec::new();
+        for (name, item) in &old_sizes {
+            let package = packages.entry(package_name(name)).or_default();
+            package.old_classes += 1;
+            package.old_methods += item.methods;
+            package.old_size += item.size;
+            package.old_download += item.download;
+            if !new_sizes.contains_key(name) {
+                package.removed_classes += 1;
+                classes.push(class_change(ClassChangeKind::Removed, name, item, &ClassItem::default()));
+            }
+        }
+        for (name, item) in &new_sizes {
+            let package = packages.entry(package_name(name)).or_default();
+            package.new_classes += 1;
+            package.new_methods += item.methods;
+            package.new_size += item.size;
+            package.new_download += item.download;
+            match old_sizes.get(name) {
+                None => {
+                    package.added_classes += 1;
+                    classes.push(class_change(ClassChangeKind::Added, name, &ClassItem::default(), item));
+                }
+                Some(old_item) if !old_item.same(item) => {
+                    classes.push(class_change(ClassChangeKind::Changed, name, old_item, item));
+                }
+                Some(_) => {}
+            }
+        }
+
+        let old_packages = packages.values().filter(|package| package.old_classes > 0).count();
+        let new_packages = packages.values().filter(|package| package.new_classes > 0).count();
+        let mut packages: Vec<PackageChange> = packages
+            .into_iter()
+            .map(|(name, mut package)| {
+                package.package = name.to_string();
+                package.diff = package.new_size as i64 - package.old_size as i64;
+                package
+            })
+            .filter(|package| {
+                package.diff != 0 || package.method_diff() != 0 || package.added_classes + package.removed_classes > 0
+            })
+            .collect();
+        packages.sort_by(|a, b| {
+            b.diff
+                .abs()
+                .cmp(&a.diff.abs())
+                .then(b.method_diff().abs().cmp(&a.method_diff().abs()))
+                .then(a.package.cmp(&b.package))
+        });
+        classes.sort_by(|a, b| b.diff.abs().cmp(&a.diff.abs()).then(a.name.cmp(&b.name)));
+
+        DexDiff {
+            old_classes: old_sizes.len(),
+            new_classes: new_sizes.len(),
+            old_methods: old_sizes.values().map(|item| item.methods).sum(),
+            new_methods: new_sizes.values().map(|item| item.methods).sum(),
+            old_size: old_sizes.values().map(|item| item.size).sum(),
+            new_size: new_sizes.values().map(|item| item.size).sum(),
+            old_download: old_sizes.values().map(|item| item.download).sum(),
+            new_download: new_sizes.values().map(|item| item.download).sum(),
+            old_packages,
+            new_packages,
+            packages,
+            classes,
+        }
+    }
+
+    fn class_change(kind: ClassChangeKind, name: &str, old: &ClassItem, new: &ClassItem) -> ClassChange {
+        ClassChange {
+            kind,
+            name: name.to_string(),
+            package: package_name(name).to_string(),
+            old_methods: old.methods,
+            new_methods: new.methods,
+            old_size: old.size,
+            new_size: new.size,
+            diff: new.size as i64 - old.size as i64,
+            old_download: old.download,
+            new_download: new.download,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{diff_classes, package_name, ClassChangeKind, DEFAULT_PACKAGE};
+        use crate::cliper::apk_archive::archive::ClassSize;
+
+        // (类名, 自身字节, 分摊的下载大小, 方法数)
+        fn class(name: &str, own_size: u64, download: u64, methods: u32) -> ClassSize {
+            ClassSize {
+                name: name.to_string(),
+                obfuscated_name: name.to_string(),
+                container: "classes.dex".to_string(),
+                size: download * 2,
+                download,
+                own_size,
+                methods,
+                ..ClassSize::default()
+            }
+        }
+
+        #[test]
+        fn diffs_classes_and_packages() {
+            let old = [
+                class("com.a.Keep", 100, 40, 2),
+                class("com.a.Grow", 50, 20, 1),
+                class("com.b.Gone", 30, 12, 1),
+            ];
+            let new = [
+                class("com.a.Keep", 100, 35, 2),
+                class("com.a.Grow", 80, 30, 3),
+                class("com.c.New", 40, 15, 1),
+                class("Root", 10, 4, 1),
+            ];
+            let diff = diff_classes(&old, &new);
+            assert_eq!((diff.old_classes, diff.new_classes), (3, 4));
+            assert_eq!((diff.old_methods, diff.new_methods), (4, 7));
+            assert_eq!((diff.old_size, diff.new_size), (180, 230));
+            assert_eq!((diff.old_download, diff.new_download), (72, 84));
+            assert_eq!((diff.old_packages, diff.new_packages), (2, 3));
+
+            // 自身字节没有变化的 com.a.Keep 不返回，即使分摊的下载大小变了
+            let classes: Vec<(ClassChangeKind, &str, i64)> =
+                diff.classes.iter().map(|item| (item.kind, item.name.as_str(), item.diff)).collect();
+            assert_eq!(
+                classes,
+                vec![
+                    (ClassChangeKind::Added, "com.c.New", 40),
+                    (ClassChangeKind::Changed, "com.a.Grow", 30),
+                    (ClassChangeKind::Removed, "com.b.Gone", -30),
+                    (ClassChangeKind::Added, "Root", 10),
+                ]
+            );
+            assert_eq!(diff.classes[1].download_diff(), 10);
+            assert_eq!(diff.count(ClassChangeKind::Added), 2);
+
+            let packages: Vec<(ClassChangeKind, &str, i64, i64)> = diff
+                .packages
+                .iter()
+                .map(|item| (item.status(), item.package.as_str(), item.diff, item.method_diff()))
+                .collect();
+            assert_eq!(
+                packages,
+                vec![
+                    (ClassChangeKind::Added, "com.c", 40, 1),
+                    (ClassChangeKind::Changed, "com.a", 30, 2),
+                    (ClassChangeKind::Removed, "com.b", -30, -1),
+                    (ClassChangeKind::Added, DEFAULT_PACKAGE, 10, 1),
+                ]
+            );
+            assert_eq!(diff.packages[1].download_diff(), 5);
+            assert_eq!(diff.count_packages(ClassChangeKind::Added), 2);
+        }
+
+        #[test]
+        fn download_only_changes_are_not_reported() {
+            let old = [class("com.a.Foo", 100, 40, 2)];
+            let new = [class("com.a.Foo", 100, 55, 2)];
+            let diff = diff_classes(&old, &new);
+            assert!(diff.classes.is_empty());
+            assert!(diff.packages.is_empty());
+            assert_eq!(diff.new_download - diff.old_download, 15);
+        }
+
+        #[test]
+        fn classes_in_several_dex_files_are_merged() {
+            let old = [class("com.a.Foo", 100, 40, 2)];
+            let mut copy = class("com.a.Foo", 20, 8, 1);
+            copy.container = "classes2.dex".to_string();
+            let new = [class("com.a.Foo", 100, 40, 2), copy];
+            let diff = diff_classes(&old, &new);
+            assert_eq!(diff.new_classes, 1);
+            let foo = &diff.classes[0];
+            assert_eq!((foo.kind, foo.new_size, foo.new_methods), (ClassChangeKind::Changed, 120, 3));
+        }
+
+        /**
+         * 损坏的 dex 解析不出类，对比的一边为空
+         */
+        #[test]
+        fn empty_sides() {
+            let diff = diff_classes(&[], &[]);
+            assert!(diff.classes.is_empty() && diff.packages.is_empty());
+            assert_eq!((diff.old_size, diff.new_size, diff.old_packages), (0, 0, 0));
+
+            let classes = [class("com.a.Foo", 100, 40, 2), class("", 8, 2, 0)];
+            let diff = diff_classes(&classes, &[]);
+            assert_eq!(diff.count(ClassChangeKind::Removed), 2);
+            assert_eq!(diff.count_packages(ClassChangeKind::Removed), 2);
+            assert!(diff.classes.iter().all(|item| item.new_size == 0 && item.diff < 0));
+        }
+
+        #[test]
+        fn package_of_nested_and_default_classes() {
+            assert_eq!(package_name("com.example.Foo$Bar"), "com.example");
+            assert_eq!(package_name("Foo"), DEFAULT_PACKAGE);
+            assert_eq!(package_name(""), DEFAULT_PACKAGE);
+        }
+    }
+}
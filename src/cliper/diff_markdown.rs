@@ -0,0 +1,471 @@
+pub mod markdown {
+    use crate::cliper::apk_archive::archive::ApkDiff;
+    use crate::cliper::cliper_info::CliperInfo;
+    use crate::cliper::dex_diff::dex_compare::DexDiff;
+    use crate::cliper::manifest_diff::manifest_compare::ManifestChange;
+    use crate::cliper::native_diff::native_compare::NativeLibraryDiff;
+    use crate::cliper::resource_diff::resource_compare::ResourceDiff;
+    use crate::cliper::size_data::SizeData;
+
+    /**
+     * markdown 摘要的参数
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct MarkdownOptions {
+        // 旧文件和新文件的名称，显示在标题中
+        pub old_name: String,
+        pub new_name: String,
+        // 新增，删除和增长的文件各显示多少个，0 为全部显示
+        pub top: usize,
+        // 下载大小变化的绝对值小于这个值的文件不显示，资源，dex 和共享库的对比中是条目，类和符号的大小变化
+        pub threshold: u64,
+    }
+
+    /**
+     * 适合贴在 pull request 评论中的 GitHub 风格 markdown：
+     * 总大小和各分类的变化，新增，删除和增长最多的文件，完整列表折叠在 <details> 中
+     */
+    pub fn render(diff: &ApkDiff, options: &MarkdownOptions) -> String {
+        let mut out = String::new();
+        let old = &diff.old_summary;
+        let new = &diff.new_summary;
+        out.push_str(&format!(
+            "### Size diff: `{}` → `{}`\n\n",
+            options.old_name, options.new_name
+        ));
+        out.push_str(&format!("**Total: {}**\n\n", change(old.all, new.all)));
+
+        out.push_str("| Category | Old | New | Diff |\n");
+        out.push_str("| --- | ---: | ---: | ---: |\n");
+        let categories = [
+            ("Assets", old.asserts, new.asserts),
+            ("Res", old.res, new.res),
+            ("Code", old.code, new.code),
+            ("Native", old.native, new.native),
+            ("Others", old.others, new.others),
+            ("Overhead", old.overhead, new.overhead),
+        ];
+        for (name, old_size, new_size) in categories {
+            out.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                name,
+                SizeData::format_size(old_size),
+                SizeData::format_size(new_size),
+                change(old_size, new_size)
+            ));
+        }
+        out.push_str(&format!(
+            "| **All** | **{}** | **{}** | **{}** |\n\n",
+            SizeData::format_size(old.all),
+            SizeData::format_size(new.all),
+            change(old.all, new.all)
+        ));
+
+        let visible = |files: &[CliperInfo]| -> Vec<CliperInfo> {
+            files
+                .iter()
+                .filter(|file| file.diff.unsigned_abs() >= options.threshold)
+                .cloned()
+                .collect()
+        };
+        let mut added = visible(&diff.added);
+        let mut removed = visible(&diff.removed);
+        let mut updated = visible(&diff.updated);
+        added.sort_by(|a, b| b.diff.cmp(&a.diff).then(a.file_path.cmp(&b.file_path)));
+        removed.sort_by(|a, b| a.diff.cmp(&b.diff).then(a.file_path.cmp(&b.file_path)));
+        updated.sort_by(|a, b| b.diff.abs().cmp(&a.diff.abs()).then(a.file_path.cmp(&b.file_path)));
+        let grown: Vec<CliperInfo> = updated.iter().filter(|file| file.diff > 0).cloned().collect();
+
+        top_files(&mut out, "Added", &added, options.top);
+        top_files(&mut out, "Removed", &removed, options.top);
+        top_files(&mut out, "Grown", &grown, options.top);
+
+        let total = added.len() + removed.len() + updated.len();
+        if total > 0 {
+            out.push_str(&format!(
+                "<details>\n<summary>All changes ({} files)</summary>\n\n",
+                total
+            ));
+            out.push_str("| Change | File | Old | New | Diff |\n");
+            out.push_str("| --- | --- | ---: | ---: | ---: |\n");
+            let mut all: Vec<(&str, &CliperInfo)> = Vec::with_capacity(total);
+            all.extend(added.iter().map(|file| ("Added", file)));
+            all.extend(removed.iter().map(|file| ("Removed", file)));
+            all.extend(updated.iter().map(|file| ("Updated", file)));
+            all.sort_by(|a, b| b.1.diff.abs().cmp(&a.1.diff.abs()).then(a.1.file_path.cmp(&b.1.file_path)));
+            for (kind, file) in all {
+                // 删除的文件 download 是旧的大小，其余是新的大小
+                let (old_size, new_size) = match kind {
+                    "Added" => (0, file.download),
+                    "Removed" => (file.download, 0),
+                    _ => ((file.download as i64 - file.diff) as u64, file.download),
+                };
+                out.push_str(&format!(
+                    "| {} | {} | {} | {} | {} |\n",
+                    kind,
+                    code(&file.file_path),
+                    SizeData::format_size(old_size),
+                    SizeData::format_size(new_size),
+                    signed_size(file.diff)
+                ));
+            }
+            out.push_str("\n</details>\n\n");
+        }
+
+        let hidden = diff.added.len() + diff.removed.len() + diff.updated.len() - total;
+        if hidden > 0 {
+            out.push_str(&format!(
+                "_{} changes smaller than {} are hidden._\n",
+                hidden,
+                SizeData::format_size(options.threshold)
+            ));
+        }
+        out
+    }
+
+    /**
+     * 清单文件的变化，有风险的变化单独列在前面
+     */
+    pub fn render_manifest(changes: &[ManifestChange], options: &MarkdownOptions) -> String {
+        let mut out = String::new();
+        out.push_str(&format!(
+            "### Manifest diff: `{}` → `{}`\n\n",
+            options.old_name, options.new_name
+        ));
+        if changes.is_empty() {
+            out.push_str("No manifest changes.\n");
+            return out;
+        }
+        let risks: Vec<&ManifestChange> = changes.iter().filter(|change| !change.risk.is_empty()).collect();
+        if !risks.is_empty() {
+            out.push_str(&format!("**⚠️ {} security relevant changes**\n\n", risks.len()));
+            for change in &risks {
+                out.push_str(&format!("- {}: {} `{}`\n", change.risk, change.section, change.name));
+            }
+            out.push('\n');
+        }
+        out.push_str("| Change | Section | Name | Old | New | Risk |\n");
+        out.push_str("| --- | --- | --- | --- | --- | --- |\n");
+        for change in changes {
+            out.push_str(&format!(
+                "| {:?} | {} | {} | {} | {} | {} |\n",
+                change.kind,
+                change.section,
+                code(&change.name),
+                cell(&change.old),
+                cell(&change.new),
+                cell(&change.risk)
+            ));
+        }
+        out
+    }
+
+    /**
+     * resources.arsc 的变化：汇总，新增和删除的资源和配置，变化最大的条目，
+     * 大小变化小于 threshold 的条目不显示，完整列表折叠在 <details> 中
+     * entries 为旧的和新的条目数
+     */
+    pub fn render_resources(diff: &ResourceDiff, entries: (usize, usize), options: &MarkdownOptions) -> String {
+        let mut out = String::new();
+        out.push_str(&format!(
+            "### Resources diff: `{}` → `{}`\n\n",
+            options.old_name, options.new_name
+        ));
+        out.push_str(&format!("**resources.arsc: {}**\n\n", change(diff.old_size, diff.new_size)));
+        let summary = vec![
+            size_row("String Pool Bytes", diff.old_string_bytes as u64, diff.new_string_bytes as u64),
+            count_row("String Pool Strings", diff.old_string_count as u64, diff.new_string_count as u64),
+            count_row("Entries", entries.0 as u64, entries.1 as u64),
+        ];
+        table(&mut out, &["Item", "Old", "New", "Diff"], 1, &summary);
+
+        let types: Vec<Vec<String>> = diff
+            .types
+            .iter()
+            .filter(|item| item.added + item.removed > 0)
+            .map(|item| {
+                let mut row = count_row(&item.type_name, item.old_count as u64, item.new_count as u64);
+                row.push(format!("+{}", item.added));
+                row.push(format!("-{}", item.removed));
+                row
+            })
+            .collect();
+        top_rows(&mut out, "Types", &["Type", "Old", "New", "Diff", "Added", "Removed"], 1, &types, 0);
+
+        let mut names: Vec<Vec<String>> = Vec::new();
+        for (change, items) in [("Added", &diff.added), ("Removed", &diff.removed)] {
+            for (type_name, name) in items {
+                names.push(vec![change.to_string(), code(&format!("@{}/{}", type_name, name))]);
+            }
+        }
+        top_rows(&mut out, "Added and removed resources", &["Change", "Resource"], 2, &names, options.top);
+
+        let configs: Vec<Vec<String>> = diff
+            .configs
+            .iter()
+            .map(|item| {
+                let change = if item.added { "Added" } else { "Removed" };
+                vec![change.to_string(), code(&item.config), item.entries.to_string()]
+            })
+            .collect();
+        top_rows(&mut out, "Configurations", &["Change", "Config", "Entries"], 2, &configs, 0);
+
+        let visible: Vec<Vec<String>> = diff
+            .changed
+            .iter()
+            .filter(|item| item.diff.unsigned_abs() >= options.threshold)
+            .map(|item| {
+                let mut row = size_row(&code(&format!("@{}/{}", item.type_name, item.name)), item.old_size, item.new_size);
+                row.push(format!("{} → {}", item.old_configs, item.new_configs));
+                row
+            })
+            .collect();
+        let header = ["Resource", "Old", "New", "Diff", "Configs"];
+        top_rows(&mut out, "Grown and shrunk entries", &header, 1, &visible, options.top);
+        details(&mut out, "All changed entries", &header, 1, &visible);
+        hidden_note(&mut out, diff.changed.len() - visible.len(), options.threshold);
+        out
+    }
+
+    /**
+     * dex 的变化：汇总，变化最大的包和类，
+     * 大小变化小于 threshold 的包和类不显示，完整的类列表折叠在 <details> 中
+     */
+    pub fn render_dex(diff: &DexDiff, options: &MarkdownOptions) -> String {
+        let mut out = String::new();
+        out.push_str(&format!(
+            "### Dex diff: `{}` → `{}`\n\n",
+            options.old_name, options.new_name
+        ));
+        out.push_str(&format!("**Download: {}**\n\n", change(diff.old_download, diff.new_download)));
+        let summary = vec![
+            size_row("Size", diff.old_size, diff.new_size),
+            size_row("Download", diff.old_download, diff.new_download),
+            count_row("Methods", diff.old_methods as u64, diff.new_methods as u64),
+            count_row("Classes", diff.old_classes as u64, diff.new_classes as u64),
+            count_row("Packages", diff.old_packages as u64, diff.new_packages as u64),
+        ];
+        table(&mut out, &["Item", "Old", "New", "Diff"], 1, &summary);
+
+        let packages: Vec<Vec<String>> = diff
+            .packages
+            .iter()
+            .filter(|item| item.diff.unsigned_abs() >= options.threshold)
+            .map(|item| {
+                let mut row = vec![format!("{:?}", item.status())];
+                row.extend(size_row(&code(&item.package), item.old_size, item.new_size));
+                row.push(format!("+{} / -{}", item.added_classes, item.removed_classes));
+                row.push(signed_count(item.method_diff()));
+                row
+            })
+            .collect();
+        let header = ["Change", "Package", "Old", "New", "Diff", "Classes", "Methods"];
+        top_rows(&mut out, "Packages", &header, 2, &packages, options.top);
+
+        let classes: Vec<Vec<String>> = diff
+            .classes
+            .iter()
+            .filter(|item| item.diff.unsigned_abs() >= options.threshold)
+            .map(|item| {
+                let mut row = vec![format!("{:?}", item.kind)];
+                row.extend(size_row(&code(&item.name), item.old_size, item.new_size));
+                row.push(signed_count(item.new_methods as i64 - item.old_methods as i64));
+                row
+            })
+            .collect();
+        let header = ["Change", "Class", "Old", "New", "Diff", "Methods"];
+        top_rows(&mut out, "Classes", &header, 2, &classes, options.top);
+        details(&mut out, "All changed classes", &header, 2, &classes);
+        let hidden = diff.packages.len() - packages.len() + diff.classes.len() - classes.len();
+        hidden_note(&mut out, hidden, options.threshold);
+        out
+    }
+
+    /**
+     * 共享库的变化：每个库的大小和 strip 状态，变化最大的节和符号，
+     * 大小变化小于 threshold 的符号不显示，完整的符号列表折叠在 <details> 中
+     */
+    pub fn render_native(diffs: &[NativeLibraryDiff], options: &MarkdownOptions) -> String {
+        let mut out = String::new();
+        out.push_str(&format!(
+            "### Native diff: `{}` → `{}`\n\n",
+            options.old_name, options.new_name
+        ));
+        if diffs.is_empty() {
+            out.push_str("No native library changes.\n");
+            return out;
+        }
+        let stripped = |value: bool| if value { "yes" } else { "no" };
+        let libraries: Vec<Vec<String>> = diffs
+            .iter()
+            .map(|item| {
+                let note = if item.lost_stripping() { "⚠️ lost stripping" } else { "" };
+                vec![
+                    code(&item.path),
+                    format!("{} → {}", stripped(item.old_stripped), stripped(item.new_stripped)),
+                    note.to_string(),
+                    SizeData::format_size(item.old_size),
+                    SizeData::format_size(item.new_size),
+                    change(item.old_size, item.new_size),
+                    change(item.old_download, item.new_download),
+                ]
+            })
+            .collect();
+        let header = ["Library", "Stripped", "Note", "Old", "New", "Diff", "Download Diff"];
+        table(&mut out, &header, 3, &libraries);
+
+        let mut hidden = 0;
+        for item in diffs {
+            out.push_str(&format!("#### {}\n\n", code(&item.path)));
+            if !item.full_symbols {
+                out.push_str("_Stripped library, exported symbols only._\n\n");
+            }
+            let sections: Vec<Vec<String>> = item
+                .sections
+                .iter()
+                .map(|section| size_row(&code(&section.name), section.old_size, section.new_size))
+                .collect();
+            top_rows(&mut out, "Sections", &["Section", "Old", "New", "Diff"], 1, &sections, options.top);
+
+            let symbols: Vec<Vec<String>> = item
+                .symbols
+                .iter()
+                .filter(|symbol| symbol.diff.unsigned_abs() >= options.threshold)
+                .map(|symbol| {
+                    let mut row = vec![format!("{:?}", symbol.kind)];
+                    row.extend(size_row(&code(&symbol.name), symbol.old_size, symbol.new_size));
+                    row
+                })
+                .collect();
+            let header = ["Change", "Symbol", "Old", "New", "Diff"];
+            top_rows(&mut out, "Symbols", &header, 2, &symbols, options.top);
+            details(&mut out, "All changed symbols", &header, 2, &symbols);
+            hidden += item.symbols.len() - symbols.len();
+        }
+        hidden_note(&mut out, hidden, options.threshold);
+        out
+    }
+
+    /**
+     * 表格，前 text 列左对齐，其余的数字列右对齐
+     */
+    fn table(out: &mut String, header: &[&str], text: usize, rows: &[Vec<String>]) {
+        out.push_str(&format!("| {} |\n", header.join(" | ")));
+        let align: Vec<&str> = (0..header.len())
+            .map(|column| if column < text { "---" } else { "---:" })
+            .collect();
+        out.push_str(&format!("| {} |\n", align.join(" | ")));
+        for row in rows {
+            out.push_str(&format!("| {} |\n", row.join(" | ")));
+        }
+        out.push('\n');
+    }
+
+    // 只显示前 top 行的表格，没有行时不输出
+    fn top_rows(out: &mut String, title: &str, header: &[&str], text: usize, rows: &[Vec<String>], top: usize) {
+        if rows.is_empty() {
+            return;
+        }
+        let top = if top == 0 { rows.len() } else { top };
+        out.push_str(&format!("**{}** ({})\n\n", title, rows.len()));
+        table(out, header, text, &rows[..top.min(rows.len())]);
+        if rows.len() > top {
+            out.push_str(&format!("_… {} more_\n\n", rows.len() - top));
+        }
+    }
+
+    // 完整列表折叠在 <details> 中，没有行时不输出
+    fn details(out: &mut String, title: &str, header: &[&str], text: usize, rows: &[Vec<String>]) {
+        if rows.is_empty() {
+            return;
+        }
+        out.push_str(&format!("<details>\n<summary>{} ({})</summary>\n\n", title, rows.len()));
+        table(out, header, text, rows);
+        out.push_str("</details>\n\n");
+    }
+
+    fn hidden_note(out: &mut String, hidden: usize, threshold: u64) {
+        if hidden > 0 {
+            out.push_str(&format!(
+                "_{} changes smaller than {} are hidden._\n",
+                hidden,
+                SizeData::format_size(threshold)
+            ));
+        }
+    }
+
+    // 名称，旧的大小，新的大小，带箭头和百分比的变化
+    fn size_row(name: &str, old: u64, new: u64) -> Vec<String> {
+        vec![
+            name.to_string(),
+            SizeData::format_size(old),
+            SizeData::format_size(new),
+            change(old, new),
+        ]
+    }
+
+    // 名称，旧的数量，新的数量，带箭头和百分比的变化
+    fn count_row(name: &str, old: u64, new: u64) -> Vec<String> {
+        vec![
+            cell(name),
+            old.to_string(),
+            new.to_string(),
+            arrow(old, new, signed_count(new as i64 - old as i64)),
+        ]
+    }
+
+    fn top_files(out: &mut String, title: &str, files: &[CliperInfo], top: usize) {
+        if files.is_empty() {
+            return;
+        }
+        let top = if top == 0 { files.len() } else { top };
+        out.push_str(&format!("**{}** ({})\n\n", title, files.len()));
+        out.push_str("| File | Diff |\n");
+        out.push_str("| --- | ---: |\n");
+        for file in files.iter().take(top) {
+            out.push_str(&format!("| {} | {} |\n", code(&file.file_path), signed_size(file.diff)));
+        }
+        if files.len() > top {
+            out.push_str(&format!("| _… {} more_ | |\n", files.len() - top));
+        }
+        out.push('\n');
+    }
+
+    /**
+     * 带箭头和百分比的变化，例如 ↑ +1.20KB (+3.10%)
+     */
+    fn change(old: u64, new: u64) -> String {
+        arrow(old, new, signed_size(new as i64 - old as i64))
+    }
+
+    // 给格式化好的变化加上箭头和百分比
+    fn arrow(old: u64, new: u64, text: String) -> String {
+        let diff = new as i64 - old as i64;
+        if diff == 0 {
+            return "0".to_string();
+        }
+        let arrow = if diff > 0 { "↑" } else { "↓" };
+        if old == 0 {
+            return format!("{} {}", arrow, text);
+        }
+        format!("{} {} ({:+.2}%)", arrow, text, diff as f64 * 100.0 / old as f64)
+    }
+
+    // 表格中的路径，| 会被当成列分隔符
+    fn code(path: &str) -> String {
+        format!("`{}`", path.replace('|', "\\|"))
+    }
+
+    fn cell(text: &str) -> String {
+        text.replace('|', "\\|")
+    }
+
+    fn signed_count(diff: i64) -> String {
+        format!("{:+}", diff)
+    }
+
+    fn signed_size(diff: i64) -> String {
+        let sign = if diff < 0 { "-" } else { "+" };
+        format!("{}{}", sign, SizeData::format_size(diff.unsigned_abs()))
+    }
+}
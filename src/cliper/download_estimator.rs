@@ -0,0 +1,109 @@
+pub mod estimator {
+    use std::io::{self, Read, Seek, SeekFrom, Write};
+
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::apk_source::source::ApkSource;
+    use zip::read::ZipArchive;
+    use zip::CompressionMethod;
+
+    // 与商店分发时的压缩参数接近
+    const BROTLI_QUALITY: u32 = 9;
+    const BROTLI_WINDOW: u32 = 22;
+    const BROTLI_BUFFER: usize = 64 * 1024;
+
+    /**
+     * 下载大小与安装大小的估算结果
+     * raw_size 为文件大小，download_size 取 gzip 与 brotli 中较小的一个
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct DownloadEstimate {
+        pub raw_size: u64,
+        pub gzip_size: u64,
+        pub brotli_size: u64,
+        pub download_size: u64,
+        pub install_size: u64,
+    }
+
+    /**
+     * 只统计写入的字节数，不保存内容
+     */
+    #[derive(Default)]
+    struct CountingWriter {
+        count: u64,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.count += buf.len() as u64;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /**
+     * 离线估算下载大小
+     * 商店分发时会把 apk 中的文件解压后整体重新压缩，这里按同样的方式把所有文件解压后
+     * 连同文件名和 zip 尾部的签名块、中央目录一起，分别用 gzip 和 brotli 压缩整个流
+     *
+     * 安装大小 = 文件大小 + 安装时需要解压出来的 native 库（压缩存储的 .so）
+     */
+    pub fn estimate(source: &ApkSource) -> Result<DownloadEstimate, ApkError> {
+        let file = source.open()?;
+        let mut archive = ZipArchive::new(file)?;
+
+        let mut gzip = flate2::write::GzEncoder::new(
+            CountingWriter::default(),
+            flate2::Compression::best(),
+        );
+        let mut brotli = brotli::CompressorWriter::new(
+            CountingWriter::default(),
+            BROTLI_BUFFER,
+            BROTLI_QUALITY,
+            BROTLI_WINDOW,
+        );
+
+        let mut estimate = DownloadEstimate::default();
+        let mut extracted_native: u64 = 0;
+        let mut entries_end: u64 = 0;
+        let mut buffer = vec![0u8; BROTLI_BUFFER];
+
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i)?;
+            let name = file.name().to_string();
+            if name.ends_with(".so") && file.compression() != CompressionMethod::Stored {
+                extracted_native += file.size();
+            }
+
+            gzip.write_all(name.as_bytes())?;
+            brotli.write_all(name.as_bytes())?;
+            loop {
+                let len = file.read(&mut buffer)?;
+                if len == 0 {
+                    break;
+                }
+                gzip.write_all(&buffer[..len])?;
+                brotli.write_all(&buffer[..len])?;
+            }
+            entries_end = entries_end.max(file.data_start() + file.compressed_size());
+        }
+
+        // 签名块，中央目录，EOCD 原样参与压缩
+        let mut reader = archive.into_inner();
+        estimate.raw_size = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(entries_end.min(estimate.raw_size)))?;
+        let mut tail: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut tail)?;
+        gzip.write_all(&tail)?;
+        brotli.write_all(&tail)?;
+
+        estimate.gzip_size = gzip.finish()?.count;
+        estimate.brotli_size = brotli.into_inner().count;
+        estimate.download_size = estimate.gzip_size.min(estimate.brotli_size);
+        estimate.install_size = estimate.raw_size + extracted_native;
+
+        Ok(estimate)
+    }
+}
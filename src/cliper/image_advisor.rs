@@ -0,0 +1,247 @@
+pub mod advisor {
+    use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+    use image::{DynamicImage, GenericImageView, ImageFormat};
+
+    use crate::cliper::cliper_info::CliperInfo;
+
+    /**
+     * 单个图片的优化建议
+     * 大小均为文件原始字节数，None 表示该方式不适用
+     */
+    #[derive(Debug, Clone)]
+    pub struct ImageAdvice {
+        pub file_path: String,
+        pub format: String,
+        pub width: u32,
+        pub height: u32,
+        pub size: u64,
+        pub download: u64,
+        pub density: String,
+        pub optimized_size: Option<u64>,
+        pub webp_lossless_size: Option<u64>,
+        pub webp_lossy_size: Option<u64>,
+        // 有 alpha 通道但是所有像素都不透明
+        pub opaque_alpha: bool,
+        // 按所在的密度目录换算成 dp 后超过了上限
+        pub oversized: bool,
+    }
+
+    impl ImageAdvice {
+        /**
+         * 无损方式下最小的大小
+         */
+        pub fn best_lossless_size(&self) -> u64 {
+            [self.optimized_size, self.webp_lossless_size]
+                .iter()
+                .flatten()
+                .fold(self.size, |min, size| min.min(*size))
+        }
+
+        /**
+         * 包含有损 WebP 在内最小的大小
+         */
+        pub fn best_size(&self) -> u64 {
+            self.webp_lossy_size
+                .map_or(self.best_lossless_size(), |size| {
+                    size.min(self.best_lossless_size())
+                })
+        }
+
+        pub fn lossless_savings(&self) -> u64 {
+            self.size - self.best_lossless_size()
+        }
+
+        pub fn savings(&self) -> u64 {
+            self.size - self.best_size()
+        }
+    }
+
+    /**
+     * 判断文件是否是需要分析的图片
+     * .9.png 在打包时会被 aapt 编译，重新编码会丢失 9-patch 信息，所以跳过
+     */
+    pub fn is_image(info: &CliperInfo) -> bool {
+        let path = info.file_path.to_lowercase();
+        if path.ends_with(".9.png") {
+            return false;
+        }
+        path.ends_with(".png") || path.ends_with(".jpg") || path.ends_with(".jpeg")
+    }
+
+    /**
+     * 解码图片并估算重新压缩和转换成 WebP 之后的大小
+     * quality 为有损 WebP 的质量 0-100，max_dp 为图片最长边允许的 dp 值
+     * 无法解码的图片返回 None
+     */
+    pub fn analyze(
+        info: &CliperInfo,
+        content: &[u8],
+        quality: f32,
+        max_dp: u32,
+    ) -> Option<ImageAdvice> {
+        let format = image::guess_format(content).ok()?;
+        if format != ImageFormat::Png && format != ImageFormat::Jpeg {
+            return None;
+        }
+        let image = image::load_from_memory_with_format(content, format).ok()?;
+        let (width, height) = image.dimensions();
+
+        let opaque_alpha = image.color().has_alpha() && is_opaque(&image);
+        let density = read_density(&info.file_folder);
+        let oversized = density.as_ref().is_some_and(|(_, scale)| {
+            width.max(height) as f32 / scale > max_dp as f32
+        });
+
+        // jpeg 没有无损的重新压缩方式
+        let optimized_size = match format {
+            ImageFormat::Png => encode_png(&image).map(|data| data.len() as u64),
+            _ => None,
+        };
+        let webp_lossless_size = encode_webp(&image, None).map(|data| data.len() as u64);
+        let webp_lossy_size = encode_webp(&image, Some(quality)).map(|data| data.len() as u64);
+
+        Some(ImageAdvice {
+            file_path: info.file_path.clone(),
+            format: if format == ImageFormat::Png { "PNG" } else { "JPEG" }.to_string(),
+            width,
+            height,
+            size: content.len() as u64,
+            download: info.download,
+            density: density.map(|(name, _)| name).unwrap_or_default(),
+            optimized_size,
+            webp_lossless_size,
+            webp_lossy_size,
+            opaque_alpha,
+            oversized,
+        })
+    }
+
+    /**
+     * 生成可以替换原图的最小版本，返回新的文件路径和内容
+     * lossy 为 false 时只考虑无损方式，转换成 WebP 时修改文件扩展名
+     * 没有更小的版本时返回 None
+     */
+    pub fn best_variant(
+        advice: &ImageAdvice,
+        content: &[u8],
+        quality: f32,
+        lossy: bool,
+    ) -> Option<(String, Vec<u8>)> {
+        let image = image::load_from_memory(content).ok()?;
+        let mut candidates: Vec<(&str, Vec<u8>)> = Vec::new();
+        if advice.optimized_size.is_some() {
+            candidates.extend(encode_png(&image).map(|data| ("png", data)));
+        }
+        candidates.extend(encode_webp(&image, None).map(|data| ("webp", data)));
+        if lossy {
+            candidates.extend(encode_webp(&image, Some(quality)).map(|data| ("webp", data)));
+        }
+        let (ext, data) = candidates
+            .into_iter()
+            .filter(|(_, data)| (data.len() as u64) < advice.size)
+            .min_by_key(|(_, data)| data.len())?;
+
+        let stem = match advice.file_path.rfind('.') {
+            Some(index) => &advice.file_path[..index],
+            None => advice.file_path.as_str(),
+        };
+        Some((format!("{}.{}", stem, ext), data))
+    }
+
+    /**
+     * 无损重新编码 png
+     * 去掉全部不透明的 alpha 通道，灰度图使用灰度格式，最高压缩级别，自适应过滤
+     */
+    pub fn encode_png(image: &DynamicImage) -> Option<Vec<u8>> {
+        let reduced = reduce_color(image);
+        let mut data: Vec<u8> = Vec::new();
+        let encoder =
+            PngEncoder::new_with_quality(&mut data, CompressionType::Best, FilterType::Adaptive);
+        reduced.write_with_encoder(encoder).ok()?;
+        Some(data)
+    }
+
+    /**
+     * 编码成 WebP，quality 为 None 时使用无损模式
+     * 超过 WebP 尺寸上限 16383px 等编码失败的图片返回 None
+     */
+    pub fn encode_webp(image: &DynamicImage, quality: Option<f32>) -> Option<Vec<u8>> {
+        let (width, height) = image.dimensions();
+        // encode 和 encode_lossless 在编码失败时会 panic，这里直接调用 encode_simple
+        let (lossless, quality) = match quality {
+            Some(quality) => (false, quality),
+            None => (true, 75.0),
+        };
+        let memory = if image.color().has_alpha() && !is_opaque(image) {
+            let rgba = image.to_rgba8();
+            webp::Encoder::from_rgba(rgba.as_raw(), width, height).encode_simple(lossless, quality).ok()?
+        } else {
+            let rgb = image.to_rgb8();
+            webp::Encoder::from_rgb(rgb.as_raw(), width, height).encode_simple(lossless, quality).ok()?
+        };
+        Some(memory.to_vec())
+    }
+
+    /**
+     * 选择能无损表示图片的最小颜色格式，16 位图片保持不变
+     */
+    fn reduce_color(image: &DynamicImage) -> DynamicImage {
+        match image {
+            DynamicImage::ImageLuma8(_)
+            | DynamicImage::ImageLumaA8(_)
+            | DynamicImage::ImageRgb8(_)
+            | DynamicImage::ImageRgba8(_) => {}
+            _ => return image.clone(),
+        }
+        let opaque = !image.color().has_alpha() || is_opaque(image);
+        let gray = image
+            .to_rgba8()
+            .pixels()
+            .all(|pixel| pixel[0] == pixel[1] && pixel[1] == pixel[2]);
+        match (gray, opaque) {
+            (true, true) => DynamicImage::ImageLuma8(image.to_luma8()),
+            (true, false) => DynamicImage::ImageLumaA8(image.to_luma_alpha8()),
+            (false, true) => DynamicImage::ImageRgb8(image.to_rgb8()),
+            (false, false) => DynamicImage::ImageRgba8(image.to_rgba8()),
+        }
+    }
+
+    fn is_opaque(image: &DynamicImage) -> bool {
+        match image {
+            DynamicImage::ImageLumaA16(_) | DynamicImage::ImageRgba16(_) => {
+                image.to_rgba16().pixels().all(|pixel| pixel[3] == u16::MAX)
+            }
+            _ => image.to_rgba8().pixels().all(|pixel| pixel[3] == u8::MAX),
+        }
+    }
+
+    /**
+     * 读取资源目录的密度限定符和缩放比例
+     * res/drawable-xxhdpi-v4 -> (xxhdpi, 3.0)，没有限定符的 drawable 目录按 mdpi 处理
+     * nodpi，anydpi 以及非 drawable/mipmap 目录返回 None
+     */
+    fn read_density(file_folder: &str) -> Option<(String, f32)> {
+        let folder = file_folder.strip_prefix("base/").unwrap_or(file_folder);
+        let folder = folder.strip_prefix("res/")?;
+        let mut qualifiers = folder.split('-');
+        let res_type = qualifiers.next()?;
+        if res_type != "drawable" && res_type != "mipmap" {
+            return None;
+        }
+        for qualifier in qualifiers {
+            let scale = match qualifier {
+                "ldpi" => 0.75,
+                "mdpi" => 1.0,
+                "tvdpi" => 1.33,
+                "hdpi" => 1.5,
+                "xhdpi" => 2.0,
+                "xxhdpi" => 3.0,
+                "xxxhdpi" => 4.0,
+                "nodpi" | "anydpi" => return None,
+                _ => continue,
+            };
+            return Some((qualifier.to_string(), scale));
+        }
+        Some(("mdpi".to_string(), 1.0))
+    }
+}
@@ -0,0 +1,473 @@
+pub mod attribution {
+    use std::collections::{BTreeSet, HashMap};
+
+    use serde::Serialize;
+
+    use crate::app::dex_parser::dex::read_uleb128;
+    use crate::cliper::apk_archive::archive::{ApkArchive, ClassSize};
+    use crate::cliper::apk_cliper::size_reader;
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::cliper_info::CliperInfo;
+
+    // 无法归属的类和文件
+    pub const UNATTRIBUTED: &str = "Unattributed";
+    // 包名与清单文件中的包名相同的类
+    pub const APP: &str = "App";
+    // res/，assets/ 和 resources.arsc
+    pub const APP_RESOURCES: &str = "App resources";
+    // 签名，清单文件等
+    pub const OTHERS: &str = "Others";
+
+    /**
+     * 常用库的包名前缀，库名和 maven group
+     * group 用于把 META-INF/<group>_<artifact>.version 中的版本号关联到库
+     */
+    const PACKAGE_RULES: &[(&str, &str, &str)] = &[
+        ("okhttp3", "OkHttp", "com.squareup.okhttp3"),
+        ("okio", "Okio", "com.squareup.okio"),
+        ("retrofit2", "Retrofit", "com.squareup.retrofit2"),
+        ("com.squareup.moshi", "Moshi", "com.squareup.moshi"),
+        ("com.squareup.picasso", "Picasso", "com.squareup.picasso"),
+        ("com.squareup.wire", "Wire", "com.squareup.wire"),
+        ("leakcanary", "LeakCanary", "com.squareup.leakcanary"),
+        ("com.bumptech.glide", "Glide", "com.github.bumptech.glide"),
+        ("com.facebook.imagepipeline", "Fresco", "com.facebook.fresco"),
+        ("com.facebook.drawee", "Fresco", "com.facebook.fresco"),
+        ("com.facebook.react", "React Native", "com.facebook.react"),
+        ("com.facebook.hermes", "React Native", "com.facebook.react"),
+        ("com.facebook", "Facebook SDK", "com.facebook.android"),
+        ("com.google.firebase", "Firebase", "com.google.firebase"),
+        ("com.google.android.gms", "Play Services", "com.google.android.gms"),
+        ("com.google.android.play", "Play Core", "com.google.android.play"),
+        ("com.google.android.material", "Material Components", "com.google.android.material"),
+        ("com.google.gson", "Gson", "com.google.code.gson"),
+        ("com.google.protobuf", "Protobuf", "com.google.protobuf"),
+        ("com.google.common", "Guava", "com.google.guava"),
+        ("com.google.zxing", "ZXing", "com.google.zxing"),
+        ("com.google.android.exoplayer2", "ExoPlayer", "com.google.android.exoplayer"),
+        ("dagger", "Dagger", "com.google.dagger"),
+        ("javax.inject", "javax.inject", "javax.inject"),
+        ("com.airbnb.lottie", "Lottie", "com.airbnb.android"),
+        ("io.reactivex.rxjava3", "RxJava 3", "io.reactivex.rxjava3"),
+        ("io.reactivex", "RxJava", "io.reactivex.rxjava2"),
+        ("kotlinx.coroutines", "Kotlin Coroutines", ""),
+        ("kotlinx.serialization", "Kotlin Serialization", ""),
+        ("kotlin", "Kotlin Stdlib", "org.jetbrains.kotlin"),
+        ("org.jetbrains.annotations", "JetBrains Annotations", "org.jetbrains"),
+        ("io.sentry", "Sentry", "io.sentry"),
+        ("io.grpc", "gRPC", "io.grpc"),
+        ("io.flutter", "Flutter", "io.flutter"),
+        ("com.unity3d", "Unity", ""),
+        ("org.chromium.net", "Cronet", "org.chromium.net"),
+        ("timber.log", "Timber", "com.jakewharton.timber"),
+        ("org.greenrobot.eventbus", "EventBus", "org.greenrobot"),
+        ("com.tencent.mmkv", "MMKV", "com.tencent"),
+        ("com.tencent.mm.opensdk", "WeChat SDK", "com.tencent.mm.opensdk"),
+        ("com.alibaba.fastjson", "Fastjson", "com.alibaba"),
+        ("org.json", "org.json", ""),
+    ];
+
+    /**
+     * so 文件名前缀对应的库
+     */
+    const NATIVE_RULES: &[(&str, &str)] = &[
+        ("libflutter", "Flutter"),
+        ("libreactnativejni", "React Native"),
+        ("libhermes", "React Native"),
+        ("libjsc", "React Native"),
+        ("libfbjni", "React Native"),
+        ("libc++_shared", "C++ STL"),
+        ("libsentry", "Sentry"),
+        ("libmmkv", "MMKV"),
+        ("libtensorflowlite", "TensorFlow Lite"),
+        ("libunity", "Unity"),
+        ("libil2cpp", "Unity"),
+        ("libmain", "Unity"),
+        ("libcrashlytics", "Firebase"),
+        ("libconscrypt", "Conscrypt"),
+        ("libsqlcipher", "SQLCipher"),
+        ("librealm", "Realm"),
+        ("libimagepipeline", "Fresco"),
+        ("libnative-imagetranscoder", "Fresco"),
+        ("libopencv", "OpenCV"),
+        ("libcronet", "Cronet"),
+    ];
+
+    /**
+     * 包名前缀和 so 文件名前缀到库的映射，可以通过规则文件扩展
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct LibraryRules {
+        // (包名前缀，库名)
+        packages: Vec<(String, String)>,
+        // (so 文件名前缀，库名)
+        natives: Vec<(String, String)>,
+        // maven group -> 库名
+        groups: HashMap<String, String>,
+        // 清单文件中的包名
+        app_package: String,
+    }
+
+    impl LibraryRules {
+        /**
+         * 内置的规则
+         */
+        pub fn builtin() -> LibraryRules {
+            let mut rules = LibraryRules::default();
+            for (prefix, library, group) in PACKAGE_RULES {
+                rules.packages.push((prefix.to_string(), library.to_string()));
+                if !group.is_empty() {
+                    rules
+                        .groups
+                        .entry(group.to_string())
+                        .or_insert_with(|| library.to_string());
+                }
+            }
+            for (prefix, library) in NATIVE_RULES {
+                rules.natives.push((prefix.to_string(), library.to_string()));
+            }
+            rules
+        }
+
+        /**
+         * 读取规则文件，每行一条，# 开头为注释，优先于内置规则
+         *   com.example.sdk = Example SDK
+         *   so:libexample = Example SDK
+         */
+        pub fn load(&mut self, text: &str) -> Result<(), ApkError> {
+            for (number, line) in text.lines().enumerate() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let (prefix, library) = line
+                    .split_once('=')
+                    .map(|(prefix, library)| (prefix.trim(), library.trim()))
+                    .filter(|(prefix, library)| !prefix.is_empty() && !library.is_empty())
+                    .ok_or_else(|| {
+                        ApkError::InvalidFilter(format!(
+                            "invalid library rule at line {}: {}, expected `prefix = Library`",
+                            number + 1,
+                            line
+                        ))
+                    })?;
+                match prefix.strip_prefix("so:") {
+                    Some(native) => self.natives.insert(0, (native.to_string(), library.to_string())),
+                    None => self.packages.insert(0, (prefix.to_string(), library.to_string())),
+                }
+            }
+            Ok(())
+        }
+
+        pub fn set_app_package(&mut self, package: &str) {
+            self.app_package = package.to_string();
+        }
+
+        /**
+         * 按最长的包名前缀匹配类名，androidx 按前两段拆分成不同的库
+         */
+        pub fn match_class(&self, name: &str) -> Option<String> {
+            let matched = self
+                .packages
+                .iter()
+                .filter(|(prefix, _)| in_package(name, prefix))
+                // 长度相同时取靠前的，用户规则插入在前面
+                .fold(None::<&(String, String)>, |best, rule| match best {
+                    Some(best) if best.0.len() >= rule.0.len() => Some(best),
+                    _ => Some(rule),
+                });
+            if let Some((_, library)) = matched {
+                return Some(library.clone());
+            }
+            if let Some(rest) = name.strip_prefix("androidx.") {
+                let module = rest.split('.').next().unwrap_or_default();
+                return Some(format!("androidx.{}", module));
+            }
+            if !self.app_package.is_empty() && in_package(name, &self.app_package) {
+                return Some(APP.to_string());
+            }
+            None
+        }
+
+        pub fn match_native(&self, file_name: &str) -> Option<String> {
+            self.natives
+                .iter()
+                .find(|(prefix, _)| file_name.starts_with(prefix.as_str()))
+                .map(|(_, library)| library.clone())
+        }
+
+        fn library_for_group(&self, group: &str) -> Option<String> {
+            if let Some(library) = self.groups.get(group) {
+                return Some(library.clone());
+            }
+            if group.starts_with("androidx.") {
+                return Some(group.to_string());
+            }
+            None
+        }
+    }
+
+    fn in_package(name: &str, prefix: &str) -> bool {
+        name == prefix
+            || (name.starts_with(prefix) && name[prefix.len()..].starts_with(['.', '$']))
+    }
+
+    /**
+     * 一个库的大小
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct LibrarySize {
+        pub name: String,
+        // 发现的版本，例如 androidx.core:core:1.9.0
+        pub versions: Vec<String>,
+        pub classes: u64,
+        pub files: u64,
+        // 原始大小，dex 中的类为分摊后的大小
+        pub size: u64,
+        pub download: u64,
+    }
+
+    impl LibrarySize {
+        fn add(&mut self, size: u64, download: u64) {
+            self.size += size;
+            self.download += download;
+        }
+    }
+
+    /**
+     * 按库汇总 apk 中的类和文件，按下载大小从大到小排序
+     * 类按包名前缀归属，META-INF 中的 .version，.kotlin_module 和 .properties 提供版本号和额外的包名，
+     * so 按文件名归属，资源文件归为 App resources
+     */
+    pub fn attribute(apk: &ApkArchive, rules: &LibraryRules) -> Result<Vec<LibrarySize>, ApkError> {
+        let mut rules = rules.clone();
+        if let Ok(manifest) = apk.manifest() {
+            rules.set_app_package(&manifest.package_name);
+        }
+
+        let entries = apk.entries()?.collect::<Result<Vec<_>, _>>()?;
+        let mut versions: HashMap<String, BTreeSet<String>> = HashMap::new();
+        let mut marker_libraries: HashMap<String, String> = HashMap::new();
+        let markers: Vec<String> = entries
+            .iter()
+            .map(|entry| entry.file_path.clone())
+            .filter(|path| is_marker(path))
+            .collect();
+        if !markers.is_empty() {
+            size_reader::visit_detail_info_with_content(
+                apk.source(),
+                |info| is_marker(&info.file_path),
+                |info, content| {
+                    let marker = read_marker(&info.file_path, &content, &rules);
+                    if let Some(library) = marker.library {
+                        if let Some(version) = marker.version {
+                            versions.entry(library.clone()).or_default().insert(version);
+                        }
+                        for package in marker.packages {
+                            if rules.match_class(&package).is_none() {
+                                rules.packages.push((package, library.clone()));
+                            }
+                        }
+                        marker_libraries.insert(info.file_path, library);
+                    }
+                    Ok(())
+                },
+            )?;
+        }
+
+        let mut libraries: HashMap<String, LibrarySize> = HashMap::new();
+
+        let classes: Vec<ClassSize> = apk.classes()?;
+        for class in &classes {
+            let name = rules
+                .match_class(&class.name)
+                .unwrap_or_else(|| UNATTRIBUTED.to_string());
+            let library = library_entry(&mut libraries, &name);
+            library.classes += 1;
+            library.add(class.size, class.download);
+        }
+
+        for entry in &entries {
+            // dex 和 jar 中的类已经计算过
+            if entry.file_type == "Code" {
+                continue;
+            }
+            let name = marker_libraries
+                .get(&entry.file_path)
+                .cloned()
+                .unwrap_or_else(|| entry_library(entry, &rules));
+            let library = library_entry(&mut libraries, &name);
+            library.files += 1;
+            library.add(entry.size, entry.download);
+        }
+
+        let mut libraries: Vec<LibrarySize> = libraries
+            .into_values()
+            .map(|mut library| {
+                library.versions = versions
+                    .remove(&library.name)
+                    .map(|versions| versions.into_iter().collect())
+                    .unwrap_or_default();
+                library
+            })
+            .collect();
+        libraries.sort_by(|a, b| b.download.cmp(&a.download).then(a.name.cmp(&b.name)));
+        Ok(libraries)
+    }
+
+    fn library_entry<'a>(libraries: &'a mut HashMap<String, LibrarySize>, name: &str) -> &'a mut LibrarySize {
+        libraries.entry(name.to_string()).or_insert_with(|| LibrarySize {
+            name: name.to_string(),
+            ..LibrarySize::default()
+        })
+    }
+
+    fn entry_library(entry: &CliperInfo, rules: &LibraryRules) -> String {
+        let path = entry.file_path.as_str();
+        if entry.file_type == "Native" {
+            return rules
+                .match_native(&entry.name)
+                .unwrap_or_else(|| UNATTRIBUTED.to_string());
+        }
+        if entry.file_type == "Res" || entry.file_type == "Assets" {
+            return APP_RESOURCES.to_string();
+        }
+        // 根目录下按包名存放的 java 资源，例如 okhttp3/internal/publicsuffix/publicsuffixes.gz
+        if !path.starts_with("META-INF/") && path.contains('/') {
+            let package = entry.file_folder.replace('/', ".");
+            if let Some(library) = rules.match_class(&package) {
+                return library;
+            }
+        }
+        OTHERS.to_string()
+    }
+
+    fn is_marker(path: &str) -> bool {
+        (path.starts_with("META-INF/")
+            && (path.ends_with(".version") || path.ends_with(".kotlin_module")))
+            || (!path.contains('/') && path.ends_with(".properties"))
+    }
+
+    struct Marker {
+        library: Option<String>,
+        version: Option<String>,
+        // kotlin_module 中声明的包名
+        packages: Vec<String>,
+    }
+
+    fn read_marker(path: &str, content: &[u8], rules: &LibraryRules) -> Marker {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let text = String::from_utf8_lossy(content);
+        let mut marker = Marker {
+            library: None,
+            version: None,
+            packages: Vec::new(),
+        };
+        if let Some(stem) = file_name.strip_suffix(".version") {
+            // META-INF/androidx.core_core.version，内容为版本号
+            let (group, artifact) = stem.split_once('_').unwrap_or((stem, stem));
+            marker.library = rules.library_for_group(group).or_else(|| Some(format!("{}:{}", group, artifact)));
+            let version = text.trim();
+            if !version.is_empty() {
+                marker.version = Some(format!("{}:{}:{}", group, artifact, version));
+            }
+        } else if let Some(module) = file_name.strip_suffix(".kotlin_module") {
+            // 模块名没有规律，通过其中的包名归属
+            marker.packages = kotlin_module_packages(content);
+            marker.library = marker
+                .packages
+                .iter()
+                .find_map(|package| rules.match_class(package))
+                .or_else(|| Some(module.to_string()));
+        } else if let Some(stem) = file_name.strip_suffix(".properties") {
+            // play-services-basement.properties：version=18.1.0，client=play-services-basement
+            let properties: HashMap<&str, &str> = text
+                .lines()
+                .filter_map(|line| line.split_once('='))
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .collect();
+            let client = properties.get("client").copied().unwrap_or(stem);
+            marker.library = if client.starts_with("play-services") {
+                Some("Play Services".to_string())
+            } else if client.starts_with("firebase") {
+                Some("Firebase".to_string())
+            } else {
+                None
+            };
+            if let Some(version) = properties.get("version") {
+                marker.version = Some(format!("{}:{}", client, version));
+            }
+        }
+        marker
+    }
+
+    /**
+     * kotlin_module：版本号数组之后是 protobuf，字段 1 为 PackageParts，其中字段 1 为包名
+     */
+    fn kotlin_module_packages(content: &[u8]) -> Vec<String> {
+        let read_be = |offset: usize| {
+            content
+                .get(offset..offset + 4)
+                .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        };
+        let Some(version_count) = read_be(0) else {
+            return Vec::new();
+        };
+        let Some(start) = version_count.checked_mul(4).and_then(|size| size.checked_add(4)) else {
+            return Vec::new();
+        };
+        let mut packages = Vec::new();
+        for (field, part) in protobuf_fields(content, start, content.len()) {
+            if field != 1 {
+                continue;
+            }
+            for (field, name) in protobuf_fields(content, part.0, part.1) {
+                if field != 1 {
+                    continue;
+                }
+                let Some(bytes) = content.get(name.0..name.1) else {
+                    continue;
+                };
+                let package = String::from_utf8_lossy(bytes).to_string();
+                if !package.is_empty() && !packages.contains(&package) {
+                    packages.push(package);
+                }
+            }
+        }
+        packages
+    }
+
+    /**
+     * 读取 [start, end) 中长度分隔的字段，返回 (字段号, 数据的区间)，其他类型的字段跳过
+     */
+    fn protobuf_fields(content: &[u8], start: usize, end: usize) -> Vec<(u32, (usize, usize))> {
+        let mut fields = Vec::new();
+        let mut position = start;
+        while position < end {
+            let Some((key, len)) = read_uleb128(content, position) else {
+                break;
+            };
+            position += len;
+            let value_end = match key & 0x7 {
+                0 => read_uleb128(content, position).and_then(|(_, len)| position.checked_add(len)),
+                1 => position.checked_add(8),
+                2 => read_uleb128(content, position).and_then(|(size, len)| {
+                    let data_start = position.checked_add(len)?;
+                    let data_end = data_start.checked_add(size as usize)?;
+                    // 数据被截断时返回剩余的部分，数据的起点已经超出范围时不返回
+                    if data_start <= end {
+                        fields.push((key >> 3, (data_start, data_end.min(end))));
+                    }
+                    Some(data_end)
+                }),
+                5 => position.checked_add(4),
+                _ => None,
+            };
+            match value_end {
+                Some(value_end) if value_end <= end => position = value_end,
+                _ => break,
+            }
+        }
+        fields
+    }
+}
@@ -0,0 +1,314 @@
+pub mod manifest_compare {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use serde::Serialize;
+
+    use crate::app::apk_info::{ApkParsedInfo, ManifestComponent};
+
+    // 运行时权限和需要用户在设置中授予的特殊权限
+    const SENSITIVE_PERMISSIONS: [&str; 33] = [
+        "android.permission.ACCESS_BACKGROUND_LOCATION",
+        "android.permission.ACCESS_COARSE_LOCATION",
+        "android.permission.ACCESS_FINE_LOCATION",
+        "android.permission.ACTIVITY_RECOGNITION",
+        "android.permission.BIND_ACCESSIBILITY_SERVICE",
+        "android.permission.BIND_DEVICE_ADMIN",
+        "android.permission.BIND_NOTIFICATION_LISTENER_SERVICE",
+        "android.permission.BLUETOOTH_SCAN",
+        "android.permission.BODY_SENSORS",
+        "android.permission.CALL_PHONE",
+        "android.permission.CAMERA",
+        "android.permission.GET_ACCOUNTS",
+        "android.permission.MANAGE_EXTERNAL_STORAGE",
+        "android.permission.NEARBY_WIFI_DEVICES",
+        "android.permission.PACKAGE_USAGE_STATS",
+        "android.permission.PROCESS_OUTGOING_CALLS",
+        "android.permission.QUERY_ALL_PACKAGES",
+        "android.permission.READ_CALENDAR",
+        "android.permission.READ_CALL_LOG",
+        "android.permission.READ_CONTACTS",
+        "android.permission.READ_EXTERNAL_STORAGE",
+        "android.permission.READ_MEDIA_AUDIO",
+        "android.permission.READ_MEDIA_IMAGES",
+        "android.permission.READ_MEDIA_VIDEO",
+        "android.permission.READ_PHONE_NUMBERS",
+        "android.permission.READ_PHONE_STATE",
+        "android.permission.READ_SMS",
+        "android.permission.RECEIVE_SMS",
+        "android.permission.RECORD_AUDIO",
+        "android.permission.REQUEST_INSTALL_PACKAGES",
+        "android.permission.SEND_SMS",
+        "android.permission.SYSTEM_ALERT_WINDOW",
+        "android.permission.WRITE_EXTERNAL_STORAGE",
+    ];
+
+    // 值为 true 时降低安全性的 application 属性：(属性, 风险, targetSdkVersion 低于这个值时没有声明默认为 true)
+    // 例如 targetSdkVersion 28 以下默认允许明文 http，allowBackup 始终默认为 true
+    const RISKY_FLAGS: [(&str, &str, u32); 5] = [
+        ("application:usesCleartextTraffic", "cleartext http traffic allowed", 28),
+        ("application:debuggable", "debuggable build", 0),
+        ("application:allowBackup", "app data can be backed up with adb", u32::MAX),
+        ("application:testOnly", "test only build", 0),
+        ("application:requestLegacyExternalStorage", "legacy external storage access", 29),
+    ];
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub enum ManifestChangeKind {
+        Added,
+        Removed,
+        Changed,
+    }
+
+    /**
+     * 清单文件中的一项变化，risk 不为空时为需要安全评审的变化
+     */
+    #[derive(Debug, Clone, Serialize)]
+    pub struct ManifestChange {
+        pub kind: ManifestChangeKind,
+        // Permission，Component，Feature 或者 Attribute
+        pub section: String,
+        pub name: String,
+        pub old: String,
+        pub new: String,
+        pub risk: String,
+    }
+
+    impl ManifestChange {
+        fn new(kind: ManifestChangeKind, section: &str, name: &str, old: &str, new: &str) -> ManifestChange {
+            ManifestChange {
+                kind,
+                section: section.to_string(),
+                name: name.to_string(),
+                old: old.to_string(),
+                new: new.to_string(),
+                risk: String::new(),
+            }
+        }
+
+        fn with_risk(mut self, risk: &str) -> ManifestChange {
+            self.risk = risk.to_string();
+            self
+        }
+    }
+
+    /**
+     * 对比两个清单文件，old 为旧的文件，new 为新的文件
+     * 按 权限，组件，特性，属性 的顺序返回，有风险的变化排在每一部分的前面
+     */
+    pub fn diff_manifest(old: &ApkParsedInfo, new: &ApkParsedInfo) -> Vec<ManifestChange> {
+        let mut changes = Vec::new();
+        changes.extend(sorted(diff_permissions(old, new)));
+        changes.extend(sorted(diff_components(&old.components, &new.components)));
+        changes.extend(sorted(diff_features(old, new)));
+        changes.extend(sorted(diff_attributes(old, new)));
+        changes
+    }
+
+    fn sorted(mut changes: Vec<ManifestChange>) -> Vec<ManifestChange> {
+        changes.sort_by(|a, b| a.risk.is_empty().cmp(&b.risk.is_empty()).then(a.name.cmp(&b.name)));
+        changes
+    }
+
+    fn diff_permissions(old: &ApkParsedInfo, new: &ApkParsedInfo) -> Vec<ManifestChange> {
+        let old_set: BTreeSet<&String> = old.permissions.iter().collect();
+        let new_set: BTreeSet<&String> = new.permissions.iter().collect();
+        let mut changes = Vec::new();
+        for permission in new_set.difference(&old_set) {
+            let mut change = ManifestChange::new(ManifestChangeKind::Added, "Permission", permission, "", permission);
+            if SENSITIVE_PERMISSIONS.contains(&permission.as_str()) {
+                change = change.with_risk("sensitive permission");
+            }
+            changes.push(change);
+        }
+        for permission in old_set.difference(&new_set) {
+            changes.push(ManifestChange::new(
+                ManifestChangeKind::Removed,
+                "Permission",
+                permission,
+                permission,
+                "",
+            ));
+        }
+        changes
+    }
+
+    fn diff_components(old: &[ManifestComponent], new: &[ManifestComponent]) -> Vec<ManifestChange> {
+        let describe = |component: &ManifestComponent| {
+            let mut text = component.kind.clone();
+            if component.exported {
+                text.push_str(" exported");
+            }
+            if !component.permission.is_empty() {
+                text.push_str(&format!(" permission={}", component.permission));
+            }
+            text
+        };
+        let mut changes = Vec::new();
+        for component in new {
+            let old_component = old
+                .iter()
+                .find(|item| item.kind == component.kind && item.name == component.name);
+            match old_component {
+                None => {
+                    let mut change = ManifestChange::new(
+                        ManifestChangeKind::Added,
+                        "Component",
+                        &component.name,
+                        "",
+                        &describe(component),
+                    );
+                    if component.exported && component.permission.is_empty() {
+                        change = change.with_risk("new exported component without permission");
+                    } else if component.exported {
+                        change = change.with_risk("new exported component");
+                    }
+                    changes.push(change);
+                }
+                Some(old_component) if old_component != component => {
+                    let mut change = ManifestChange::new(
+                        ManifestChangeKind::Changed,
+                        "Component",
+                        &component.name,
+                        &describe(old_component),
+                        &describe(component),
+                    );
+                    if component.exported && !old_component.exported {
+                        change = change.with_risk("component is now exported");
+                    } else if component.exported && component.permission.is_empty() {
+                        change = change.with_risk("exported component lost its permission");
+                    }
+                    changes.push(change);
+                }
+                Some(_) => {}
+            }
+        }
+        for component in old {
+            if !new
+                .iter()
+                .any(|item| item.kind == component.kind && item.name == component.name)
+            {
+                changes.push(ManifestChange::new(
+                    ManifestChangeKind::Removed,
+                    "Component",
+                    &component.name,
+                    &describe(component),
+                    "",
+                ));
+            }
+        }
+        changes
+    }
+
+    /**
+     * 特性按名称对比，只改了 required 的记为 Changed
+     */
+    fn diff_features(old: &ApkParsedInfo, new: &ApkParsedInfo) -> Vec<ManifestChange> {
+        let key = |feature: &String| feature.trim_end_matches(" (optional)").to_string();
+        let old_map: BTreeMap<String, &String> = old.features.iter().map(|feature| (key(feature), feature)).collect();
+        let new_map: BTreeMap<String, &String> = new.features.iter().map(|feature| (key(feature), feature)).collect();
+        let mut changes = Vec::new();
+        for (name, feature) in &new_map {
+            match old_map.get(name) {
+                None => changes.push(ManifestChange::new(ManifestChangeKind::Added, "Feature", name, "", feature)),
+                Some(old_feature) if old_feature != feature => changes.push(ManifestChange::new(
+                    ManifestChangeKind::Changed,
+                    "Feature",
+                    name,
+                    old_feature,
+                    feature,
+                )),
+                Some(_) => {}
+            }
+        }
+        for (name, feature) in &old_map {
+            if !new_map.contains_key(name) {
+                changes.push(ManifestChange::new(ManifestChangeKind::Removed, "Feature", name, feature, ""));
+            }
+        }
+        changes
+    }
+
+    /**
+     * 属性按资源引用解析后的值比较，资源的值变化时即使引用没变也算作变化
+     * 引用资源的属性显示为 引用 (值)，例如 @bool/cleartext (true)
+     */
+    fn diff_attributes(old: &ApkParsedInfo, new: &ApkParsedInfo) -> Vec<ManifestChange> {
+        let names: BTreeSet<&String> = old.attributes.keys().chain(new.attributes.keys()).collect();
+        let (old_sdk, new_sdk) = (target_sdk(old), target_sdk(new));
+        let mut changes = Vec::new();
+        for name in names {
+            let old_value = old.attributes.get(name).map(|value| value.as_str());
+            let new_value = new.attributes.get(name).map(|value| value.as_str());
+            let old_resolved = old.resolved_attributes.get(name).map(|value| value.as_str()).or(old_value);
+            let new_resolved = new.resolved_attributes.get(name).map(|value| value.as_str()).or(new_value);
+            let kind = match (old_value, new_value) {
+                (None, Some(_)) => ManifestChangeKind::Added,
+                (Some(_), None) => ManifestChangeKind::Removed,
+                (Some(old_value), Some(new_value)) if old_value != new_value || old_resolved != new_resolved => {
+                    ManifestChangeKind::Changed
+                }
+                _ => continue,
+            };
+            let display = |value: Option<&str>, resolved: Option<&str>| match (value, resolved) {
+                (Some(value), Some(resolved)) if value != resolved => format!("{} ({})", value, resolved),
+                (value, _) => value.unwrap_or_default().to_string(),
+            };
+            let mut change = ManifestChange::new(
+                kind,
+                "Attribute",
+                name,
+                &display(old_value, old_resolved),
+                &display(new_value, new_resolved),
+            );
+            if let Some(risk) = attribute_risk(name, (old_resolved, old_sdk), (new_resolved, new_sdk)) {
+                change = change.with_risk(risk);
+            }
+            changes.push(change);
+        }
+        changes
+    }
+
+    /**
+     * 解析后的 targetSdkVersion，没有声明时与 minSdkVersion 相同，都没有时为 1
+     */
+    fn target_sdk(info: &ApkParsedInfo) -> u32 {
+        let resolved = |name: &str| {
+            info.resolved_attributes
+                .get(name)
+                .or_else(|| info.attributes.get(name))
+                .and_then(|value| value.parse::<u32>().ok())
+        };
+        resolved("uses-sdk:targetSdkVersion")
+            .or_else(|| resolved("uses-sdk:minSdkVersion"))
+            .unwrap_or(1)
+    }
+
+    /**
+     * old 和 new 为 (解析后的值, targetSdkVersion)，值为 None 时没有声明
+     */
+    fn attribute_risk(name: &str, old: (Option<&str>, u32), new: (Option<&str>, u32)) -> Option<&'static str> {
+        if let Some((_, risk, default_below)) = RISKY_FLAGS.iter().find(|(flag, _, _)| *flag == name) {
+            // 没有声明时使用系统的默认值，解析不出值的资源引用可能在某些配置下为 true
+            let enabled = |(value, target_sdk): (Option<&str>, u32)| match value {
+                None => target_sdk < *default_below,
+                Some(value) => value == "true" || value.starts_with('@'),
+            };
+            return (enabled(new) && !enabled(old)).then_some(*risk);
+        }
+        let (old, new) = (old.0.unwrap_or_default(), new.0.unwrap_or_default());
+        let number = |value: &str| value.parse::<i64>().ok();
+        match name {
+            "uses-sdk:minSdkVersion" => match (number(old), number(new)) {
+                (Some(old), Some(new)) if new < old => Some("minSdkVersion lowered"),
+                _ => None,
+            },
+            "uses-sdk:targetSdkVersion" => match (number(old), number(new)) {
+                (Some(old), Some(new)) if new < old => Some("targetSdkVersion lowered"),
+                _ => None,
+            },
+            "application:networkSecurityConfig" => Some("network security config changed"),
+            "manifest:sharedUserId" => Some("shared user id changed"),
+            _ => None,
+        }
+    }
+}
@@ -1,4 +1,23 @@
 pub mod apk_cliper;
 pub mod size_data;
 pub mod cliper_info;
-pub mod cmds;
\ No newline at end of file
+pub mod cmds;
+pub mod zip_layout;
+pub mod download_estimator;
+pub mod image_advisor;
+pub mod res_xml_analyzer;
+pub mod apk_extractor;
+pub mod apk_error;
+pub mod apk_source;
+pub mod apk_archive;
+pub mod library_attribution;
+pub mod module_map;
+pub mod size_history;
+pub mod compare_matrix;
+pub mod diff_markdown;
+pub mod manifest_diff;
+pub mod resource_diff;
+pub mod dex_diff;
+pub mod native_diff;
+pub mod apk_browser;
+pub mod size_report;
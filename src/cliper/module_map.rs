@@ -0,0 +1,340 @@
+pub mod modules {
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::Path;
+
+    use serde::Serialize;
+    use serde_json::Value;
+
+    use crate::cliper::apk_archive::archive::{ApkArchive, ClassSize};
+    use crate::cliper::apk_error::ApkError;
+
+    // 映射中找不到的类
+    pub const UNMAPPED: &str = "Unmapped";
+
+    /**
+     * 类名到 gradle 模块或外部依赖的映射
+     */
+    #[derive(Debug, Clone, Default)]
+    pub struct ModuleMap {
+        // 类名 -> 模块
+        classes: HashMap<String, String>,
+        // (包名前缀，模块)，来自 com.example.* 形式的条目
+        packages: Vec<(String, String)>,
+        // 模块 -> 版本，来自依赖报告
+        versions: HashMap<String, String>,
+    }
+
+    impl ModuleMap {
+        /**
+         * 按内容识别格式：json 对象 {"模块": ["类名", "包名.*"]}，json 数组 [{"module", "classes"}]，
+         * csv 的 模块,类名，或者 ./gradlew dependencies 的文本报告
+         * 依赖报告需要配合 jars 目录，从同名的 jar 和 aar 中读取类名
+         */
+        pub fn load(path: &str, jars: Option<&str>) -> Result<ModuleMap, ApkError> {
+            let text = fs::read_to_string(path)?;
+            let trimmed = text.trim_start();
+            let mut map = ModuleMap::default();
+            if trimmed.starts_with('{') || trimmed.starts_with('[') {
+                map.load_json(&text)?;
+            } else if text.contains("--- ") {
+                let dependencies = parse_dependency_report(&text);
+                let jars = jars.ok_or_else(|| {
+                    ApkError::InvalidInput(format!(
+                        "{} is a dependency report, please add --jars with the jar and aar files",
+                        path
+                    ))
+                })?;
+                map.load_jars(&dependencies, jars)?;
+            } else {
+                map.load_csv(&text)?;
+            }
+            Ok(map)
+        }
+
+        pub fn insert(&mut self, module: &str, class: &str) {
+            let pattern = class.trim();
+            match pattern.strip_suffix(".**").or_else(|| pattern.strip_suffix(".*")) {
+                Some(package) => self.packages.push((package.to_string(), module.to_string())),
+                None => {
+                    self.classes
+                        .entry(pattern.to_string())
+                        .or_insert_with(|| module.to_string());
+                }
+            }
+        }
+
+        fn load_json(&mut self, text: &str) -> Result<(), ApkError> {
+            let value: Value = serde_json::from_str(text)
+                .map_err(|e| ApkError::InvalidInput(format!("invalid module map: {}", e)))?;
+            let class_names = |value: &Value| -> Vec<String> {
+                value
+                    .as_array()
+                    .map(|classes| {
+                        classes
+                            .iter()
+                            .filter_map(|class| class.as_str().map(|class| class.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+            match value {
+                Value::Object(modules) => {
+                    for (module, classes) in &modules {
+                        for class in class_names(classes) {
+                            self.insert(module, &class);
+                        }
+                    }
+                }
+                Value::Array(modules) => {
+                    for item in &modules {
+                        let module = item.get("module").and_then(|module| module.as_str()).unwrap_or_default();
+                        if module.is_empty() {
+                            continue;
+                        }
+                        if let Some(version) = item.get("version").and_then(|version| version.as_str()) {
+                            self.versions.insert(module.to_string(), version.to_string());
+                        }
+                        for class in item.get("classes").map(class_names).unwrap_or_default() {
+                            self.insert(module, &class);
+                        }
+                    }
+                }
+                _ => {
+                    return Err(ApkError::InvalidInput(
+                        "invalid module map: expected a json object or array".to_string(),
+                    ))
+                }
+            }
+            Ok(())
+        }
+
+        fn load_csv(&mut self, text: &str) -> Result<(), ApkError> {
+            let mut reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(text.as_bytes());
+            for record in reader.records() {
+                let record = record?;
+                let (Some(module), Some(class)) = (record.get(0), record.get(1)) else {
+                    continue;
+                };
+                let (module, class) = (module.trim(), class.trim());
+                // 可选的表头
+                if module.is_empty() || class.is_empty() || (module == "module" && class == "class") {
+                    continue;
+                }
+                self.insert(module, class);
+            }
+            Ok(())
+        }
+
+        /**
+         * 在 jars 目录中查找依赖对应的 jar 或 aar，外部依赖按 artifact-version 匹配，
+         * 项目模块按模块名匹配，例如 :core 对应 core.jar 或 core-release.aar
+         */
+        fn load_jars(&mut self, dependencies: &[Dependency], jars: &str) -> Result<(), ApkError> {
+            let mut files = Vec::new();
+            collect_archives(Path::new(jars), &mut files)?;
+            for dependency in dependencies {
+                if !dependency.version.is_empty() {
+                    self.versions
+                        .insert(dependency.module.clone(), dependency.version.clone());
+                }
+                for file in files.iter().filter(|file| dependency.matches(file)) {
+                    let archive = ApkArchive::open(&file.display().to_string())?;
+                    for class in archive.classes()? {
+                        self.insert(&dependency.module, &class.name);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        /**
+         * 类所属的模块，内部类按外部类查找，再按最长的包名前缀查找
+         */
+        pub fn module_of(&self, class: &str) -> Option<&str> {
+            if let Some(module) = self.classes.get(class) {
+                return Some(module);
+            }
+            if let Some((outer, _)) = class.split_once('$') {
+                if let Some(module) = self.classes.get(outer) {
+                    return Some(module);
+                }
+            }
+            self.packages
+                .iter()
+                .filter(|(package, _)| {
+                    class.starts_with(package.as_str()) && class[package.len()..].starts_with('.')
+                })
+                .max_by_key(|(package, _)| package.len())
+                .map(|(_, module)| module.as_str())
+        }
+
+        pub fn version_of(&self, module: &str) -> Option<&str> {
+            self.versions.get(module).map(|version| version.as_str())
+        }
+    }
+
+    /**
+     * 依赖报告中的一个依赖，外部依赖为 group:artifact，项目模块为 :name
+     */
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Dependency {
+        pub module: String,
+        pub version: String,
+    }
+
+    impl Dependency {
+        fn matches(&self, file: &Path) -> bool {
+            let Some(stem) = file.file_stem().map(|stem| stem.to_string_lossy().to_string()) else {
+                return false;
+            };
+            match self.module.strip_prefix(':') {
+                Some(project) => {
+                    let name = project.rsplit(':').next().unwrap_or(project);
+                    stem == name || stem.starts_with(&format!("{}-", name))
+                }
+                None => {
+                    let artifact = self.module.rsplit(':').next().unwrap_or_default();
+                    stem == format!("{}-{}", artifact, self.version)
+                }
+            }
+        }
+    }
+
+    /**
+     * 解析 ./gradlew dependencies 的输出，去重后按出现顺序返回
+     *   +--- com.squareup.okhttp3:okhttp:4.10.0 -> 4.11.0 (*)
+     *   \--- project :core
+     */
+    pub fn parse_dependency_report(text: &str) -> Vec<Dependency> {
+        let mut dependencies: Vec<Dependency> = Vec::new();
+        for line in text.lines() {
+            let Some((_, spec)) = line.split_once("--- ") else {
+                continue;
+            };
+            let spec = spec
+                .trim()
+                .trim_end_matches(" (*)")
+                .trim_end_matches(" (c)")
+                .trim_end_matches(" (n)")
+                .trim();
+            let dependency = if let Some(project) = spec.strip_prefix("project ") {
+                Dependency {
+                    module: project.trim().to_string(),
+                    version: String::new(),
+                }
+            } else {
+                let (coordinate, resolved) = match spec.split_once(" -> ") {
+                    Some((coordinate, resolved)) => (coordinate.trim(), Some(resolved.trim())),
+                    None => (spec, None),
+                };
+                let parts: Vec<&str> = coordinate.split(':').collect();
+                if parts.len() < 2 {
+                    continue;
+                }
+                Dependency {
+                    module: format!("{}:{}", parts[0], parts[1]),
+                    version: resolved
+                        .or_else(|| parts.get(2).copied())
+                        .unwrap_or_default()
+                        .to_string(),
+                }
+            };
+            if !dependencies.iter().any(|item| item.module == dependency.module) {
+                dependencies.push(dependency);
+            }
+        }
+        dependencies
+    }
+
+    fn collect_archives(dir: &Path, files: &mut Vec<std::path::PathBuf>) -> Result<(), ApkError> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                collect_archives(&path, files)?;
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext == "jar" || ext == "aar")
+            {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * 一个模块的大小
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct ModuleSize {
+        pub module: String,
+        pub version: String,
+        pub classes: u64,
+        pub size: u64,
+        pub download: u64,
+    }
+
+    /**
+     * 按模块汇总类的大小，按下载大小从大到小排序
+     */
+    pub fn attribute_modules(classes: &[ClassSize], map: &ModuleMap) -> Vec<ModuleSize> {
+        let mut modules: HashMap<&str, ModuleSize> = HashMap::new();
+        for class in classes {
+            let module = map.module_of(&class.name).unwrap_or(UNMAPPED);
+            let item = modules.entry(module).or_insert_with(|| ModuleSize {
+                module: module.to_string(),
+                version: map.version_of(module).unwrap_or_default().to_string(),
+                ..ModuleSize::default()
+            });
+            item.classes += 1;
+            item.size += class.size;
+            item.download += class.download;
+        }
+        let mut modules: Vec<ModuleSize> = modules.into_values().collect();
+        modules.sort_by(|a, b| b.download.cmp(&a.download).then(a.module.cmp(&b.module)));
+        modules
+    }
+
+    /**
+     * 两个版本中同一个模块的大小，diff 为新减旧的下载大小
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct ModuleDiff {
+        pub module: String,
+        pub old: Option<ModuleSize>,
+        pub new: Option<ModuleSize>,
+        pub diff: i64,
+    }
+
+    /**
+     * 对比两个版本的模块大小，按变化的绝对值从大到小排序，没有变化的模块不返回
+     */
+    pub fn diff_modules(old: &[ModuleSize], new: &[ModuleSize]) -> Vec<ModuleDiff> {
+        let mut diffs: Vec<ModuleDiff> = Vec::new();
+        for module in new {
+            let old_module = old.iter().find(|item| item.module == module.module);
+            diffs.push(ModuleDiff {
+                module: module.module.clone(),
+                diff: module.download as i64 - old_module.map_or(0, |item| item.download as i64),
+                old: old_module.cloned(),
+                new: Some(module.clone()),
+            });
+        }
+        for module in old {
+            if !new.iter().any(|item| item.module == module.module) {
+                diffs.push(ModuleDiff {
+                    module: module.module.clone(),
+                    diff: -(module.download as i64),
+                    old: Some(module.clone()),
+                    new: None,
+                });
+            }
+        }
+        diffs.retain(|item| item.diff != 0);
+        diffs.sort_by(|a, b| b.diff.abs().cmp(&a.diff.abs()).then(a.module.cmp(&b.module)));
+        diffs
+    }
+}
@@ -0,0 +1,187 @@
+pub mod native_compare {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use serde::Serialize;
+
+    use crate::app::elf_parser::elf::{ElfFile, ElfSymbol};
+    use crate::cliper::apk_archive::archive::NativeLibrary;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub enum SymbolChangeKind {
+        Added,
+        Removed,
+        Changed,
+    }
+
+    /**
+     * 同名节的大小变化，同名的多个节合并计算
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct SectionChange {
+        pub name: String,
+        pub old_size: u64,
+        pub new_size: u64,
+        pub diff: i64,
+    }
+
+    /**
+     * 符号的变化，exported 为任意一边在 .dynsym 中导出
+     */
+    #[derive(Debug, Clone, Serialize)]
+    pub struct SymbolChange {
+        pub kind: SymbolChangeKind,
+        pub name: String,
+        pub function: bool,
+        pub exported: bool,
+        pub old_size: u64,
+        pub new_size: u64,
+        pub diff: i64,
+    }
+
+    /**
+     * 同一路径下内容不同的共享库
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct NativeLibraryDiff {
+        pub path: String,
+        pub old_size: u64,
+        pub new_size: u64,
+        pub old_download: u64,
+        pub new_download: u64,
+        pub old_stripped: bool,
+        pub new_stripped: bool,
+        // 两边都有 .symtab 时符号大小包含未导出的符号，否则只有 .dynsym
+        pub full_symbols: bool,
+        // 按变化的绝对值从大到小排序，没有变化的不返回
+        pub sections: Vec<SectionChange>,
+        pub symbols: Vec<SymbolChange>,
+    }
+
+    impl NativeLibraryDiff {
+        pub fn diff(&self) -> i64 {
+            self.new_size as i64 - self.old_size as i64
+        }
+
+        pub fn download_diff(&self) -> i64 {
+            self.new_download as i64 - self.old_download as i64
+        }
+
+        /**
+         * 旧的版本去掉了符号和调试信息，新的版本没有
+         */
+        pub fn lost_stripping(&self) -> bool {
+            self.old_stripped && !self.new_stripped
+        }
+    }
+
+    /**
+     * 对比两个版本中路径相同且 md5 不同的共享库，old 为旧的文件，new 为新的文件
+     * 按文件大小变化的绝对值从大到小排序，丢失 strip 的排在最前面
+     */
+    pub fn diff_native(old: &[(NativeLibrary, ElfFile)], new: &[(NativeLibrary, ElfFile)]) -> Vec<NativeLibraryDiff> {
+        let old_map: BTreeMap<&str, &(NativeLibrary, ElfFile)> =
+            old.iter().map(|item| (item.0.path.as_str(), item)).collect();
+        let mut diffs: Vec<NativeLibraryDiff> = new
+            .iter()
+            .filter_map(|(library, elf)| {
+                let (old_library, old_elf) = old_map.get(library.path.as_str())?;
+                (old_library.md5 != library.md5).then(|| diff_library(old_library, old_elf, library, elf))
+            })
+            .collect();
+        diffs.sort_by(|a, b| {
+            b.lost_stripping()
+                .cmp(&a.lost_stripping())
+                .then(b.diff().abs().cmp(&a.diff().abs()))
+                .then(a.path.cmp(&b.path))
+        });
+        diffs
+    }
+
+    fn diff_library(old: &NativeLibrary, old_elf: &ElfFile, new: &NativeLibrary, new_elf: &ElfFile) -> NativeLibraryDiff {
+        let full_symbols = old_elf.has_symtab() && new_elf.has_symtab();
+        NativeLibraryDiff {
+            path: new.path.clone(),
+            old_size: old.size,
+            new_size: new.size,
+            old_download: old.download,
+            new_download: new.download,
+            old_stripped: old_elf.is_stripped(),
+            new_stripped: new_elf.is_stripped(),
+            full_symbols,
+            sections: diff_sections(old_elf, new_elf),
+            symbols: diff_symbols(old_elf, new_elf, full_symbols),
+        }
+    }
+
+    fn diff_sections(old: &ElfFile, new: &ElfFile) -> Vec<SectionChange> {
+        let sizes = |elf: &ElfFile| {
+            let mut sizes: BTreeMap<String, u64> = BTreeMap::new();
+            for section in &elf.sections {
+                *sizes.entry(section.name.clone()).or_default() += section.size;
+            }
+            sizes
+        };
+        let old_sizes = sizes(old);
+        let new_sizes = sizes(new);
+        let names: BTreeSet<&String> = old_sizes.keys().chain(new_sizes.keys()).collect();
+        let mut changes: Vec<SectionChange> = names
+            .into_iter()
+            .map(|name| {
+                let old_size = old_sizes.get(name).copied().unwrap_or(0);
+                let new_size = new_sizes.get(name).copied().unwrap_or(0);
+                SectionChange {
+                    name: name.clone(),
+                    old_size,
+                    new_size,
+                    diff: new_size as i64 - old_size as i64,
+                }
+            })
+            .filter(|change| change.diff != 0)
+            .collect();
+        changes.sort_by(|a, b| b.diff.abs().cmp(&a.diff.abs()).then(a.name.cmp(&b.name)));
+        changes
+    }
+
+    /**
+     * 只有一边有 .symtab 时只比较导出的符号，否则 strip 的变化会让所有内部符号看起来都被删除或者新增
+     */
+    fn diff_symbols(old: &ElfFile, new: &ElfFile, full_symbols: bool) -> Vec<SymbolChange> {
+        let old_symbols = symbol_map(old, full_symbols);
+        let new_symbols = symbol_map(new, full_symbols);
+        let names: BTreeSet<&str> = old_symbols.keys().chain(new_symbols.keys()).copied().collect();
+        let mut changes = Vec::new();
+        for name in names {
+            let old_symbol = old_symbols.get(name);
+            let new_symbol = new_symbols.get(name);
+            let kind = match (old_symbol, new_symbol) {
+                (None, Some(_)) => SymbolChangeKind::Added,
+                (Some(_), None) => SymbolChangeKind::Removed,
+                (Some(old_symbol), Some(new_symbol)) if old_symbol.size != new_symbol.size => SymbolChangeKind::Changed,
+                _ => continue,
+            };
+            let old_size = old_symbol.map(|symbol| symbol.size).unwrap_or(0);
+            let new_size = new_symbol.map(|symbol| symbol.size).unwrap_or(0);
+            let symbol = new_symbol.or(old_symbol).copied();
+            changes.push(SymbolChange {
+                kind,
+                name: name.to_string(),
+                function: symbol.map(|symbol| symbol.function).unwrap_or_default(),
+                exported: old_symbol.is_some_and(|symbol| symbol.exported)
+                    || new_symbol.is_some_and(|symbol| symbol.exported),
+                old_size,
+                new_size,
+                diff: new_size as i64 - old_size as i64,
+            });
+        }
+        changes.sort_by(|a, b| b.diff.abs().cmp(&a.diff.abs()).then(a.name.cmp(&b.name)));
+        changes
+    }
+
+    fn symbol_map(elf: &ElfFile, full_symbols: bool) -> BTreeMap<&str, &ElfSymbol> {
+        elf.symbols
+            .iter()
+            .filter(|symbol| full_symbols || symbol.exported)
+            .map(|symbol| (symbol.name.as_str(), symbol))
+            .collect()
+    }
+}
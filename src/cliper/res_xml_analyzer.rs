@@ -0,0 +1,92 @@
+pub mod xml_analyzer {
+    use crate::app::android_xml::axml;
+    use crate::app::axml_walker::walker::{XmlElement, ANDROID_NAMESPACE};
+    use crate::cliper::cliper_info::CliperInfo;
+
+    /**
+     * 单个 res 下的 xml 文件的分析结果
+     * category 为资源类型，矢量图单独归为 vector
+     */
+    #[derive(Debug, Clone)]
+    pub struct XmlResInfo {
+        pub file_path: String,
+        pub category: String,
+        pub size: u64,
+        pub download: u64,
+        // 元素的最大嵌套深度，根节点为 1
+        pub depth: usize,
+        pub element_count: usize,
+        // 矢量图中最长的 pathData 字符数
+        pub max_path_length: usize,
+        // 解码后的文本 xml 的 md5
+        pub decoded_md5: String,
+    }
+
+    /**
+     * 判断是否是 res 目录下的 xml 文件
+     */
+    pub fn is_res_xml(info: &CliperInfo) -> bool {
+        (info.file_path.starts_with("res/") || info.file_path.starts_with("base/res/"))
+            && info.file_path.ends_with(".xml")
+    }
+
+    /**
+     * 解码二进制 xml 并统计嵌套深度，元素数量和 pathData 长度
+     * 不是二进制 xml 或者解析失败时返回 None
+     */
+    pub fn analyze(info: &CliperInfo, content: &[u8]) -> Option<XmlResInfo> {
+        let document = axml::parse_document(content).ok()?;
+        let root = &document.root;
+        let decoded = axml::format_document(&document, None);
+
+        let mut stats = XmlStats::default();
+        collect_stats(root, 1, &mut stats);
+
+        let res_type = read_res_type(&info.file_folder);
+        let category = if res_type == "drawable"
+            && (root.tag == "vector" || root.tag == "animated-vector")
+        {
+            "vector".to_string()
+        } else {
+            res_type
+        };
+
+        Some(XmlResInfo {
+            file_path: info.file_path.clone(),
+            category,
+            size: info.size,
+            download: info.download,
+            depth: stats.depth,
+            element_count: stats.element_count,
+            max_path_length: stats.max_path_length,
+            decoded_md5: format!("{:x}", md5::compute(decoded.as_bytes())),
+        })
+    }
+
+    #[derive(Default)]
+    struct XmlStats {
+        depth: usize,
+        element_count: usize,
+        max_path_length: usize,
+    }
+
+    fn collect_stats(e: &XmlElement, level: usize, stats: &mut XmlStats) {
+        stats.depth = stats.depth.max(level);
+        stats.element_count += 1;
+        if let Some(path_data) = e.attribute(ANDROID_NAMESPACE, "pathData") {
+            stats.max_path_length = stats.max_path_length.max(path_data.value().len());
+        }
+        for child in &e.children {
+            collect_stats(child, level + 1, stats);
+        }
+    }
+
+    /**
+     * 读取资源类型，res/layout-land -> layout
+     */
+    fn read_res_type(file_folder: &str) -> String {
+        let folder = file_folder.strip_prefix("base/").unwrap_or(file_folder);
+        let folder = folder.strip_prefix("res/").unwrap_or(folder);
+        folder.split('-').next().unwrap_or_default().to_string()
+    }
+}
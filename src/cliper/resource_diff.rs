@@ -0,0 +1,298 @@
+pub mod resource_compare {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use serde::Serialize;
+
+    use crate::app::arsc_parser::arsc::ResourceTable;
+
+    // 默认配置的显示名称
+    pub const DEFAULT_CONFIG: &str = "default";
+
+    /**
+     * 每种资源类型的数量变化
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct TypeChange {
+        pub type_name: String,
+        pub old_count: usize,
+        pub new_count: usize,
+        pub added: usize,
+        pub removed: usize,
+    }
+
+    /**
+     * 新增或者删除的配置，entries 为该配置下的条目数
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct ConfigChange {
+        pub config: String,
+        pub added: bool,
+        pub entries: usize,
+    }
+
+    /**
+     * 两个版本中都有的资源，大小为所有配置下条目的字节之和
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct EntryChange {
+        pub type_name: String,
+        pub name: String,
+        pub old_size: u64,
+        pub new_size: u64,
+        pub diff: i64,
+        pub old_configs: usize,
+        pub new_configs: usize,
+    }
+
+    /**
+     * 两个 resources.arsc 的差异，资源按 类型/名称 对应，不按 id，id 在不同构建之间会变化
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct ResourceDiff {
+        pub old_size: u64,
+        pub new_size: u64,
+        pub old_string_count: u32,
+        pub new_string_count: u32,
+        pub old_string_bytes: u32,
+        pub new_string_bytes: u32,
+        pub types: Vec<TypeChange>,
+        // (类型, 名称)，按类型和名称排序
+        pub added: Vec<(String, String)>,
+        pub removed: Vec<(String, String)>,
+        pub configs: Vec<ConfigChange>,
+        // 按变化的绝对值从大到小排序，没有变化的不返回
+        pub changed: Vec<EntryChange>,
+    }
+
+    // (类型, 名称) -> (字节数, 配置数)
+    type ResourceSizes = BTreeMap<(String, String), (u64, usize)>;
+
+    fn resource_sizes(table: &ResourceTable) -> ResourceSizes {
+        let mut sizes = ResourceSizes::new();
+        for entry in &table.entries {
+            let item = sizes
+                .entry((entry.type_name.clone(), entry.name.clone()))
+                .or_default();
+            item.0 += entry.size as u64;
+            item.1 += 1;
+        }
+        sizes
+    }
+
+    fn config_counts(table: &ResourceTable) -> BTreeMap<String, usize> {
+        let mut configs = BTreeMap::new();
+        for entry in &table.entries {
+            let config = if entry.config.is_empty() {
+                DEFAULT_CONFIG.to_string()
+            } else {
+                entry.config.clone()
+            };
+            *configs.entry(config).or_default() += 1;
+        }
+        configs
+    }
+
+    /**
+     * 对比两个资源表，old 为旧的文件，new 为新的文件
+     */
+    pub fn diff_resources(old: &ResourceTable, new: &ResourceTable) -> ResourceDiff {
+        let old_sizes = resource_sizes(old);
+        let new_sizes = resource_sizes(new);
+        let added: Vec<(String, String)> = new_sizes
+            .keys()
+            .filter(|key| !old_sizes.contains_key(*key))
+            .cloned()
+            .collect();
+        let removed: Vec<(String, String)> = old_sizes
+            .keys()
+            .filter(|key| !new_sizes.contains_key(*key))
+            .cloned()
+            .collect();
+
+        let type_names: BTreeSet<&String> = old_sizes
+            .keys()
+            .chain(new_sizes.keys())
+            .map(|(type_name, _)| type_name)
+            .collect();
+        let count = |sizes: &ResourceSizes, type_name: &str| sizes.keys().filter(|(name, _)| name == type_name).count();
+        let mut types: Vec<TypeChange> = type_names
+            .into_iter()
+            .map(|type_name| TypeChange {
+                type_name: type_name.clone(),
+                old_count: count(&old_sizes, type_name),
+                new_count: count(&new_sizes, type_name),
+                added: added.iter().filter(|(name, _)| name == type_name).count(),
+                removed: removed.iter().filter(|(name, _)| name == type_name).count(),
+            })
+            .collect();
+        types.sort_by(|a, b| (b.added + b.removed).cmp(&(a.added + a.removed)).then(a.type_name.cmp(&b.type_name)));
+
+        let old_configs = config_counts(old);
+        let new_configs = config_counts(new);
+        let mut configs: Vec<ConfigChange> = new_configs
+            .iter()
+            .filter(|(config, _)| !old_configs.contains_key(*config))
+            .map(|(config, entries)| ConfigChange {
+                config: config.clone(),
+                added: true,
+                entries: *entries,
+            })
+            .collect();
+        configs.extend(
+            old_configs
+                .iter()
+                .filter(|(config, _)| !new_configs.contains_key(*config))
+                .map(|(config, entries)| ConfigChange {
+                    config: config.clone(),
+                    added: false,
+                    entries: *entries,
+                }),
+        );
+
+        let mut changed: Vec<EntryChange> = new_sizes
+            .iter()
+            .filter_map(|(key, (new_size, new_count))| {
+                let (old_size, old_count) = old_sizes.get(key)?;
+                let diff = *new_size as i64 - *old_size as i64;
+                (diff != 0).then(|| EntryChange {
+                    type_name: key.0.clone(),
+                    name: key.1.clone(),
+                    old_size: *old_size,
+                    new_size: *new_size,
+                    diff,
+                    old_configs: *old_count,
+                    new_configs: *new_count,
+                })
+            })
+            .collect();
+        changed.sort_by(|a, b| {
+            b.diff
+                .abs()
+                .cmp(&a.diff.abs())
+                .then(a.type_name.cmp(&b.type_name))
+                .then(a.name.cmp(&b.name))
+        });
+
+        ResourceDiff {
+            old_size: old.size,
+            new_size: new.size,
+            old_string_count: old.string_count,
+            new_string_count: new.string_count,
+            old_string_bytes: old.string_bytes,
+            new_string_bytes: new.string_bytes,
+            types,
+            added,
+            removed,
+            configs,
+            changed,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::{diff_resources, DEFAULT_CONFIG};
+        use crate::app::arsc_parser::arsc::{ResourceEntry, ResourceTable};
+
+        // entries 为 (类型, 名称, 配置, 字节数)
+        fn table(entries: &[(&str, &str, &str, u32)]) -> ResourceTable {
+            let mut table = ResourceTable::default();
+            table.entries = entries
+                .iter()
+                .enumerate()
+                .map(|(index, (type_name, name, config, size))| ResourceEntry {
+                    id: 0x7f010000 + index as u32,
+                    type_name: type_name.to_string(),
+                    name: name.to_string(),
+                    config: config.to_string(),
+                    size: *size,
+                })
+                .collect();
+            table.size = table.entries.iter().map(|entry| entry.size as u64).sum();
+            table
+        }
+
+        fn pair(type_name: &str, name: &str) -> (String, String) {
+            (type_name.to_string(), name.to_string())
+        }
+
+        #[test]
+        fn diffs_names_configs_and_sizes() {
+            let old = table(&[
+                ("string", "app_name", "", 20),
+                ("string", "app_name", "fr", 21),
+                ("string", "legacy", "", 10),
+                ("color", "primary", "", 16),
+            ]);
+            let new = table(&[
+                ("string", "app_name", "", 30),
+                ("string", "app_name", "fr", 21),
+                ("string", "app_name", "de", 22),
+                ("string", "welcome", "", 12),
+                ("color", "primary", "", 16),
+            ]);
+            let diff = diff_resources(&old, &new);
+            assert_eq!((diff.old_size, diff.new_size), (67, 101));
+            assert_eq!(diff.added, vec![pair("string", "welcome")]);
+            assert_eq!(diff.removed, vec![pair("string", "legacy")]);
+
+            let types: Vec<(&str, usize, usize, usize, usize)> = diff
+                .types
+                .iter()
+                .map(|item| (item.type_name.as_str(), item.old_count, item.new_count, item.added, item.removed))
+                .collect();
+            assert_eq!(types, vec![("string", 2, 2, 1, 1), ("color", 1, 1, 0, 0)]);
+
+            let configs: Vec<(&str, bool, usize)> =
+                diff.configs.iter().map(|item| (item.config.as_str(), item.added, item.entries)).collect();
+            assert_eq!(configs, vec![("de", true, 1)]);
+
+            // 没有变化的 color/primary 不返回
+            assert_eq!(diff.changed.len(), 1);
+            let app_name = &diff.changed[0];
+            assert_eq!(pair(&app_name.type_name, &app_name.name), pair("string", "app_name"));
+            assert_eq!((app_name.old_size, app_name.new_size, app_name.diff), (41, 73, 32));
+            assert_eq!((app_name.old_configs, app_name.new_configs), (2, 3));
+        }
+
+        #[test]
+        fn changes_are_sorted_by_size() {
+            let old = table(&[("string", "a", "", 10), ("string", "b", "", 10), ("string", "c", "", 10)]);
+            let new = table(&[("string", "a", "", 12), ("string", "b", "", 2), ("string", "c", "", 15)]);
+            let names: Vec<(String, i64)> =
+                diff_resources(&old, &new).changed.into_iter().map(|item| (item.name, item.diff)).collect();
+            assert_eq!(names, vec![("b".to_string(), -8), ("c".to_string(), 5), ("a".to_string(), 2)]);
+        }
+
+        #[test]
+        fn identical_tables_have_no_changes() {
+            let old = table(&[("string", "app_name", "", 20), ("drawable", "icon", "xxhdpi", 16)]);
+            let diff = diff_resources(&old, &old.clone());
+            assert!(diff.added.is_empty() && diff.removed.is_empty());
+            assert!(diff.configs.is_empty() && diff.changed.is_empty());
+            assert!(diff.types.iter().all(|item| item.added == 0 && item.removed == 0));
+        }
+
+        /**
+         * 损坏的 resources.arsc 解析出的是空表，或者类型名和键名为空的条目
+         */
+        #[test]
+        fn empty_and_nameless_tables() {
+            let empty = ResourceTable::default();
+            let full = table(&[("string", "app_name", "", 20), ("string", "app_name", "fr", 21)]);
+            let diff = diff_resources(&empty, &full);
+            assert_eq!(diff.added, vec![pair("string", "app_name")]);
+            let configs: Vec<(&str, bool)> = diff.configs.iter().map(|item| (item.config.as_str(), item.added)).collect();
+            assert_eq!(configs, vec![(DEFAULT_CONFIG, true), ("fr", true)]);
+            assert!(diff.changed.is_empty());
+
+            let diff = diff_resources(&full, &empty);
+            assert_eq!(diff.removed, vec![pair("string", "app_name")]);
+            assert!(diff.configs.iter().all(|item| !item.added));
+
+            let nameless = table(&[("", "", "", 8), ("", "", "", 8)]);
+            let diff = diff_resources(&nameless, &table(&[("", "", "", 8)]));
+            assert_eq!(diff.changed[0].diff, -8);
+            assert_eq!((diff.changed[0].old_configs, diff.changed[0].new_configs), (2, 1));
+        }
+    }
+}
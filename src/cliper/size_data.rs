@@ -1,40 +1,56 @@
-#[derive(Debug)]
+use serde::Serialize;
+
+use crate::cliper::apk_cliper::size_reader::PackageKind;
+use crate::cliper::zip_layout::layout_reader::ArchiveOverhead;
+
+#[derive(Debug, Default, Serialize)]
 pub struct SizeData {
     // ... fields for fileName, version, asserts, res, code, native, others, all, etc.
     pub file_name: String,
+    pub kind: PackageKind,
     pub asserts: u64,
     pub res: u64,
     pub code: u64,
     pub native: u64,
     pub others: u64,
+    // zip 结构开销，文件头，中央目录，签名块等
+    pub overhead: u64,
+    pub overhead_detail: ArchiveOverhead,
     pub all: u64,
 }
 
 impl SizeData {
     // 原始数据转换成如果是1000以上的数据，转换成KB，如果是1000以上的数据，转换成MB, 保留2位小数
     pub fn convert_size(&self, size: u64) -> String {
+        SizeData::format_size(size)
+    }
+
+    pub fn format_size(size: u64) -> String {
         if size > 1000 {
             let kb = size as f64 / 1000.00;
             if kb > 1000.00 {
                 let mb = kb / 1000.00;
-                return format!("{:.2}MB", mb);
+                format!("{:.2}MB", mb)
             } else {
                 // 保留2位小数
-                return format!("{:.2}KB", kb);
+                format!("{:.2}KB", kb)
             }
         } else {
-            return format!("{}B", size);
+            format!("{}B", size)
         }
     }
 
     pub fn new() -> SizeData {
         SizeData {
             file_name: String::new(),
+            kind: PackageKind::Apk,
             asserts: 0,
             res: 0,
             code: 0,
             native: 0,
             others: 0,
+            overhead: 0,
+            overhead_detail: ArchiveOverhead::default(),
             all: 0,
         }
     }
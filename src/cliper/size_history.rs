@@ -0,0 +1,232 @@
+pub mod history {
+    use std::collections::BTreeMap;
+    use std::fmt;
+    use std::fs;
+    use std::path::Path;
+
+    use redb::{Database, ReadableDatabase, ReadableTable, TableDefinition};
+    use serde::{Deserialize, Serialize};
+
+    use crate::cliper::apk_archive::archive::ApkArchive;
+    use crate::cliper::apk_error::ApkError;
+
+    // id -> json 格式的快照，字段增加时旧的记录仍然可以读取
+    const SNAPSHOTS: TableDefinition<u64, &str> = TableDefinition::new("snapshots");
+
+    // 分类的顺序与 summary 的表格一致
+    pub const CATEGORIES: [&str; 6] = ["Assets", "Res", "Code", "Native", "Others", "Overhead"];
+
+    /**
+     * 历史记录数据库的错误
+     */
+    #[derive(Debug)]
+    pub enum HistoryError {
+        // 数据库无法打开或者读写
+        Redb(redb::Error),
+        // 快照无法转换成 json 或者记录中的 json 无法解析
+        Snapshot(serde_json::Error),
+    }
+
+    impl fmt::Display for HistoryError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                HistoryError::Redb(err) => write!(f, "{}", err),
+                HistoryError::Snapshot(err) => write!(f, "invalid snapshot: {}", err),
+            }
+        }
+    }
+
+    impl std::error::Error for HistoryError {
+        fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+            match self {
+                HistoryError::Redb(err) => Some(err),
+                HistoryError::Snapshot(err) => Some(err),
+            }
+        }
+    }
+
+    /**
+     * 一次构建的大小快照，大小都是下载大小
+     */
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    #[serde(default)]
+    pub struct SizeSnapshot {
+        pub id: u64,
+        // 例如 git describe 的输出
+        pub label: String,
+        // unix 时间戳，单位为秒
+        pub timestamp: i64,
+        pub file_name: String,
+        pub package_name: String,
+        pub version_code: String,
+        pub version_name: String,
+        // 分类 -> 大小，见 CATEGORIES
+        pub categories: BTreeMap<String, u64>,
+        // 目录 -> 大小，根目录的文件记为 /
+        pub folders: BTreeMap<String, u64>,
+        pub total: u64,
+    }
+
+    impl SizeSnapshot {
+        /**
+         * 读取 apk 的版本，分类大小和目录大小，没有清单文件的 jar 版本为空
+         */
+        pub fn from_archive(apk: &ApkArchive, label: &str, timestamp: i64) -> Result<SizeSnapshot, ApkError> {
+            let summary = apk.summary()?;
+            let manifest = match apk.manifest() {
+                Ok(manifest) => Some(manifest),
+                Err(ApkError::MissingManifest) => None,
+                Err(e) => return Err(e),
+            };
+            let mut snapshot = SizeSnapshot {
+                label: label.to_string(),
+                timestamp,
+                file_name: summary.file_name.clone(),
+                total: summary.all,
+                ..SizeSnapshot::default()
+            };
+            if let Some(manifest) = manifest {
+                snapshot.package_name = manifest.package_name;
+                snapshot.version_code = manifest.version_code;
+                snapshot.version_name = manifest.version_name;
+            }
+            let sizes = [
+                summary.asserts,
+                summary.res,
+                summary.code,
+                summary.native,
+                summary.others,
+                summary.overhead,
+            ];
+            for (category, size) in CATEGORIES.iter().zip(sizes) {
+                snapshot.categories.insert(category.to_string(), size);
+            }
+            for entry in apk.entries()? {
+                let entry = entry?;
+                let folder = if entry.file_folder.is_empty() {
+                    "/".to_string()
+                } else {
+                    entry.file_folder
+                };
+                *snapshot.folders.entry(folder).or_default() += entry.download;
+            }
+            Ok(snapshot)
+        }
+
+        pub fn category(&self, category: &str) -> u64 {
+            self.categories.get(category).copied().unwrap_or(0)
+        }
+
+        /**
+         * 版本名和版本号，例如 1.2.0 (120)
+         */
+        pub fn version(&self) -> String {
+            match (self.version_name.is_empty(), self.version_code.is_empty()) {
+                (true, true) => String::new(),
+                (false, true) => self.version_name.clone(),
+                (true, false) => format!("({})", self.version_code),
+                (false, false) => format!("{} ({})", self.version_name, self.version_code),
+            }
+        }
+    }
+
+    /**
+     * 保存在本地 redb 文件中的快照
+     */
+    pub struct SizeHistory {
+        db: Database,
+    }
+
+    impl SizeHistory {
+        /**
+         * 打开数据库，文件或者父目录不存在时创建
+         */
+        pub fn open(path: &str) -> Result<SizeHistory, ApkError> {
+            if let Some(parent) = Path::new(path).parent() {
+                if !parent.as_os_str().is_empty() && !parent.exists() {
+                    fs::create_dir_all(parent)?;
+                }
+            }
+            let db = Database::create(path).map_err(redb::Error::from)?;
+            Ok(SizeHistory { db })
+        }
+
+        /**
+         * 追加一条快照，返回分配的 id
+         */
+        pub fn record(&self, snapshot: &mut SizeSnapshot) -> Result<u64, ApkError> {
+            let transaction = self.db.begin_write().map_err(redb::Error::from)?;
+            {
+                let mut table = transaction.open_table(SNAPSHOTS).map_err(redb::Error::from)?;
+                let last = table.last().map_err(redb::Error::from)?.map(|(id, _)| id.value());
+                snapshot.id = last.map_or(1, |id| id + 1);
+                let value = serde_json::to_string(snapshot).map_err(HistoryError::Snapshot)?;
+                table
+                    .insert(snapshot.id, value.as_str())
+                    .map_err(redb::Error::from)?;
+            }
+            transaction.commit().map_err(redb::Error::from)?;
+            Ok(snapshot.id)
+        }
+
+        /**
+         * 最近的 limit 条快照，按记录顺序从旧到新，limit 为 0 时返回全部
+         */
+        pub fn latest(&self, limit: usize) -> Result<Vec<SizeSnapshot>, ApkError> {
+            let transaction = self.db.begin_read().map_err(redb::Error::from)?;
+            let table = match transaction.open_table(SNAPSHOTS) {
+                Ok(table) => table,
+                // 还没有记录过
+                Err(redb::TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+                Err(e) => return Err(redb::Error::from(e).into()),
+            };
+            let mut snapshots = Vec::new();
+            for item in table.iter().map_err(redb::Error::from)?.rev() {
+                if limit > 0 && snapshots.len() >= limit {
+                    break;
+                }
+                let (_, value) = item.map_err(redb::Error::from)?;
+                let snapshot: SizeSnapshot = serde_json::from_str(value.value()).map_err(HistoryError::Snapshot)?;
+                snapshots.push(snapshot);
+            }
+            snapshots.reverse();
+            Ok(snapshots)
+        }
+    }
+
+    /**
+     * 一个分类或者目录在第一条和最后一条快照之间的变化
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct SizeMover {
+        pub name: String,
+        pub first: u64,
+        pub last: u64,
+        pub diff: i64,
+    }
+
+    /**
+     * 对比两组大小，按变化的绝对值从大到小排序，没有变化的不返回
+     */
+    pub fn movers(first: &BTreeMap<String, u64>, last: &BTreeMap<String, u64>) -> Vec<SizeMover> {
+        let mut names: Vec<&String> = first.keys().chain(last.keys()).collect();
+        names.sort();
+        names.dedup();
+        let mut movers: Vec<SizeMover> = names
+            .into_iter()
+            .map(|name| {
+                let old = first.get(name).copied().unwrap_or(0);
+                let new = last.get(name).copied().unwrap_or(0);
+                SizeMover {
+                    name: name.clone(),
+                    first: old,
+                    last: new,
+                    diff: new as i64 - old as i64,
+                }
+            })
+            .filter(|mover| mover.diff != 0)
+            .collect();
+        movers.sort_by(|a, b| b.diff.abs().cmp(&a.diff.abs()).then(a.name.cmp(&b.name)));
+        movers
+    }
+}
@@ -0,0 +1,288 @@
+pub mod report {
+    use std::fs::{self, File};
+    use std::path::Path;
+
+    use csv::Writer;
+    use prettytable::{row, Cell, Row, Table};
+    use regex::Regex;
+
+    use crate::cliper::apk_archive::archive::ApkDiff;
+    use crate::cliper::apk_error::ApkError;
+    use crate::cliper::cliper_info::CliperInfo;
+    use crate::cliper::cmds::DetailOpts;
+    use crate::cliper::download_estimator::estimator::DownloadEstimate;
+    use crate::cliper::size_data::SizeData;
+
+    /**
+     * 文件过滤条件，正则只在创建时编译一次，不合法时返回 InvalidFilter
+     */
+    pub struct EntryFilter<'a> {
+        detail: &'a DetailOpts,
+        regex: Option<Regex>,
+    }
+
+    impl<'a> EntryFilter<'a> {
+        pub fn new(detail: &'a DetailOpts) -> Result<EntryFilter<'a>, ApkError> {
+            let regex = if detail.filter_regex.is_empty() {
+                None
+            } else {
+                let regex = Regex::new(detail.filter_regex.as_str()).map_err(|e| {
+                    ApkError::InvalidFilter(format!("--filter-regex \"{}\": {}", detail.filter_regex, e))
+                })?;
+                Some(regex)
+            };
+            Ok(EntryFilter { detail, regex })
+        }
+
+        /**
+         * 满足所有过滤条件的返回 true，没有设置的条件不过滤
+         */
+        pub fn matches(&self, info: &CliperInfo) -> bool {
+            let detail = self.detail;
+            // 路径不为空并且不是以过滤路径开头的，不满足条件
+            let filter_path_enable = !detail.filter_path.is_empty() && !info.file_path.starts_with(&detail.filter_path);
+            // 大小不为0并且下载大小小于过滤大小，不满足条件
+            let filter_size_enable = detail.filter_size > 0 && info.download < detail.filter_size;
+            // 后缀不为空并且不是以过滤后缀结尾的，不满足条件
+            let filter_ext_enable = !detail.filter_ext.is_empty() && !info.file_path.ends_with(&detail.filter_ext);
+            // 类型不为空并且不是过滤类型，不满足条件
+            let filter_type_enable = !detail.filter_type.is_empty() && info.file_type != detail.filter_type;
+            // 正则不为空并且 file_path 不匹配，不满足条件
+            let filter_regex_enable = self
+                .regex
+                .as_ref()
+                .is_some_and(|regex| !regex.is_match(&info.file_path));
+            !(filter_path_enable || filter_size_enable || filter_ext_enable || filter_type_enable || filter_regex_enable)
+        }
+    }
+
+    /**
+     * 只保留表头和前 limit 行，limit 为 0 时保留全部
+     */
+    pub fn limit_table(table: &Table, limit: usize) -> Table {
+        let rows = if limit == 0 { table.len() } else { limit + 1 };
+        let mut limited_table = Table::new();
+        for row in table.row_iter().take(rows) {
+            limited_table.add_row(Row::new(
+                row.iter()
+                    .map(|cell| Cell::new(&cell.get_content()))
+                    .collect(),
+            ));
+        }
+        limited_table
+    }
+
+    /**
+     * 把表格写入 csv 文件，已存在的文件会被覆盖，表格为空时不写
+     */
+    pub fn write_csv(table: &Table, output: &str) -> Result<(), ApkError> {
+        if table.is_empty() {
+            return Ok(());
+        }
+        if fs::metadata(output).is_ok() {
+            fs::remove_file(output)?;
+        }
+        // 父目录不存在则创建
+        if let Some(parent) = Path::new(output).parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let mut wtr = Writer::from_writer(File::create(output)?);
+        for row in table.row_iter() {
+            let v: Vec<String> = row.iter().map(|cell| cell.get_content()).collect();
+            wtr.write_record(&v)?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    /**
+     * 分类汇总，大小按 SizeData 的单位转换
+     */
+    pub fn summary_table(value: &SizeData) -> Table {
+        let mut table = Table::new();
+        table.add_row(row![
+            "Assets", "Res", "Code", "Native", "Others", "Overhead", "All"
+        ]);
+        table.add_row(Row::new(vec![
+            Cell::new(&value.convert_size(value.asserts)),
+            Cell::new(&value.convert_size(value.res)),
+            Cell::new(&value.convert_size(value.code)),
+            Cell::new(&value.convert_size(value.native)),
+            Cell::new(&value.convert_size(value.others)),
+            Cell::new(&value.convert_size(value.overhead)),
+            Cell::new(&value.convert_size(value.all)),
+        ]));
+        table
+    }
+
+    /**
+     * 压缩包结构开销明细
+     */
+    pub fn overhead_table(value: &SizeData) -> Table {
+        let detail = &value.overhead_detail;
+        let mut table = Table::new();
+        table.add_row(row![
+            "Local Headers",
+            "Data Descriptors",
+            "Central Directory",
+            "EOCD",
+            "Signing Block",
+            "Padding",
+            "Archive Overhead"
+        ]);
+        table.add_row(Row::new(vec![
+            Cell::new(&detail.local_headers.to_string()),
+            Cell::new(&detail.data_descriptors.to_string()),
+            Cell::new(&detail.central_directory.to_string()),
+            Cell::new(&detail.end_of_central_directory.to_string()),
+            Cell::new(&detail.signing_block.to_string()),
+            Cell::new(&detail.padding.to_string()),
+            Cell::new(&value.overhead.to_string()),
+        ]));
+        table
+    }
+
+    /**
+     * 离线估算的下载大小和安装大小
+     */
+    pub fn estimate_table(value: &SizeData, estimate: &DownloadEstimate) -> Table {
+        let mut table = Table::new();
+        table.add_row(row![
+            "File Size",
+            "Est. Download (gzip)",
+            "Est. Download (brotli)",
+            "Est. Download",
+            "Est. Install"
+        ]);
+        table.add_row(Row::new(vec![
+            Cell::new(&value.convert_size(estimate.raw_size)),
+            Cell::new(&value.convert_size(estimate.gzip_size)),
+            Cell::new(&value.convert_size(estimate.brotli_size)),
+            Cell::new(&value.convert_size(estimate.download_size)),
+            Cell::new(&value.convert_size(estimate.install_size)),
+        ]));
+        table
+    }
+
+    /**
+     * 文件明细，只包含满足过滤条件的文件，id 为过滤后的序号
+     */
+    pub fn detail_table(value: &[CliperInfo], filter: &EntryFilter) -> Table {
+        let mut table = Table::new();
+        table.add_row(row![
+            "id",
+            "Folder Path",
+            "Name",
+            "Size",
+            "Download",
+            "Type",
+            "File Type",
+            "File Folder"
+        ]);
+        for (index, cliper_item) in value.iter().filter(|info| filter.matches(info)).enumerate() {
+            table.add_row(Row::new(vec![
+                Cell::new(&(index + 1).to_string()),
+                Cell::new(&cliper_item.file_path),
+                Cell::new(&cliper_item.name),
+                Cell::new(&cliper_item.size.to_string()),
+                Cell::new(&cliper_item.download.to_string()),
+                Cell::new(&cliper_item.file_ext),
+                Cell::new(&cliper_item.file_type.to_string()),
+                Cell::new(&cliper_item.file_folder),
+            ]));
+        }
+        table
+    }
+
+    /**
+     * 新增，删除或者更新的文件，Diff 为下载大小的变化
+     */
+    pub fn entries_diff_table(value: &[CliperInfo]) -> Table {
+        let mut table = Table::new();
+        table.add_row(row![
+            "id",
+            "Folder Path",
+            "Name",
+            "Size",
+            "Download",
+            "Type",
+            "File Type",
+            "File Folder",
+            "Diff"
+        ]);
+        for (index, cliper_item) in value.iter().enumerate() {
+            table.add_row(Row::new(vec![
+                Cell::new(&(index + 1).to_string()),
+                Cell::new(&cliper_item.file_path),
+                Cell::new(&cliper_item.name),
+                Cell::new(&cliper_item.size.to_string()),
+                Cell::new(&cliper_item.download.to_string()),
+                Cell::new(&cliper_item.file_ext),
+                Cell::new(&cliper_item.file_type.to_string()),
+                Cell::new(&cliper_item.file_folder),
+                Cell::new(&cliper_item.diff.to_string()),
+            ]));
+        }
+        table
+    }
+
+    /**
+     * 分类汇总对比，包含压缩包结构开销
+     * estimates 为 (新, 旧) 的估算结果，没有时估算的行显示为 -
+     */
+    pub fn size_diff_table(diff: &ApkDiff, estimates: Option<&(DownloadEstimate, DownloadEstimate)>) -> Table {
+        let value = &diff.new_summary;
+        let value_cmp = &diff.old_summary;
+        let detail = &value.overhead_detail;
+        let detail_cmp = &value_cmp.overhead_detail;
+        let rows = [
+            ("Assets", value.asserts, value_cmp.asserts),
+            ("Res", value.res, value_cmp.res),
+            ("Code", value.code, value_cmp.code),
+            ("Native", value.native, value_cmp.native),
+            ("Others", value.others, value_cmp.others),
+            ("Overhead", value.overhead, value_cmp.overhead),
+            ("  Local Headers", detail.local_headers, detail_cmp.local_headers),
+            ("  Data Descriptors", detail.data_descriptors, detail_cmp.data_descriptors),
+            ("  Central Directory", detail.central_directory, detail_cmp.central_directory),
+            ("  EOCD", detail.end_of_central_directory, detail_cmp.end_of_central_directory),
+            ("  Signing Block", detail.signing_block, detail_cmp.signing_block),
+            ("  Padding", detail.padding, detail_cmp.padding),
+            ("All", value.all, value_cmp.all),
+        ];
+
+        let mut table = Table::new();
+        table.add_row(row!["Type", "Old", "New", "Diff"]);
+        for (name, new_size, old_size) in rows {
+            table.add_row(Row::new(vec![
+                Cell::new(name),
+                Cell::new(&old_size.to_string()),
+                Cell::new(&new_size.to_string()),
+                Cell::new(&(new_size as i64 - old_size as i64).to_string()),
+            ]));
+        }
+
+        let sizes = |estimate: &DownloadEstimate| {
+            [estimate.gzip_size, estimate.brotli_size, estimate.download_size, estimate.install_size]
+        };
+        let names = ["Est. Download (gzip)", "Est. Download (brotli)", "Est. Download", "Est. Install"];
+        for (index, name) in names.into_iter().enumerate() {
+            let cells = match estimates {
+                Some((estimate, estimate_cmp)) => {
+                    let (new_size, old_size) = (sizes(estimate)[index], sizes(estimate_cmp)[index]);
+                    [old_size.to_string(), new_size.to_string(), (new_size as i64 - old_size as i64).to_string()]
+                }
+                None => ["-".to_string(), "-".to_string(), "-".to_string()],
+            };
+            table.add_row(Row::new(vec![
+                Cell::new(name),
+                Cell::new(&cells[0]),
+                Cell::new(&cells[1]),
+                Cell::new(&cells[2]),
+            ]));
+        }
+        table
+    }
+}
@@ -0,0 +1,341 @@
+pub mod layout_reader {
+    use std::io::{Read, Result, Seek, SeekFrom};
+
+    use serde::Serialize;
+
+    const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x04034b50;
+    const DATA_DESCRIPTOR_SIGNATURE: u32 = 0x08074b50;
+    const CENTRAL_DIRECTORY_END_SIGNATURE: u32 = 0x06054b50;
+    const ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE: u32 = 0x07064b50;
+    const ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE: u32 = 0x06064b50;
+    const ZIP64_EXTRA_FIELD_ID: u16 = 0x0001;
+    // 本地文件头固定部分 30 字节，之后是文件名和扩展字段
+    const LOCAL_FILE_HEADER_SIZE: u64 = 30;
+    const APK_SIGNING_BLOCK_MAGIC: &[u8; 16] = b"APK Sig Block 42";
+    // EOCD 固定 22 字节，注释最长 65535 字节
+    const CENTRAL_DIRECTORY_END_MAX_SEARCH: u64 = 22 + 65535;
+
+    /**
+     * zip 中一个文件在磁盘上的位置
+     * 本地文件头起点，数据起点，压缩后的数据长度
+     */
+    #[derive(Debug, Clone)]
+    pub struct EntrySpan {
+        pub header_start: u64,
+        pub data_start: u64,
+        pub compressed_size: u64,
+    }
+
+    /**
+     * zip 结构本身占用的空间，不包含文件数据
+     */
+    #[derive(Debug, Clone, Default, Serialize)]
+    pub struct ArchiveOverhead {
+        pub local_headers: u64,
+        pub data_descriptors: u64,
+        pub central_directory: u64,
+        pub end_of_central_directory: u64,
+        pub signing_block: u64,
+        pub padding: u64,
+    }
+
+    impl ArchiveOverhead {
+        pub fn total(&self) -> u64 {
+            self.local_headers
+                + self.data_descriptors
+                + self.central_directory
+                + self.end_of_central_directory
+                + self.signing_block
+                + self.padding
+        }
+    }
+
+    /**
+     * 计算 zip 结构的开销
+     * 文件数据以外的部分：本地文件头，数据描述符，中央目录，EOCD/zip64 记录，签名块，以及剩余的填充
+     * 所有分类加上文件数据正好等于文件长度
+     */
+    pub fn read_overhead<R: Read + Seek>(
+        reader: &mut R,
+        spans: &[EntrySpan],
+        central_directory_start: Option<u64>,
+    ) -> Result<ArchiveOverhead> {
+        let file_len = reader.seek(SeekFrom::End(0))?;
+        let mut overhead = ArchiveOverhead::default();
+
+        let end_start = read_end_records_start(reader, file_len)?;
+        overhead.end_of_central_directory = file_len - end_start;
+
+        let central_directory_start = central_directory_start.unwrap_or(end_start).min(end_start);
+        overhead.central_directory = end_start - central_directory_start;
+
+        let mut spans = spans.to_vec();
+        spans.sort_by_key(|span| span.header_start);
+
+        let mut data_size: u64 = 0;
+        let mut entries_end: u64 = 0;
+        for span in &spans {
+            overhead.local_headers += span.data_start.saturating_sub(span.header_start);
+            data_size += span.compressed_size;
+            let data_end = span.data_start + span.compressed_size;
+            let descriptor = read_data_descriptor_len(reader, span, data_end)?;
+            overhead.data_descriptors += descriptor;
+            entries_end = entries_end.max(data_end + descriptor);
+        }
+
+        // 签名块位于最后一个文件和中央目录之间
+        if central_directory_start > entries_end {
+            overhead.signing_block = read_signing_block_len(reader, central_directory_start)?
+                .filter(|len| *len <= central_directory_start - entries_end)
+                .unwrap_or(0);
+        }
+
+        overhead.padding = file_len
+            .saturating_sub(data_size)
+            .saturating_sub(overhead.local_headers)
+            .saturating_sub(overhead.data_descriptors)
+            .saturating_sub(overhead.central_directory)
+            .saturating_sub(overhead.end_of_central_directory)
+            .saturating_sub(overhead.signing_block);
+
+        Ok(overhead)
+    }
+
+    /**
+     * 查找 EOCD，如果存在 zip64 记录，返回 zip64 记录的起点
+     */
+    fn read_end_records_start<R: Read + Seek>(reader: &mut R, file_len: u64) -> Result<u64> {
+        let search_len = file_len.min(CENTRAL_DIRECTORY_END_MAX_SEARCH);
+        let search_start = file_len - search_len;
+        let mut buffer = vec![0u8; search_len as usize];
+        reader.seek(SeekFrom::Start(search_start))?;
+        reader.read_exact(&mut buffer)?;
+
+        let signature = CENTRAL_DIRECTORY_END_SIGNATURE.to_le_bytes();
+        let eocd_offset = match (0..buffer.len().saturating_sub(3))
+            .rev()
+            .find(|i| buffer[*i..*i + 4] == signature)
+        {
+            Some(index) => search_start + index as u64,
+            None => return Ok(file_len),
+        };
+
+        // zip64 EOCD locator 固定 20 字节，紧挨着 EOCD
+        if eocd_offset < 20 {
+            return Ok(eocd_offset);
+        }
+        let locator_offset = eocd_offset - 20;
+        if read_u32_at(reader, locator_offset)? != ZIP64_CENTRAL_DIRECTORY_END_LOCATOR_SIGNATURE {
+            return Ok(eocd_offset);
+        }
+        let zip64_end_offset = read_u64_at(reader, locator_offset + 8)?;
+        if zip64_end_offset < locator_offset
+            && read_u32_at(reader, zip64_end_offset)? == ZIP64_CENTRAL_DIRECTORY_END_SIGNATURE
+        {
+            return Ok(zip64_end_offset);
+        }
+        Ok(locator_offset)
+    }
+
+    /**
+     * 读取数据描述符的长度，通用标志位第 3 位为 1 时才存在
+     */
+    fn read_data_descriptor_len<R: Read + Seek>(
+        reader: &mut R,
+        span: &EntrySpan,
+        data_end: u64,
+    ) -> Result<u64> {
+        if read_u32_at(reader, span.header_start)? != LOCAL_FILE_HEADER_SIGNATURE {
+            return Ok(0);
+        }
+        let flags = read_u16_at(reader, span.header_start + 6)?;
+        if flags & 0x0008 == 0 {
+            return Ok(0);
+        }
+        // crc32 + 压缩大小 + 原始大小，本地文件头带有 zip64 扩展字段时大小字段为 8 字节
+        let mut len = if has_zip64_extra(reader, span)? { 20 } else { 12 };
+        if read_u32_at(reader, data_end)? == DATA_DESCRIPTOR_SIGNATURE {
+            len += 4;
+        }
+        Ok(len)
+    }
+
+    /**
+     * 本地文件头的扩展字段中是否有 zip64 扩展 (0x0001)
+     */
+    fn has_zip64_extra<R: Read + Seek>(reader: &mut R, span: &EntrySpan) -> Result<bool> {
+        let name_len = read_u16_at(reader, span.header_start + 26)? as u64;
+        let extra_len = read_u16_at(reader, span.header_start + 28)? as u64;
+        let mut offset = span.header_start + LOCAL_FILE_HEADER_SIZE + name_len;
+        let extra_end = offset + extra_len;
+        // 每个扩展字段为 id + 长度 + 数据
+        while offset + 4 <= extra_end {
+            if read_u16_at(reader, offset)? == ZIP64_EXTRA_FIELD_ID {
+                return Ok(true);
+            }
+            offset += 4 + read_u16_at(reader, offset + 2)? as u64;
+        }
+        Ok(false)
+    }
+
+    /**
+     * 读取 APK 签名块的长度，包含前面 8 字节的长度字段
+     * 签名块以 "APK Sig Block 42" 结尾，紧挨着中央目录
+     */
+    fn read_signing_block_len<R: Read + Seek>(
+        reader: &mut R,
+        central_directory_start: u64,
+    ) -> Result<Option<u64>> {
+        if central_directory_start < 32 {
+            return Ok(None);
+        }
+        let mut magic = [0u8; 16];
+        reader.seek(SeekFrom::Start(central_directory_start - 16))?;
+        reader.read_exact(&mut magic)?;
+        if &magic != APK_SIGNING_BLOCK_MAGIC {
+            return Ok(None);
+        }
+        let block_size = read_u64_at(reader, central_directory_start - 24)?;
+        Ok(block_size.checked_add(8))
+    }
+
+    fn read_u16_at<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<u16> {
+        let mut buffer = [0u8; 2];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut buffer)?;
+        Ok(u16::from_le_bytes(buffer))
+    }
+
+    fn read_u32_at<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<u32> {
+        let mut buffer = [0u8; 4];
+        reader.seek(SeekFrom::Start(offset))?;
+        match reader.read_exact(&mut buffer) {
+            Ok(_) => Ok(u32::from_le_bytes(buffer)),
+            // 读到文件末尾时视为不匹配
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_u64_at<R: Read + Seek>(reader: &mut R, offset: u64) -> Result<u64> {
+        let mut buffer = [0u8; 8];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut buffer)?;
+        Ok(u64::from_le_bytes(buffer))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::io::{Cursor, Write};
+
+        use zip::write::FileOptions;
+        use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+        use super::{read_overhead, EntrySpan, DATA_DESCRIPTOR_SIGNATURE, LOCAL_FILE_HEADER_SIGNATURE};
+
+        fn spans(data: &[u8]) -> (Vec<EntrySpan>, u64) {
+            let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+            let mut spans = Vec::new();
+            let mut central_directory_start = u64::MAX;
+            for i in 0..archive.len() {
+                let file = archive.by_index(i).unwrap();
+                spans.push(EntrySpan {
+                    header_start: file.header_start(),
+                    data_start: file.data_start(),
+                    compressed_size: file.compressed_size(),
+                });
+                central_directory_start = central_directory_start.min(file.central_header_start());
+            }
+            (spans, central_directory_start)
+        }
+
+        /**
+         * 只有一个文件的 zip，通用标志位第 3 位为 1，数据后面带有签名的数据描述符
+         * zip64 为 true 时本地文件头带有 zip64 扩展字段，数据描述符的大小字段为 8 字节
+         */
+        fn descriptor_zip(zip64: bool) -> (Vec<u8>, EntrySpan) {
+            let extra: Vec<u8> = if zip64 {
+                [&0x0001u16.to_le_bytes()[..], &16u16.to_le_bytes(), &[0u8; 16]].concat()
+            } else {
+                Vec::new()
+            };
+            let mut data = Vec::new();
+            data.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+            data.extend_from_slice(&[20, 0, 0x08, 0, 0, 0]);
+            data.extend_from_slice(&[0u8; 16]);
+            data.extend_from_slice(&1u16.to_le_bytes());
+            data.extend_from_slice(&(extra.len() as u16).to_le_bytes());
+            data.push(b'a');
+            data.extend_from_slice(&extra);
+            let data_start = data.len() as u64;
+            data.extend_from_slice(b"abc");
+            data.extend_from_slice(&DATA_DESCRIPTOR_SIGNATURE.to_le_bytes());
+            data.extend_from_slice(&[0u8; 4]);
+            data.extend_from_slice(&vec![0u8; if zip64 { 16 } else { 8 }]);
+            // 空的中央目录和 EOCD
+            data.extend_from_slice(&[0x50, 0x4b, 0x05, 0x06]);
+            data.extend_from_slice(&[0u8; 18]);
+            let span = EntrySpan {
+                header_start: 0,
+                data_start,
+                compressed_size: 3,
+            };
+            (data, span)
+        }
+
+        #[test]
+        fn overhead_adds_up_to_file_length() {
+            let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+            writer.start_file("AndroidManifest.xml", FileOptions::default()).unwrap();
+            writer.write_all(&b"<manifest/>".repeat(32)).unwrap();
+            let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+            writer.start_file("res/raw/data.bin", stored).unwrap();
+            writer.write_all(&[7u8; 100]).unwrap();
+            let data = writer.finish().unwrap().into_inner();
+
+            let (spans, central_directory_start) = spans(&data);
+            let overhead = read_overhead(&mut Cursor::new(&data), &spans, Some(central_directory_start)).unwrap();
+            let file_data: u64 = spans.iter().map(|span| span.compressed_size).sum();
+            assert_eq!(overhead.data_descriptors, 0);
+            assert_eq!(overhead.end_of_central_directory, 22);
+            assert_eq!(overhead.padding, 0);
+            assert_eq!(overhead.total() + file_data, data.len() as u64);
+        }
+
+        #[test]
+        fn zip64_descriptor_follows_extra_field() {
+            for (zip64, expected) in [(false, 16), (true, 24)] {
+                let (data, span) = descriptor_zip(zip64);
+                let central_directory_start = data.len() as u64 - 22;
+                let overhead = read_overhead(&mut Cursor::new(&data), &[span], Some(central_directory_start)).unwrap();
+                assert_eq!(overhead.data_descriptors, expected);
+                assert_eq!(overhead.padding, 0);
+                assert_eq!(overhead.total() + 3, data.len() as u64);
+            }
+        }
+
+        #[test]
+        fn truncated_archive_does_not_panic() {
+            let (data, span) = descriptor_zip(true);
+            let spans = [span];
+            for len in 0..data.len() {
+                let truncated = &data[..len];
+                let _ = read_overhead(&mut Cursor::new(truncated), &spans, Some(len as u64));
+                let _ = read_overhead(&mut Cursor::new(truncated), &spans, None);
+            }
+        }
+
+        #[test]
+        fn spans_past_the_end_are_ignored() {
+            let (data, _) = descriptor_zip(false);
+            let span = EntrySpan {
+                header_start: u32::MAX as u64,
+                data_start: u32::MAX as u64 + 30,
+                compressed_size: 10,
+            };
+            let overhead = read_overhead(&mut Cursor::new(&data), &[span], None).unwrap();
+            assert_eq!(overhead.data_descriptors, 0);
+            assert_eq!(overhead.end_of_central_directory, 22);
+        }
+    }
+}
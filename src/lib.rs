@@ -0,0 +1,10 @@
+pub mod app;
+pub mod cliper;
+
+pub use app::apk_info::ApkParsedInfo;
+pub use cliper::apk_archive::archive::{diff, ApkArchive, ApkDiff, DuplicateGroup};
+pub use cliper::apk_cliper::size_reader::PackageKind;
+pub use cliper::apk_error::ApkError;
+pub use cliper::apk_source::source::ApkSource;
+pub use cliper::cliper_info::CliperInfo;
+pub use cliper::size_data::SizeData;
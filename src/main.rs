@@ -1,398 +1,1368 @@
-use std::fs::File;
+use std::io::{self, Write};
 use std::{env, fs};
 
 use async_std::task;
-use csv::Writer;
 use prettytable::{row, Cell, Row, Table};
-use regex::Regex;
 use std::collections::HashMap;
 use std::path::Path;
 use structopt::StructOpt;
 
-mod app;
-use app::{apk_info::ApkParsedInfo, manifest_parser::parser};
-mod cliper;
-use cliper::cmds::{Args, CommonOpts, DetailOpts};
-use cliper::{apk_cliper::size_reader, cliper_info::CliperInfo};
-
-// 添加一个过滤器，过滤掉不需要的文件, 满足条件的返回true
-fn cliper_filter(info: &CliperInfo, filter: &DetailOpts) -> bool {
-    let path_filter = filter.filter_path.as_str();
-    let size_filter = &filter.filter_size;
-    let ext_filter = filter.filter_ext.as_str();
-    let type_filter = filter.filter_type.as_str();
-
-    let mut result = true;
-    // 过滤路径 路径不为空并且不是以过滤路径开头的，为true，不满足条件
-    let filter_path_enable = !path_filter.is_empty() && !info.file_path.starts_with(path_filter);
-    // 过滤大小 大小不为0并且下载大小小于过滤大小，为true，不满足条件
-    let filter_size_enable = *size_filter > 0 && info.download < *size_filter;
-    // 过滤后缀 后缀不为空并且不是以过滤后缀结尾的，为true，不满足条件
-    let filter_ext_enable = !ext_filter.is_empty() && !info.file_path.ends_with(ext_filter);
-    // 过滤类型 类型不为空并且不是过滤类型，为true，不满足条件
-    let filter_type_enable = !type_filter.is_empty() && info.file_type != type_filter;
-    // 过滤正则匹配 正则不为空并且不匹配，为true，不满足条件
-    // file_path filter_regex 做正则匹配
-    let relex = Regex::new(filter.filter_regex.as_str()).unwrap();
-    let filter_regex_enable = !filter.filter_regex.is_empty() && !relex.is_match(&info.file_path);
-    // 如果有一个条件满足，就返回true
-    if filter_path_enable
-        || filter_size_enable
-        || filter_ext_enable
-        || filter_type_enable
-        || filter_regex_enable
-    {
-        result = false;
-    }
-    return result;
-}
-
-async fn read_info(filename: &str) -> ApkParsedInfo {
-    match parser::parse(&filename).await {
-        Some(value) => {
-            let message = format!(
-                "APK Information:\nFile: {}\nPackage Name: {}\nVersion Code: {}\nVersion Name: {}",
-                filename, value.package_name, value.version_code, value.version_name
-            );
-            println_message(message.as_str());
-            return value;
-        }
-        None => {
-            println!("");
-            printline();
-            println!("Failed to read APK information");
-            printline();
-            return ApkParsedInfo::new();
-        }
-    }
-}
-
-async fn read_total(filename: &str, filter: &CommonOpts) {
-    match size_reader::read_size(filename) {
-        Ok(value) => {
-            let mut table = Table::new();
-            table.add_row(row!["Assets", "Res", "Code", "Native", "Others", "All"]);
-            table.add_row(Row::new(vec![
-                Cell::new(&value.convert_size(value.asserts)),
-                Cell::new(&value.convert_size(value.res)),
-                Cell::new(&value.convert_size(value.code)),
-                Cell::new(&value.convert_size(value.native)),
-                Cell::new(&value.convert_size(value.others)),
-                Cell::new(&value.convert_size(value.all)),
-            ]));
-            println!("");
-            printline();
-            table.printstd();
-            printline();
-            if filter.output_csv {
-                let output = output_path(&filter.build_path, "table_total.csv");
-                create_csv(&table, &output);
-            }
+use cliper::app::arsc_parser::arsc;
+use cliper::cliper::apk_archive::archive::{self, DuplicateClassKind, NativeAction, NativeMatch};
+use cliper::cliper::apk_cliper::size_reader::{self, Entries};
+use cliper::cliper::cmds::{
+    Args, BrowseOpts, CommonOpts, DetailOpts, DiffOpts, HistoryOpts, ImageOpts, LibraryOpts, ModuleOpts, SameOpts, XmlOpts,
+};
+use cliper::cliper::download_estimator::estimator;
+use cliper::cliper::image_advisor::advisor;
+use cliper::cliper::library_attribution::attribution::{self, LibraryRules};
+use cliper::cliper::module_map::modules::{self, ModuleMap};
+use cliper::cliper::res_xml_analyzer::xml_analyzer;
+use cliper::cliper::compare_matrix::matrix;
+use cliper::cliper::diff_markdown::markdown::{self, MarkdownOptions};
+use cliper::cliper::manifest_diff::manifest_compare;
+use cliper::cliper::resource_diff::resource_compare;
+use cliper::cliper::dex_diff::dex_compare;
+use cliper::cliper::native_diff::native_compare;
+use cliper::cliper::size_history::history::{self, SizeHistory, SizeSnapshot};
+use cliper::cliper::apk_extractor::extractor;
+use cliper::cliper::apk_browser::browser::{self, BrowseOptions};
+use cliper::cliper::size_report::report::{self, EntryFilter};
+use cliper::{ApkArchive, ApkDiff, ApkError, ApkParsedInfo, ApkSource, CliperInfo, PackageKind};
+
+async fn read_info(source: &ApkSource) -> Result<ApkParsedInfo, ApkError> {
+    let value = ApkArchive::from_source(source.clone()).and_then(|apk| apk.manifest())?;
+    let mut message = format!(
+        "APK Information:\nFile: {}\nPackage Name: {}\nVersion Code: {}\nVersion Name: {}",
+        source.name(), value.package_name, value.version_code, value.version_name
+    );
+    if let Some(mapping) = source.mapping()? {
+        message.push_str(format!("\nMapping: {}", mapping.name()).as_str());
+    }
+    println_message(message.as_str());
+    Ok(value)
+}
+
+async fn read_total(source: &ApkSource, filter: &CommonOpts) -> Result<(), ApkError> {
+    let value = ApkArchive::from_source(source.clone()).and_then(|apk| apk.summary())?;
+    let table = report::summary_table(&value);
+    let overhead_table = report::overhead_table(&value);
+    // aar 和 jar 不会被直接下载安装，不估算
+    let estimate = (value.kind == PackageKind::Apk).then(|| estimator::estimate(source));
+    let estimate_table = match estimate {
+        Some(Ok(estimate)) => Some(report::estimate_table(&value, &estimate)),
+        Some(Err(e)) => {
+            println!("Failed to estimate download size: {}", e);
+            None
         }
-        Err(e) => {
-            println!("");
-            printline();
-            println!("Failed to read APK information: {}", e);
-            printline();
-        }
-    }
-}
-
-async fn read_detail_info(filename: &str, filter: &CommonOpts, detail: &DetailOpts) {
-    match size_reader::read_detail_info(filename) {
-        Ok(value) => {
-            // 对value进行排序，以donwload大小进行排序
-            let mut value = value;
-            value.sort_by(|a, b| b.download.cmp(&a.download));
-
-            let mut table = Table::new();
-            let mut line_num = 0;
-            table.add_row(row![
-                "id",
-                "Folder Path",
-                "Name",
-                "Size",
-                "Download",
-                "Type",
-                "File Type",
-                "File Folder"
-            ]);
-            for cliper_item in &value {
-                if !cliper_filter(cliper_item, detail) {
-                    continue;
-                }
-                line_num += 1;
-                table.add_row(Row::new(vec![
-                    // Cell::new(&cliper_item.id.to_string()),
-                    Cell::new(&line_num.to_string()),
-                    Cell::new(&cliper_item.file_path),
-                    Cell::new(&cliper_item.name),
-                    Cell::new(&cliper_item.size.to_string()),
-                    Cell::new(&cliper_item.download.to_string()),
-                    Cell::new(&cliper_item.file_ext),
-                    Cell::new(&cliper_item.file_type.to_string()),
-                    Cell::new(&cliper_item.file_folder),
-                ]));
-            }
-            println!("");
-            printline();
-            println!("Total: {}, Filter: {}", &value.len(), line_num);
-            let limit = detail.limit;
-            if limit <= 0 || limit >= line_num {
-                table.printstd();
-            } else {
-                let mut limited_table = Table::new();
-                for row in table.row_iter().take(limit + 1) {
-                    limited_table.add_row(Row::new(
-                        row.iter()
-                            .map(|cell| Cell::new(&cell.get_content()))
-                            .collect(),
-                    ));
-                }
-                limited_table.printstd();
-            }
-            printline();
-            if filter.output_csv {
-                let output = output_path(&filter.build_path, "table_detail.csv");
-                create_csv(&table, &output);
-            }
+        None => None,
+    };
+    println!();
+    printline();
+    println!("Kind: {}", value.kind);
+    table.printstd();
+    overhead_table.printstd();
+    if let Some(estimate_table) = &estimate_table {
+        estimate_table.printstd();
+    }
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_total.csv");
+        report::write_csv(&table, &output)?;
+        let output = output_path(&filter.build_path, "table_overhead.csv");
+        report::write_csv(&overhead_table, &output)?;
+        if let Some(estimate_table) = &estimate_table {
+            let output = output_path(&filter.build_path, "table_estimate.csv");
+            report::write_csv(estimate_table, &output)?;
         }
-        Err(e) => {
-            println!("");
-            printline();
-            println!("Failed to read APK information: {}", e);
-            printline();
-        }
-    }
-}
-
-#[derive(Debug)]
-pub struct Md5SizeInfo {
-    // | id | file Path | Name  | Size | Download | Type  | File Type | File Folder |
-    pub id: u64,
-    pub file_path: String,
-    pub name: String,
-    pub size: u64,
-    pub download: u64,
-    pub file_type: String,
-    pub file_ext: String,
-    pub file_folder: String,
-    pub md5: String,
-}
-
-pub struct Md5Group {
-    pub md5: String,
-    pub file_names: String,
-    pub size: u64,
-}
-
-fn group_by_md5(data: Vec<CliperInfo>) -> HashMap<String, Vec<Md5SizeInfo>> {
-    let mut md5_map: HashMap<String, Vec<Md5SizeInfo>> = HashMap::new();
-    // 根据 md5 值获取对应的向量，如果不存在则插入一个新的空向量
-    for cliper_info in data {
-        let file_names = md5_map
-            .entry(cliper_info.md5.clone())
-            .or_insert_with(Vec::new);
-        let md5_size_info = Md5SizeInfo {
-            id: cliper_info.id,
-            file_path: cliper_info.file_path,
-            name: cliper_info.name,
-            size: cliper_info.size,
-            download: cliper_info.download,
-            file_type: cliper_info.file_type,
-            file_ext: cliper_info.file_ext,
-            file_folder: cliper_info.file_folder,
-            md5: cliper_info.md5.clone(),
+    }
+    Ok(())
+}
+
+// 读取全部文件信息，在单行打印进度
+fn collect_entries(entries: Result<Entries, ApkError>) -> Result<Vec<CliperInfo>, ApkError> {
+    let mut entries = entries?;
+    let mut list = Vec::new();
+    while let Some(entry) = entries.next() {
+        print_progress(entries.position(), entries.total());
+        list.push(entry?);
+    }
+    Ok(list)
+}
+
+async fn read_detail_info(source: &ApkSource, filter: &CommonOpts, detail: &DetailOpts) -> Result<(), ApkError> {
+    let entry_filter = EntryFilter::new(detail)?;
+    let apk = ApkArchive::from_source(source.clone())?;
+    let mut value = collect_entries(apk.entries())?;
+    // aar 中的 jar 展开成 class 级别的大小
+    if apk.kind()? == PackageKind::Aar {
+        value.retain(|info| !(info.file_type == "Code" && info.file_ext == ".jar"));
+        value.extend(apk.class_entries()?);
+    }
+    // 对value进行排序，以donwload大小进行排序
+    value.sort_by_key(|b| std::cmp::Reverse(b.download));
+
+    let table = report::detail_table(&value, &entry_filter);
+    println!();
+    printline();
+    println!("Total: {}, Filter: {}", &value.len(), table.len() - 1);
+    report::limit_table(&table, detail.limit).printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_detail.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+// 全屏浏览，过滤参数作为初始的过滤条件，界面中按 e 导出到 build/table_browse.csv
+async fn browse_files(source: &ApkSource, filter: &CommonOpts, browse: &BrowseOpts) -> Result<(), ApkError> {
+    let apk = ApkArchive::from_source(source.clone())?;
+    let mut value = collect_entries(apk.entries_with_md5())?;
+    // aar 中的 jar 展开成 class 级别的大小，与 detail 一致
+    if apk.kind()? == PackageKind::Aar {
+        value.retain(|info| !(info.file_type == "Code" && info.file_ext == ".jar"));
+        value.extend(apk.class_entries()?);
+    }
+    let options = BrowseOptions {
+        title: source.name(),
+        query: browser::Query::text(
+            &browse.filter_path,
+            &browse.filter_ext,
+            &browse.filter_type,
+            &browse.filter_regex,
+            browse.filter_size,
+        ),
+        csv_path: output_path(&filter.build_path, "table_browse.csv"),
+    };
+    browser::run(&value, &options)
+}
+
+async fn read_same_info(source: &ApkSource, filter: &CommonOpts, same: &SameOpts) -> Result<(), ApkError> {
+    if same.code {
+        return read_same_code_info(source, filter, same);
+    }
+    if same.native {
+        return read_same_native_info(source, filter);
+    }
+    let value = ApkArchive::from_source(source.clone()).and_then(|apk| collect_entries(apk.entries_with_md5()))?;
+    let md5_groups = archive::group_duplicates(value);
+
+    let mut md5_table = Table::new();
+    let mut md5_line_num = 0;
+    md5_table.add_row(row!["id", "md5", "files", "size"]);
+
+    // 打印出按 MD5 分组的文件名
+    for item_info in md5_groups {
+        md5_line_num += 1;
+        let file_names = item_info
+            .files
+            .iter()
+            .map(|file| file.file_path.clone())
+            .collect::<Vec<String>>()
+            .join("\n");
+        md5_table.add_row(Row::new(vec![
+            // Cell::new(&cliper_item.id.to_string()),
+            Cell::new(&md5_line_num.to_string()),
+            Cell::new(&item_info.md5),
+            Cell::new(&file_names),
+            Cell::new(&item_info.size.to_string()),
+        ]));
+    }
+    // 按文件大小排序
+    println!();
+    printline();
+    println!("Total: {}, Filter: {}", &md5_table.len() - 1, md5_line_num);
+    md5_table.printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_same.csv");
+        report::write_csv(&md5_table, &output)?;
+    }
+    Ok(())
+}
+
+// 重复的类，同名的类和结构相同的类
+fn read_same_code_info(source: &ApkSource, filter: &CommonOpts, same: &SameOpts) -> Result<(), ApkError> {
+    let groups = ApkArchive::from_source(source.clone())?.duplicate_classes(same.min_code_units)?;
+
+    let mut table = Table::new();
+    table.add_row(row!["id", "Kind", "Classes", "Containers", "Size", "Wasted"]);
+    for (index, group) in groups.iter().enumerate() {
+        let kind = match group.kind {
+            DuplicateClassKind::Descriptor => "Same Name",
+            DuplicateClassKind::Body => "Same Body",
         };
-        // 将文件名添加到向量中
-        file_names.push(md5_size_info);
-    }
-
-    md5_map
-}
-
-async fn read_same_info(filename: &str, filter: &CommonOpts) {
-    match size_reader::read_detail_info_with_md5(filename) {
-        Ok(value) => {
-            // 对value进行排序，以donwload大小进行排序
-            let md5_groups = group_by_md5(value);
-
-            let md5_groups_convert = md5_groups
-                .into_iter()
-                // 过滤 md5 值只有一个文件的情况
-                .filter_map(|(md5, file_names)| {
-                    if file_names.len() == 1 {
-                        return None;
-                    }
-                    Some((md5, file_names))
-                })
-                .map(|(md5, file_names)| {
-                    let size = file_names.iter().map(|file_name| file_name.size).sum();
-                    let file_names = file_names
-                        .iter()
-                        .map(|file_name| file_name.file_path.clone())
-                        .collect::<Vec<String>>()
-                        .join("\n");
-                    Md5Group {
-                        md5,
-                        file_names,
-                        size,
-                    }
-                })
-                .collect::<Vec<Md5Group>>();
-
-            // 按照size大小排序
-            let mut md5_groups_convert = md5_groups_convert;
-            md5_groups_convert.sort_by(|a, b| b.size.cmp(&a.size));
-
-            let mut md5_table = Table::new();
-            let mut md5_line_num = 0;
-            md5_table.add_row(row!["id", "md5", "files", "size"]);
-
-            // 打印出按 MD5 分组的文件名
-            for item_info in md5_groups_convert {
-                md5_line_num += 1;
-                md5_table.add_row(Row::new(vec![
-                    // Cell::new(&cliper_item.id.to_string()),
-                    Cell::new(&md5_line_num.to_string()),
-                    Cell::new(&item_info.md5),
-                    Cell::new(&item_info.file_names),
-                    Cell::new(&item_info.size.to_string()),
-                ]));
-            }
-            // 按文件大小排序
-            println!("");
-            printline();
-            println!("Total: {}, Filter: {}", &md5_table.len() - 1, md5_line_num);
-            md5_table.printstd();
-            printline();
-            if filter.output_csv {
-                let output = output_path(&filter.build_path, "table_same.csv");
-                create_csv(&md5_table, &output);
-            }
+        let names = group
+            .classes
+            .iter()
+            .map(|class| class.name.clone())
+            .collect::<Vec<String>>()
+            .join("\n");
+        let containers = group
+            .classes
+            .iter()
+            .map(|class| class.container.clone())
+            .collect::<Vec<String>>()
+            .join("\n");
+        let size: u64 = group.classes.iter().map(|class| class.size).sum();
+        table.add_row(Row::new(vec![
+            Cell::new(&(index + 1).to_string()),
+            Cell::new(kind),
+            Cell::new(&names),
+            Cell::new(&containers),
+            Cell::new(&size.to_string()),
+            Cell::new(&group.wasted.to_string()),
+        ]));
+    }
+    let wasted: u64 = groups.iter().map(|group| group.wasted).sum();
+    println!();
+    printline();
+    println!("Duplicates: {}, Wasted: {}", groups.len(), wasted);
+    table.printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_same_code.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+// 重复的共享库，内容相同或者 DT_SONAME，build-id 相同
+fn read_same_native_info(source: &ApkSource, filter: &CommonOpts) -> Result<(), ApkError> {
+    let groups = ApkArchive::from_source(source.clone())?.duplicate_native_libraries()?;
+
+    let mut table = Table::new();
+    table.add_row(row!["id", "Match", "Files", "Machine", "SONAME", "Build ID", "Size", "Action", "Wasted"]);
+    for (index, group) in groups.iter().enumerate() {
+        let matches = group
+            .matches
+            .iter()
+            .map(|item| match item {
+                NativeMatch::Content => "Content",
+                NativeMatch::SoName => "SONAME",
+                NativeMatch::BuildId => "Build ID",
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let column = |value: &dyn Fn(&archive::NativeLibrary) -> String| {
+            group.libraries.iter().map(value).collect::<Vec<String>>().join("\n")
+        };
+        let actions = group
+            .actions
+            .iter()
+            .map(|action| match action {
+                NativeAction::Keep => "keep",
+                NativeAction::Drop => "drop",
+                NativeAction::Review => "review",
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+        table.add_row(Row::new(vec![
+            Cell::new(&(index + 1).to_string()),
+            Cell::new(&matches),
+            Cell::new(&column(&|library| library.path.clone())),
+            Cell::new(&column(&|library| library.machine.clone())),
+            Cell::new(&column(&|library| library.soname.clone())),
+            // build-id 通常是 20 字节，表格中只显示前 16 位
+            Cell::new(&column(&|library| library.build_id.chars().take(16).collect())),
+            Cell::new(&column(&|library| library.size.to_string())),
+            Cell::new(&actions),
+            Cell::new(&group.wasted.to_string()),
+        ]));
+    }
+    let wasted: u64 = groups.iter().map(|group| group.wasted).sum();
+    println!();
+    printline();
+    println!("Duplicates: {}, Wasted: {}", groups.len(), wasted);
+    table.printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_same_native.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+async fn read_images_info(
+    source: &ApkSource,
+    filter: &CommonOpts,
+    detail: &DetailOpts,
+    image: &ImageOpts,
+) -> Result<(), ApkError> {
+    let entry_filter = EntryFilter::new(detail)?;
+    let accept = |info: &CliperInfo| advisor::is_image(info) && entry_filter.matches(info);
+    let value = size_reader::read_detail_info_with_content(source, accept)?;
+    let total = value.len();
+    let mut advices = Vec::new();
+    for (index, (info, content)) in value.iter().enumerate() {
+        print_progress(index + 1, total);
+        if let Some(advice) = advisor::analyze(info, content, image.quality, image.max_dp) {
+            advices.push(advice);
+        }
+    }
+    // 按照可节省的大小排序
+    advices.sort_by_key(|b| std::cmp::Reverse(b.savings()));
+
+    let size_cell = |size: Option<u64>| match size {
+        Some(size) => size.to_string(),
+        None => "-".to_string(),
+    };
+    let mut table = Table::new();
+    table.add_row(row![
+        "id",
+        "File Path",
+        "Format",
+        "Dimensions",
+        "Density",
+        "Size",
+        "Download",
+        "Optimized PNG",
+        "WebP Lossless",
+        "WebP Lossy",
+        "Opaque Alpha",
+        "Oversized",
+        "Lossless Savings",
+        "Savings"
+    ]);
+    let mut lossless_savings: u64 = 0;
+    let mut savings: u64 = 0;
+    for (index, advice) in advices.iter().enumerate() {
+        lossless_savings += advice.lossless_savings();
+        savings += advice.savings();
+        table.add_row(Row::new(vec![
+            Cell::new(&(index + 1).to_string()),
+            Cell::new(&advice.file_path),
+            Cell::new(&advice.format),
+            Cell::new(&format!("{}x{}", advice.width, advice.height)),
+            Cell::new(&advice.density),
+            Cell::new(&advice.size.to_string()),
+            Cell::new(&advice.download.to_string()),
+            Cell::new(&size_cell(advice.optimized_size)),
+            Cell::new(&size_cell(advice.webp_lossless_size)),
+            Cell::new(&size_cell(advice.webp_lossy_size)),
+            Cell::new(if advice.opaque_alpha { "Yes" } else { "" }),
+            Cell::new(if advice.oversized { "Yes" } else { "" }),
+            Cell::new(&advice.lossless_savings().to_string()),
+            Cell::new(&advice.savings().to_string()),
+        ]));
+    }
+    println!();
+    printline();
+    println!(
+        "Total: {}, Decoded: {}, Lossless Savings: {}, Savings (WebP q{}): {}",
+        total,
+        advices.len(),
+        lossless_savings,
+        image.quality,
+        savings
+    );
+    report::limit_table(&table, detail.limit).printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_images.csv");
+        report::write_csv(&table, &output)?;
+    }
+    if image.extract {
+        extract_images(&value, &advices, filter, image)?;
+    }
+    Ok(())
+}
+
+// 把可以优化的图片写入 build 目录，保持在 apk 中的路径，并生成一份大小对照的 csv
+fn extract_images(
+    value: &[(CliperInfo, Vec<u8>)],
+    advices: &[advisor::ImageAdvice],
+    filter: &CommonOpts,
+    image: &ImageOpts,
+) -> Result<(), ApkError> {
+    let contents: HashMap<&str, &Vec<u8>> = value
+        .iter()
+        .map(|(info, content)| (info.file_path.as_str(), content))
+        .collect();
+    let mut table = Table::new();
+    table.add_row(row![
+        "File Path",
+        "Output Path",
+        "Original Size",
+        "New Size",
+        "Savings"
+    ]);
+    let mut skipped = 0;
+    for advice in advices {
+        let savings = if image.lossy {
+            advice.savings()
+        } else {
+            advice.lossless_savings()
+        };
+        if savings == 0 || savings < image.min_savings {
+            continue;
         }
-        Err(e) => {
-            println!("");
-            printline();
-            println!("Failed to read APK information: {}", e);
-            printline();
-        }
-    }
-}
-
-async fn diff_files(filename: &str, filename_cmp: &str, filter: &CommonOpts) {
-    let mut file_values: Vec<CliperInfo> = Vec::new();
-    let mut file_cmp_values: Vec<CliperInfo> = Vec::new();
-    match size_reader::read_detail_info(filename) {
-        Ok(value) => {
-            // 对value进行排序，以donwload大小进行排序
-            let mut value: Vec<CliperInfo> = value;
-            value.sort_by(|a, b| b.download.cmp(&a.download));
-            file_values = value;
-        }
-        Err(e) => {
-            println!("");
-            printline();
-            println!("Failed to read APK information: {}", e);
-            printline();
-        }
-    }
-    match size_reader::read_detail_info(filename_cmp) {
-        Ok(value) => {
-            // 对value进行排序，以donwload大小进行排序
-            let mut value: Vec<CliperInfo> = value;
-            value.sort_by(|a, b| b.download.cmp(&a.download));
-            file_cmp_values = value;
-        }
-        Err(e) => {
-            println!("");
-            printline();
-            println!("Failed to read APK information: {}", e);
-            printline();
-        }
-    }
-    // 对比两个文件的差异, filename - 新的文件，filename_cmp - 旧的文件
-    let mut new_files: Vec<CliperInfo> = Vec::new();
-    let mut delete_files: Vec<CliperInfo> = Vec::new();
-    let mut update_files: Vec<CliperInfo> = Vec::new();
-    // 查找新文件
-    for file in &file_values {
-        let mut find = false;
-        for file_cmp in &file_cmp_values {
-            if file.file_path == file_cmp.file_path {
-                find = true;
-                break;
+        let content = match contents.get(advice.file_path.as_str()) {
+            Some(content) => content,
+            None => continue,
+        };
+        let (new_path, data) =
+            match advisor::best_variant(advice, content, image.quality, image.lossy) {
+                Some(variant) => variant,
+                None => continue,
+            };
+        // 压缩包中的路径可能包含 .. 或者绝对路径，这类文件不写出
+        let output = match extractor::output_file(&filter.build_path, &new_path) {
+            Some(output) => output,
+            None => {
+                skipped += 1;
+                continue;
+            }
+        };
+        if let Some(parent) = output.parent() {
+            if !parent.exists() {
+                fs::create_dir_all(parent)?;
             }
         }
-        if !find {
-            let mut find_result_file = file.clone();
-            find_result_file.diff = file.download as i64;
-            new_files.push(find_result_file);
+        fs::write(&output, &data)?;
+        table.add_row(Row::new(vec![
+            Cell::new(&advice.file_path),
+            Cell::new(&new_path),
+            Cell::new(&advice.size.to_string()),
+            Cell::new(&data.len().to_string()),
+            Cell::new(&(advice.size - data.len() as u64).to_string()),
+        ]));
+    }
+    let output = output_path(&filter.build_path, "images_manifest.csv");
+    println_message(&format!(
+        "Extracted: {}, Skipped: {}, Output: {}, Manifest: {}",
+        table.len() - 1,
+        skipped,
+        filter.build_path,
+        output
+    ));
+    report::write_csv(&table, &output)
+}
+
+async fn read_xml_info(source: &ApkSource, filter: &CommonOpts, detail: &DetailOpts, xml: &XmlOpts) -> Result<(), ApkError> {
+    let entry_filter = EntryFilter::new(detail)?;
+    let accept = |info: &CliperInfo| xml_analyzer::is_res_xml(info) && entry_filter.matches(info);
+    let value = size_reader::read_detail_info_with_content(source, accept)?;
+    let total = value.len();
+    let mut xml_infos = Vec::new();
+    for (index, (info, content)) in value.iter().enumerate() {
+        print_progress(index + 1, total);
+        if let Some(xml_info) = xml_analyzer::analyze(info, content) {
+            xml_infos.push(xml_info);
         }
     }
-    // 查找删除文件
-    for file_cmp in &file_cmp_values {
-        let mut find = false;
-        for file in &file_values {
-            if file.file_path == file_cmp.file_path {
-                find = true;
-                break;
-            }
+    xml_infos.sort_by_key(|b| std::cmp::Reverse(b.download));
+
+    // 按资源类型汇总
+    let mut categories: HashMap<String, (usize, u64, u64)> = HashMap::new();
+    for xml_info in &xml_infos {
+        let category = categories.entry(xml_info.category.clone()).or_default();
+        category.0 += 1;
+        category.1 += xml_info.size;
+        category.2 += xml_info.download;
+    }
+    let mut categories: Vec<(String, (usize, u64, u64))> = categories.into_iter().collect();
+    categories.sort_by_key(|b| std::cmp::Reverse(b.1 .2));
+    let mut category_table = Table::new();
+    category_table.add_row(row!["Category", "Count", "Size", "Download"]);
+    for (category, (count, size, download)) in &categories {
+        category_table.add_row(Row::new(vec![
+            Cell::new(category),
+            Cell::new(&count.to_string()),
+            Cell::new(&size.to_string()),
+            Cell::new(&download.to_string()),
+        ]));
+    }
+
+    // 嵌套过深的布局和 pathData 过长的矢量图
+    let mut flagged_table = Table::new();
+    flagged_table.add_row(row![
+        "id",
+        "File Path",
+        "Category",
+        "Size",
+        "Download",
+        "Depth",
+        "Elements",
+        "Path Length",
+        "Reason"
+    ]);
+    let mut line_num = 0;
+    for xml_info in &xml_infos {
+        let mut reasons = Vec::new();
+        if xml_info.category == "layout" && xml_info.depth > xml.max_depth {
+            reasons.push("Deep nesting");
+        }
+        if xml_info.category == "vector" && xml_info.max_path_length > xml.max_path_length {
+            reasons.push("Long path data");
         }
-        if !find {
-            let mut find_result_file = file_cmp.clone();
-            find_result_file.diff = -(file_cmp.download as i64);
-            delete_files.push(find_result_file);
+        if reasons.is_empty() {
+            continue;
         }
+        line_num += 1;
+        flagged_table.add_row(Row::new(vec![
+            Cell::new(&line_num.to_string()),
+            Cell::new(&xml_info.file_path),
+            Cell::new(&xml_info.category),
+            Cell::new(&xml_info.size.to_string()),
+            Cell::new(&xml_info.download.to_string()),
+            Cell::new(&xml_info.depth.to_string()),
+            Cell::new(&xml_info.element_count.to_string()),
+            Cell::new(&xml_info.max_path_length.to_string()),
+            Cell::new(&reasons.join(", ")),
+        ]));
+    }
+
+    // 解码后内容相同，但是存放在不同位置的 xml
+    let mut same_groups: HashMap<&str, Vec<&xml_analyzer::XmlResInfo>> = HashMap::new();
+    for xml_info in &xml_infos {
+        same_groups
+            .entry(xml_info.decoded_md5.as_str())
+            .or_default()
+            .push(xml_info);
+    }
+    let mut same_groups: Vec<(&str, Vec<&xml_analyzer::XmlResInfo>)> = same_groups
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .collect();
+    // 保留一份，其余的都是浪费
+    let wasted = |files: &Vec<&xml_analyzer::XmlResInfo>| -> u64 {
+        let total: u64 = files.iter().map(|file| file.download).sum();
+        total - files.iter().map(|file| file.download).max().unwrap_or(0)
+    };
+    same_groups.sort_by_key(|b| std::cmp::Reverse(wasted(&b.1)));
+    let mut same_table = Table::new();
+    same_table.add_row(row!["id", "decoded md5", "files", "download", "wasted"]);
+    for (index, (md5, files)) in same_groups.iter().enumerate() {
+        same_table.add_row(Row::new(vec![
+            Cell::new(&(index + 1).to_string()),
+            Cell::new(md5),
+            Cell::new(
+                &files
+                    .iter()
+                    .map(|file| file.file_path.clone())
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            ),
+            Cell::new(&files.iter().map(|file| file.download).sum::<u64>().to_string()),
+            Cell::new(&wasted(files).to_string()),
+        ]));
+    }
+
+    println!();
+    printline();
+    println!("Total: {}, Decoded: {}", total, xml_infos.len());
+    category_table.printstd();
+    println!("Flagged: {}", line_num);
+    flagged_table.printstd();
+    println!("Same after decoding: {}", same_groups.len());
+    same_table.printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_xml_category.csv");
+        report::write_csv(&category_table, &output)?;
+        let output = output_path(&filter.build_path, "table_xml_flagged.csv");
+        report::write_csv(&flagged_table, &output)?;
+        let output = output_path(&filter.build_path, "table_xml_same.csv");
+        report::write_csv(&same_table, &output)?;
     }
-    // 查找更新文件
-    for file in &file_values {
-        for file_cmp in &file_cmp_values {
-            if file.file_path == file_cmp.file_path && file.download != file_cmp.download {
-                let mut result = file.clone();
-                result.diff = file_cmp.download as i64 - file.download as i64;
-                update_files.push(result);
+    Ok(())
+}
+
+async fn extract_files(source: &ApkSource, filter: &CommonOpts, detail: &DetailOpts, output_dir: &str) -> Result<(), ApkError> {
+    // 先解析 resources.arsc，用于还原 xml 中的资源引用
+    let accept_arsc = |info: &CliperInfo| info.file_path.ends_with("resources.arsc");
+    let resources = match size_reader::read_detail_info_with_content(source, accept_arsc) {
+        Ok(value) => value
+            .first()
+            .and_then(|(_, content)| arsc::parse(content)),
+        Err(_) => None,
+    };
+
+    let mut table = Table::new();
+    table.add_row(row!["id", "File Path", "Output Path", "Size", "Decoded"]);
+    let mut skipped = 0;
+    let mut decoded_count = 0;
+    let entry_filter = EntryFilter::new(detail)?;
+    let accept = |info: &CliperInfo| !info.file_path.ends_with('/') && entry_filter.matches(info);
+    let result = size_reader::visit_detail_info_with_content(source, accept, |info, content| {
+        let output = match extractor::output_file(output_dir, &info.file_path) {
+            Some(output) => output,
+            None => {
+                skipped += 1;
+                return Ok(());
             }
+        };
+        let decoded = extractor::decode_entry(&info, &content, resources.as_ref());
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let data = decoded.as_ref().unwrap_or(&content);
+        fs::write(&output, data)?;
+        if decoded.is_some() {
+            decoded_count += 1;
         }
+        table.add_row(Row::new(vec![
+            Cell::new(&table.len().to_string()),
+            Cell::new(&info.file_path),
+            Cell::new(&output.display().to_string()),
+            Cell::new(&data.len().to_string()),
+            Cell::new(if decoded.is_some() { "Yes" } else { "" }),
+        ]));
+        Ok(())
+    });
+    result?;
+    println_message(&format!(
+        "Extracted: {}, Decoded: {}, Skipped: {}, Resources: {}, Output: {}",
+        table.len() - 1,
+        decoded_count,
+        skipped,
+        if resources.is_some() { "Yes" } else { "No" },
+        output_dir
+    ));
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_extract.csv");
+        report::write_csv(&table, &output)?;
     }
-    print_table("新增文件", new_files, filter.output_csv, filter);
-    print_table("删除文件", delete_files, filter.output_csv, filter);
-    print_table("更新文件", update_files, filter.output_csv, filter);
+    Ok(())
 }
 
-// cargo run diff --input /Users/liangrui/Work/liangrui/cliper/build/14.3.0.apk --input-cmp /Users/liangrui/Work/liangrui/cliper/build/14.2.0.apk --output-csv
-fn print_table(title: &str, value: Vec<CliperInfo>, output_csv: bool, filter: &CommonOpts) {
+async fn read_libraries_info(source: &ApkSource, filter: &CommonOpts, library: &LibraryOpts) -> Result<(), ApkError> {
+    let mut rules = LibraryRules::builtin();
+    if !library.rules.is_empty() {
+        rules.load(&fs::read_to_string(absolute_path(&library.rules))?)?;
+    }
+    let apk = ApkArchive::from_source(source.clone())?;
+    let libraries = attribution::attribute(&apk, &rules)?;
+    let total_download: u64 = libraries.iter().map(|library| library.download).sum();
+
     let mut table = Table::new();
-    let mut line_num = 0;
-    let mut total_download: i64 = 0;
     table.add_row(row![
-        "id",
-        "Folder Path",
-        "Name",
-        "Size",
-        "Download",
-        "Type",
-        "File Type",
-        "File Folder",
-        "Diff"
+        "id", "Library", "Versions", "Classes", "Files", "Size", "Download", "Share"
     ]);
-    for cliper_item in &value {
-        line_num += 1;
-        total_download += cliper_item.diff;
+    for (index, item) in libraries.iter().enumerate() {
         table.add_row(Row::new(vec![
-            // Cell::new(&cliper_item.id.to_string()),
-            Cell::new(&line_num.to_string()),
-            Cell::new(&cliper_item.file_path),
-            Cell::new(&cliper_item.name),
-            Cell::new(&cliper_item.size.to_string()),
-            Cell::new(&cliper_item.download.to_string()),
-            Cell::new(&cliper_item.file_ext),
-            Cell::new(&cliper_item.file_type.to_string()),
-            Cell::new(&cliper_item.file_folder),
-            Cell::new(&cliper_item.diff.to_string()),
+            Cell::new(&(index + 1).to_string()),
+            Cell::new(&item.name),
+            Cell::new(&item.versions.join("\n")),
+            Cell::new(&item.classes.to_string()),
+            Cell::new(&item.files.to_string()),
+            Cell::new(&item.size.to_string()),
+            Cell::new(&item.download.to_string()),
+            Cell::new(&format!(
+                "{:.2}%",
+                item.download as f64 * 100.0 / total_download.max(1) as f64
+            )),
+        ]));
+    }
+    println!();
+    printline();
+    println!("Libraries: {}, Download: {}", libraries.len(), total_download);
+    let limit = library.limit;
+    report::limit_table(&table, limit).printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_libraries.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+// 读取 --map，没有指定时返回 None
+fn load_module_map(module: &ModuleOpts) -> Result<Option<ModuleMap>, ApkError> {
+    if module.map.is_empty() {
+        return Ok(None);
+    }
+    let jars = (!module.jars.is_empty()).then(|| absolute_path(&module.jars));
+    ModuleMap::load(&absolute_path(&module.map), jars.as_deref()).map(Some)
+}
+
+async fn read_modules_info(source: &ApkSource, filter: &CommonOpts, module: &ModuleOpts, limit: usize) -> Result<(), ApkError> {
+    let map = load_module_map(module)?.ok_or_else(|| {
+        ApkError::InvalidInput("please input the class map: --map ./build/modules.json".to_string())
+    })?;
+    let classes = ApkArchive::from_source(source.clone())?.classes()?;
+    let value = modules::attribute_modules(&classes, &map);
+    let total_download: u64 = value.iter().map(|item| item.download).sum();
+
+    let mut table = Table::new();
+    table.add_row(row!["id", "Module", "Version", "Classes", "Size", "Download", "Share"]);
+    for (index, item) in value.iter().enumerate() {
+        table.add_row(Row::new(vec![
+            Cell::new(&(index + 1).to_string()),
+            Cell::new(&item.module),
+            Cell::new(&item.version),
+            Cell::new(&item.classes.to_string()),
+            Cell::new(&item.size.to_string()),
+            Cell::new(&item.download.to_string()),
+            Cell::new(&format!(
+                "{:.2}%",
+                item.download as f64 * 100.0 / total_download.max(1) as f64
+            )),
         ]));
     }
-    println!("");
+    println!();
+    printline();
+    println!("Modules: {}, Classes: {}, Download: {}", value.len(), classes.len(), total_download);
+    report::limit_table(&table, limit).printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "table_modules.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+// 按模块对比 dex 的下载大小
+fn print_module_diff(apk: &ApkArchive, apk_cmp: &ApkArchive, map: &ModuleMap, filter: &CommonOpts) -> Result<(), ApkError> {
+    let new_modules = modules::attribute_modules(&apk.classes()?, map);
+    let old_modules = modules::attribute_modules(&apk_cmp.classes()?, map);
+    let value = modules::diff_modules(&old_modules, &new_modules);
+
+    let mut table = Table::new();
+    table.add_row(row!["Module", "Old Version", "New Version", "Old", "New", "Diff"]);
+    for item in &value {
+        let version = |size: &Option<modules::ModuleSize>| {
+            size.as_ref().map(|size| size.version.clone()).unwrap_or_default()
+        };
+        let download = |size: &Option<modules::ModuleSize>| size.as_ref().map_or(0, |size| size.download);
+        table.add_row(Row::new(vec![
+            Cell::new(&item.module),
+            Cell::new(&version(&item.old)),
+            Cell::new(&version(&item.new)),
+            Cell::new(&download(&item.old).to_string()),
+            Cell::new(&download(&item.new).to_string()),
+            Cell::new(&item.diff.to_string()),
+        ]));
+    }
+    println!();
+    printline();
+    println!("Title: 模块对比, Total: {}", value.len());
+    table.printstd();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "模块对比.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+// 追加一条大小快照
+async fn record_size(source: &ApkSource, filter: &CommonOpts, history: &HistoryOpts, label: &str) -> Result<(), ApkError> {
+    let apk = ApkArchive::from_source(source.clone())?;
+    let label = if label.is_empty() { git_label() } else { label.to_string() };
+    let mut snapshot = SizeSnapshot::from_archive(&apk, &label, chrono::Utc::now().timestamp())?;
+    let db = history_db(history, &filter.build_path);
+    let history = SizeHistory::open(&db)?;
+    history.record(&mut snapshot)?;
+
+    let mut table = Table::new();
+    table.add_row(row!["id", "Label", "Time", "Version", "All"]);
+    table.add_row(Row::new(vec![
+        Cell::new(&snapshot.id.to_string()),
+        Cell::new(&snapshot.label),
+        Cell::new(&format_timestamp(snapshot.timestamp)),
+        Cell::new(&snapshot.version()),
+        Cell::new(&snapshot.total.to_string()),
+    ]));
+    println!();
+    printline();
+    println!("Recorded: {}", db);
+    table.printstd();
+    printline();
+    Ok(())
+}
+
+// 最近几次记录的大小变化，分类的合计和变化最大的目录
+async fn read_trend_info(history: &HistoryOpts, limit: usize, top: usize, output_csv: bool) -> Result<(), ApkError> {
+    let build_path = get_build_dir();
+    let db = history_db(history, &build_path);
+    let snapshots = SizeHistory::open(&db)?.latest(limit)?;
+    let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+        println_message(&format!("No records in {}, run `cliper record` first", db));
+        return Ok(());
+    };
+
+    let mut records_table = Table::new();
+    let mut header = vec!["id", "Label", "Time", "Version"];
+    header.extend(history::CATEGORIES);
+    header.extend(["All", "Diff"]);
+    records_table.add_row(Row::new(header.iter().map(|title| Cell::new(title)).collect()));
+    let mut previous: Option<&SizeSnapshot> = None;
+    for snapshot in &snapshots {
+        let mut cells = vec![
+            Cell::new(&snapshot.id.to_string()),
+            Cell::new(&snapshot.label),
+            Cell::new(&format_timestamp(snapshot.timestamp)),
+            Cell::new(&snapshot.version()),
+        ];
+        for category in history::CATEGORIES {
+            cells.push(Cell::new(&snapshot.category(category).to_string()));
+        }
+        let diff = previous.map_or(0, |previous| snapshot.total as i64 - previous.total as i64);
+        cells.push(Cell::new(&snapshot.total.to_string()));
+        cells.push(Cell::new(&format!("{:+}", diff)));
+        records_table.add_row(Row::new(cells));
+        previous = Some(snapshot);
+    }
+
+    // 第一条和最后一条记录之间每个分类的变化
+    let mut category_table = Table::new();
+    category_table.add_row(row!["Category", "First", "Last", "Diff", "Growth"]);
+    let mut totals: Vec<(String, u64, u64)> = history::CATEGORIES
+        .iter()
+        .map(|category| (category.to_string(), first.category(category), last.category(category)))
+        .collect();
+    totals.push(("All".to_string(), first.total, last.total));
+    for (category, old, new) in totals {
+        let diff = new as i64 - old as i64;
+        // 从 0 开始增长时没有比例
+        let growth = if old == 0 && diff != 0 {
+            "-".to_string()
+        } else {
+            format!("{:+.2}%", diff as f64 * 100.0 / old.max(1) as f64)
+        };
+        category_table.add_row(Row::new(vec![
+            Cell::new(&category),
+            Cell::new(&old.to_string()),
+            Cell::new(&new.to_string()),
+            Cell::new(&format!("{:+}", diff)),
+            Cell::new(&growth),
+        ]));
+    }
+
+    let movers = history::movers(&first.folders, &last.folders);
+    let mut movers_table = Table::new();
+    movers_table.add_row(row!["Folder", "First", "Last", "Diff"]);
+    for mover in movers.iter().take(top) {
+        movers_table.add_row(Row::new(vec![
+            Cell::new(&mover.name),
+            Cell::new(&mover.first.to_string()),
+            Cell::new(&mover.last.to_string()),
+            Cell::new(&format!("{:+}", mover.diff)),
+        ]));
+    }
+
+    println!();
+    printline();
+    println!("Records: {}, From: #{} {}, To: #{} {}", snapshots.len(), first.id, first.label, last.id, last.label);
+    records_table.printstd();
+    printline();
+    println!("Title: 分类变化");
+    category_table.printstd();
+    printline();
+    println!("Title: 变化最大的目录, Total: {}", movers.len());
+    movers_table.printstd();
+    printline();
+    if output_csv {
+        report::write_csv(&records_table, &output_path(&build_path, "table_trend.csv"))?;
+        report::write_csv(&category_table, &output_path(&build_path, "table_trend_categories.csv"))?;
+        report::write_csv(&movers_table, &output_path(&build_path, "table_trend_folders.csv"))?;
+    }
+    Ok(())
+}
+
+// 多个 apk 并排对比，第一个为基准
+async fn compare_files(sources: &[ApkSource], top: usize, output: &CompareOutput) -> Result<(), ApkError> {
+    let apks = sources
+        .iter()
+        .map(|source| ApkArchive::from_source(source.clone()))
+        .collect::<Result<Vec<_>, _>>()?;
+    let value = matrix::compare(&apks, top)?;
+
+    let mut table = Table::new();
+    let mut header = vec![Cell::new("Section"), Cell::new("Name")];
+    for (index, column) in value.columns.iter().enumerate() {
+        header.push(Cell::new(&column.name));
+        if index > 0 {
+            header.push(Cell::new(&format!("{} Diff", column.name)));
+        }
+    }
+    table.add_row(Row::new(header));
+    let mut version = vec![Cell::new(""), Cell::new("Version")];
+    for (index, column) in value.columns.iter().enumerate() {
+        version.push(Cell::new(&column.version));
+        if index > 0 {
+            version.push(Cell::new(""));
+        }
+    }
+    table.add_row(Row::new(version));
+    for row in &value.rows {
+        let mut cells = vec![Cell::new(&row.section), Cell::new(&row.name)];
+        for (index, (size, delta)) in row.values.iter().zip(&row.deltas).enumerate() {
+            cells.push(Cell::new(&size.to_string()));
+            if index > 0 {
+                cells.push(Cell::new(&format!("{:+}", delta)));
+            }
+        }
+        table.add_row(Row::new(cells));
+    }
+
+    println!();
+    printline();
+    for (index, column) in value.columns.iter().enumerate() {
+        println!("{}: {}", index + 1, column.file);
+    }
+    table.printstd();
+    printline();
+    let build_path = get_build_dir();
+    if output.csv {
+        report::write_csv(&table, &output_path(&build_path, "table_compare.csv"))?;
+    }
+    if output.json {
+        let json = serde_json::to_string_pretty(&value)
+            .map_err(|e| ApkError::Io(std::io::Error::other(e.to_string())))?;
+        write_file(&output_path(&build_path, "compare.json"), &json)?;
+    }
+    if output.html {
+        write_file(&output_path(&build_path, "compare.html"), &value.to_html())?;
+    }
+    Ok(())
+}
+
+// compare 的导出格式
+struct CompareOutput {
+    csv: bool,
+    json: bool,
+    html: bool,
+}
+
+fn write_file(output: &str, content: &str) -> Result<(), ApkError> {
+    if let Some(parent) = Path::new(output).parent() {
+        if !parent.exists() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(output, content)?;
+    println!("Output: {}", output);
+    Ok(())
+}
+
+fn history_db(history: &HistoryOpts, build_path: &str) -> String {
+    if history.db.is_empty() {
+        format!("{}/cliper.redb", build_path)
+    } else {
+        absolute_path(&history.db)
+    }
+}
+
+// 当前目录的 git describe，不在 git 仓库中时为空
+fn git_label() -> String {
+    std::process::Command::new("git")
+        .args(["describe", "--tags", "--always", "--dirty"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
+fn format_timestamp(timestamp: i64) -> String {
+    chrono::DateTime::from_timestamp(timestamp, 0)
+        .map(|time| time.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+async fn diff_files(
+    source: &ApkSource,
+    source_cmp: &ApkSource,
+    filter: &CommonOpts,
+    module: &ModuleOpts,
+    output: &DiffOpts,
+) -> Result<(), ApkError> {
+    if output.manifest {
+        return diff_manifest_files(source, source_cmp, filter, output);
+    }
+    if output.resources {
+        return diff_resource_files(source, source_cmp, filter, output);
+    }
+    if output.dex {
+        return diff_dex_files(source, source_cmp, filter, output);
+    }
+    if output.native {
+        return diff_native_files(source, source_cmp, filter, output);
+    }
+    // source - 新的文件，source_cmp - 旧的文件
+    let result = ApkArchive::from_source(source.clone()).and_then(|apk| {
+        let apk_cmp = ApkArchive::from_source(source_cmp.clone())?;
+        cliper::diff(&apk_cmp, &apk)
+    });
+    // 任意一个文件读取失败都直接返回，不再用空列表继续对比
+    let value = result?;
+    if output.format == "markdown" {
+        // 只输出 markdown，方便直接重定向到文件
+        let options = MarkdownOptions {
+            old_name: source_cmp.name(),
+            new_name: source.name(),
+            top: output.top,
+            threshold: output.threshold,
+        };
+        print!("{}", markdown::render(&value, &options));
+        return Ok(());
+    }
+    print_table("新增文件", &value.added, filter.output_csv, filter)?;
+    print_table("删除文件", &value.removed, filter.output_csv, filter)?;
+    print_table("更新文件", &value.updated, filter.output_csv, filter)?;
+    if let Some(map) = load_module_map(module)? {
+        let apk = ApkArchive::from_source(source.clone())?;
+        let apk_cmp = ApkArchive::from_source(source_cmp.clone())?;
+        print_module_diff(&apk, &apk_cmp, &map, filter)?;
+    }
+    print_size_diff(source, source_cmp, &value, filter)
+}
+
+// 清单文件对比，权限，组件，特性和属性
+fn diff_manifest_files(source: &ApkSource, source_cmp: &ApkSource, filter: &CommonOpts, output: &DiffOpts) -> Result<(), ApkError> {
+    let manifest = ApkArchive::from_source(source.clone())?.manifest()?;
+    let manifest_cmp = ApkArchive::from_source(source_cmp.clone())?.manifest()?;
+    let value = manifest_compare::diff_manifest(&manifest_cmp, &manifest);
+    if output.format == "markdown" {
+        let options = MarkdownOptions {
+            old_name: source_cmp.name(),
+            new_name: source.name(),
+            top: output.top,
+            threshold: output.threshold,
+        };
+        print!("{}", markdown::render_manifest(&value, &options));
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.add_row(row!["Change", "Section", "Name", "Old", "New", "Risk"]);
+    for item in &value {
+        table.add_row(Row::new(vec![
+            Cell::new(&format!("{:?}", item.kind)),
+            Cell::new(&item.section),
+            Cell::new(&item.name),
+            Cell::new(&item.old),
+            Cell::new(&item.new),
+            Cell::new(&item.risk),
+        ]));
+    }
+    let risks = value.iter().filter(|item| !item.risk.is_empty()).count();
+    println!();
+    printline();
+    println!("Title: 清单对比, Total: {}, Risks: {}", value.len(), risks);
+    table.printstd();
+    printline();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "清单对比.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+// resources.arsc 对比，资源名称，配置，字符串池和条目大小
+fn diff_resource_files(source: &ApkSource, source_cmp: &ApkSource, filter: &CommonOpts, output: &DiffOpts) -> Result<(), ApkError> {
+    let missing = |source: &ApkSource| ApkError::InvalidInput(format!("{} has no resources.arsc", source.name()));
+    let resources = ApkArchive::from_source(source.clone())?
+        .resources()?
+        .ok_or_else(|| missing(source))?;
+    let resources_cmp = ApkArchive::from_source(source_cmp.clone())?
+        .resources()?
+        .ok_or_else(|| missing(source_cmp))?;
+    let value = resource_compare::diff_resources(&resources_cmp, &resources);
+    if output.format == "markdown" {
+        let options = MarkdownOptions {
+            old_name: source_cmp.name(),
+            new_name: source.name(),
+            top: output.top,
+            threshold: output.threshold,
+        };
+        let entries = (resources_cmp.entries.len(), resources.entries.len());
+        print!("{}", markdown::render_resources(&value, entries, &options));
+        return Ok(());
+    }
+
+    let mut summary_table = Table::new();
+    summary_table.add_row(row!["Item", "Old", "New", "Diff"]);
+    let summary = [
+        ("resources.arsc", value.old_size, value.new_size),
+        ("String Pool Bytes", value.old_string_bytes as u64, value.new_string_bytes as u64),
+        ("String Pool Strings", value.old_string_count as u64, value.new_string_count as u64),
+        ("Entries", resources_cmp.entries.len() as u64, resources.entries.len() as u64),
+    ];
+    for (name, old_size, new_size) in summary {
+        summary_table.add_row(Row::new(vec![
+            Cell::new(name),
+            Cell::new(&old_size.to_string()),
+            Cell::new(&new_size.to_string()),
+            Cell::new(&format!("{:+}", new_size as i64 - old_size as i64)),
+        ]));
+    }
+    print_diff_section("资源汇总", &summary_table, 0, "资源汇总.csv", filter)?;
+
+    let mut type_table = Table::new();
+    type_table.add_row(row!["Type", "Old", "New", "Added", "Removed"]);
+    for item in &value.types {
+        type_table.add_row(Row::new(vec![
+            Cell::new(&item.type_name),
+            Cell::new(&item.old_count.to_string()),
+            Cell::new(&item.new_count.to_string()),
+            Cell::new(&item.added.to_string()),
+            Cell::new(&item.removed.to_string()),
+        ]));
+    }
+    print_diff_section("资源类型", &type_table, 0, "资源类型.csv", filter)?;
+
+    let mut names_table = Table::new();
+    names_table.add_row(row!["Change", "Type", "Name"]);
+    for (change, names) in [("Added", &value.added), ("Removed", &value.removed)] {
+        for (type_name, name) in names {
+            names_table.add_row(Row::new(vec![Cell::new(change), Cell::new(type_name), Cell::new(name)]));
+        }
+    }
+    print_diff_section("新增和删除的资源", &names_table, output.top, "新增和删除的资源.csv", filter)?;
+
+    let mut config_table = Table::new();
+    config_table.add_row(row!["Change", "Config", "Entries"]);
+    for item in &value.configs {
+        config_table.add_row(Row::new(vec![
+            Cell::new(if item.added { "Added" } else { "Removed" }),
+            Cell::new(&item.config),
+            Cell::new(&item.entries.to_string()),
+        ]));
+    }
+    print_diff_section("新增和删除的配置", &config_table, 0, "新增和删除的配置.csv", filter)?;
+
+    let mut changed_table = Table::new();
+    changed_table.add_row(row!["Type", "Name", "Old Configs", "New Configs", "Old", "New", "Diff"]);
+    for item in &value.changed {
+        changed_table.add_row(Row::new(vec![
+            Cell::new(&item.type_name),
+            Cell::new(&item.name),
+            Cell::new(&item.old_configs.to_string()),
+            Cell::new(&item.new_configs.to_string()),
+            Cell::new(&item.old_size.to_string()),
+            Cell::new(&item.new_size.to_string()),
+            Cell::new(&format!("{:+}", item.diff)),
+        ]));
+    }
+    print_diff_section("变化最大的资源", &changed_table, output.top, "变化最大的资源.csv", filter)
+}
+
+// dex 对比，新增和删除的类和包，每个包的方法数和大小变化
+fn diff_dex_files(source: &ApkSource, source_cmp: &ApkSource, filter: &CommonOpts, output: &DiffOpts) -> Result<(), ApkError> {
+    let mapping = match output.mapping.as_str() {
+        "" => None,
+        path => Some(input_source(path)?),
+    };
+    let mapping_cmp = match output.mapping_cmp.as_str() {
+        "" => None,
+        path => Some(input_source(path)?),
+    };
+    let classes = ApkArchive::from_source(source.clone())?.classes_with_mapping(mapping.as_ref())?;
+    let classes_cmp = ApkArchive::from_source(source_cmp.clone())?.classes_with_mapping(mapping_cmp.as_ref())?;
+    let value = dex_compare::diff_classes(&classes_cmp, &classes);
+    if output.format == "markdown" {
+        let options = MarkdownOptions {
+            old_name: source_cmp.name(),
+            new_name: source.name(),
+            top: output.top,
+            threshold: output.threshold,
+        };
+        print!("{}", markdown::render_dex(&value, &options));
+        return Ok(());
+    }
+
+    let mut summary_table = Table::new();
+    summary_table.add_row(row!["Item", "Old", "New", "Diff", "Added", "Removed"]);
+    let added = dex_compare::ClassChangeKind::Added;
+    let removed = dex_compare::ClassChangeKind::Removed;
+    let summary = [
+        ("Size", value.old_size, value.new_size, String::new(), String::new()),
+        ("Download", value.old_download, value.new_download, String::new(), String::new()),
+        ("Methods", value.old_methods as u64, value.new_methods as u64, String::new(), String::new()),
+        (
+            "Classes",
+            value.old_classes as u64,
+            value.new_classes as u64,
+            value.count(added).to_string(),
+            value.count(removed).to_string(),
+        ),
+        (
+            "Packages",
+            value.old_packages as u64,
+            value.new_packages as u64,
+            value.count_packages(added).to_string(),
+            value.count_packages(removed).to_string(),
+        ),
+    ];
+    for (name, old_value, new_value, added, removed) in summary {
+        summary_table.add_row(Row::new(vec![
+            Cell::new(name),
+            Cell::new(&old_value.to_string()),
+            Cell::new(&new_value.to_string()),
+            Cell::new(&format!("{:+}", new_value as i64 - old_value as i64)),
+            Cell::new(&added),
+            Cell::new(&removed),
+        ]));
+    }
+    print_diff_section("代码汇总", &summary_table, 0, "代码汇总.csv", filter)?;
+
+    let mut package_table = Table::new();
+    package_table.add_row(row![
+        "Change", "Package", "Old Classes", "New Classes", "Added", "Removed", "Methods", "Old", "New", "Diff", "Download Diff"
+    ]);
+    for item in &value.packages {
+        package_table.add_row(Row::new(vec![
+            Cell::new(&format!("{:?}", item.status())),
+            Cell::new(&item.package),
+            Cell::new(&item.old_classes.to_string()),
+            Cell::new(&item.new_classes.to_string()),
+            Cell::new(&item.added_classes.to_string()),
+            Cell::new(&item.removed_classes.to_string()),
+            Cell::new(&format!("{:+}", item.method_diff())),
+            Cell::new(&item.old_size.to_string()),
+            Cell::new(&item.new_size.to_string()),
+            Cell::new(&format!("{:+}", item.diff)),
+            Cell::new(&format!("{:+}", item.download_diff())),
+        ]));
+    }
+    print_diff_section("包的变化", &package_table, output.limit, "包的变化.csv", filter)?;
+
+    let mut class_table = Table::new();
+    class_table.add_row(row!["Change", "Class", "Old Methods", "New Methods", "Old", "New", "Diff", "Download Diff"]);
+    for item in &value.classes {
+        class_table.add_row(Row::new(vec![
+            Cell::new(&format!("{:?}", item.kind)),
+            Cell::new(&item.name),
+            Cell::new(&item.old_methods.to_string()),
+            Cell::new(&item.new_methods.to_string()),
+            Cell::new(&item.old_size.to_string()),
+            Cell::new(&item.new_size.to_string()),
+            Cell::new(&format!("{:+}", item.diff)),
+            Cell::new(&format!("{:+}", item.download_diff())),
+        ]));
+    }
+    print_diff_section("类的变化", &class_table, output.limit, "类的变化.csv", filter)
+}
+
+// 共享库对比，每个变化的 .so 的节和符号
+fn diff_native_files(source: &ApkSource, source_cmp: &ApkSource, filter: &CommonOpts, output: &DiffOpts) -> Result<(), ApkError> {
+    let libraries = ApkArchive::from_source(source.clone())?.parsed_native_libraries()?;
+    let libraries_cmp = ApkArchive::from_source(source_cmp.clone())?.parsed_native_libraries()?;
+    let value = native_compare::diff_native(&libraries_cmp, &libraries);
+    if output.format == "markdown" {
+        let options = MarkdownOptions {
+            old_name: source_cmp.name(),
+            new_name: source.name(),
+            top: output.top,
+            threshold: output.threshold,
+        };
+        print!("{}", markdown::render_native(&value, &options));
+        return Ok(());
+    }
+    let stripped = |value: bool| if value { "yes" } else { "no" };
+
+    let mut library_table = Table::new();
+    library_table.add_row(row!["Library", "Old", "New", "Diff", "Download Diff", "Stripped", "Note"]);
+    for item in &value {
+        let note = if item.lost_stripping() {
+            "lost stripping: .symtab or debug info is back"
+        } else if !item.full_symbols {
+            "exported symbols only"
+        } else {
+            ""
+        };
+        library_table.add_row(Row::new(vec![
+            Cell::new(&item.path),
+            Cell::new(&item.old_size.to_string()),
+            Cell::new(&item.new_size.to_string()),
+            Cell::new(&format!("{:+}", item.diff())),
+            Cell::new(&format!("{:+}", item.download_diff())),
+            Cell::new(&format!("{} → {}", stripped(item.old_stripped), stripped(item.new_stripped))),
+            Cell::new(note),
+        ]));
+    }
+    print_diff_section("共享库的变化", &library_table, 0, "共享库的变化.csv", filter)?;
+
+    for item in &value {
+        let name = item.path.replace('/', "_");
+        let mut section_table = Table::new();
+        section_table.add_row(row!["Section", "Old", "New", "Diff"]);
+        for section in &item.sections {
+            section_table.add_row(Row::new(vec![
+                Cell::new(&section.name),
+                Cell::new(&section.old_size.to_string()),
+                Cell::new(&section.new_size.to_string()),
+                Cell::new(&format!("{:+}", section.diff)),
+            ]));
+        }
+        print_diff_section(
+            &format!("{} 节的变化", item.path),
+            &section_table,
+            output.top,
+            &format!("节的变化_{}.csv", name),
+            filter,
+        )?;
+
+        let mut symbol_table = Table::new();
+        symbol_table.add_row(row!["Change", "Symbol", "Type", "Exported", "Old", "New", "Diff"]);
+        for symbol in &item.symbols {
+            symbol_table.add_row(Row::new(vec![
+                Cell::new(&format!("{:?}", symbol.kind)),
+                Cell::new(&symbol.name),
+                Cell::new(if symbol.function { "func" } else { "object" }),
+                Cell::new(stripped(symbol.exported)),
+                Cell::new(&symbol.old_size.to_string()),
+                Cell::new(&symbol.new_size.to_string()),
+                Cell::new(&format!("{:+}", symbol.diff)),
+            ]));
+        }
+        print_diff_section(
+            &format!("{} 符号的变化", item.path),
+            &symbol_table,
+            output.top,
+            &format!("符号的变化_{}.csv", name),
+            filter,
+        )?;
+    }
+    Ok(())
+}
+
+/**
+ * 打印 diff 中的一张表，limit 为 0 时打印全部，csv 中总是全部
+ */
+fn print_diff_section(title: &str, table: &Table, limit: usize, csv_name: &str, filter: &CommonOpts) -> Result<(), ApkError> {
+    let total = table.len().saturating_sub(1);
+    let shown = if limit == 0 { total } else { limit.min(total) };
+    println!();
+    printline();
+    println!("Title: {}, Total: {}", title, total);
+    report::limit_table(table, shown).printstd();
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, csv_name);
+        report::write_csv(table, &output)?;
+    }
+    Ok(())
+}
+
+// 分类汇总对比，包含压缩包结构开销
+fn print_size_diff(source: &ApkSource, source_cmp: &ApkSource, diff: &ApkDiff, filter: &CommonOpts) -> Result<(), ApkError> {
+    // 估算失败时不影响前面的统计，估算的行显示为 -
+    let estimates = estimator::estimate(source).and_then(|estimate| Ok((estimate, estimator::estimate(source_cmp)?)));
+    let table = report::size_diff_table(diff, estimates.as_ref().ok());
+    println!();
+    printline();
+    println!("Title: 分类汇总");
+    table.printstd();
+    if let Err(e) = &estimates {
+        println!("Failed to estimate download size: {}", e);
+    }
+
+    if filter.output_csv {
+        let output = output_path(&filter.build_path, "分类汇总.csv");
+        report::write_csv(&table, &output)?;
+    }
+    Ok(())
+}
+
+// cargo run diff --input /Users/liangrui/Work/liangrui/cliper/build/14.3.0.apk --input-cmp /Users/liangrui/Work/liangrui/cliper/build/14.2.0.apk --output-csv
+fn print_table(title: &str, value: &[CliperInfo], output_csv: bool, filter: &CommonOpts) -> Result<(), ApkError> {
+    let table = report::entries_diff_table(value);
+    let total_download: i64 = value.iter().map(|cliper_item| cliper_item.diff).sum();
+    println!();
     printline();
     println!("Title: {}, Total: {}, Donwload: {}", title, &value.len(), total_download);
     table.printstd();
@@ -400,9 +1370,9 @@ fn print_table(title: &str, value: Vec<CliperInfo>, output_csv: bool, filter: &C
     if output_csv {
         let file_name = format!("{}{}", title, ".csv");
         let output = output_path(&filter.build_path, &file_name);
-        create_csv(&table, &output);
+        report::write_csv(&table, &output)?;
     }
-
+    Ok(())
 }
 
 fn output_path(build_path: &str, file_name: &str) -> String {
@@ -415,7 +1385,13 @@ fn output_path(build_path: &str, file_name: &str) -> String {
         // 以 / 结尾 或 不以 / 结尾
         output = format!("{}/{}", build_path, file_name);
     }
-    return output;
+    output
+}
+
+// 在单行打印进度，print! 不会自动刷新
+fn print_progress(position: usize, total: usize) {
+    print!("\r进度: {}/{}", position, total);
+    let _ = io::stdout().flush();
 }
 
 fn printline() {
@@ -423,51 +1399,28 @@ fn printline() {
 }
 
 fn println_message(messge: &str) {
-    println!("");
+    println!();
     printline();
     println!("{}", messge);
     printline();
 }
 
-fn create_csv(table: &Table, output: &str) {
-    // 如果表格为空，直接返回
-    if table.is_empty() {
-        return;
-    }
-    // 如果文件存在，删除文件
-    if fs::metadata(output).is_ok() {
-        fs::remove_file(output).expect("Cannot remove file");
-    }
-    // 判断文件的父目录是否存在，不存在则创建
-    let file_path = Path::new(output);
-    if let Some(parent) = file_path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).expect("Cannot create dir");
-        }
-    }
-    let mut wtr = Writer::from_writer(File::create(output).expect("Cannot create file"));
-    for row in table.row_iter() {
-        let v: Vec<String> = row.iter().map(|cell| cell.get_content()).collect();
-        wtr.write_record(&v).expect("Cannot write record");
-    }
-    wtr.flush().expect("Cannot flush");
-}
-
+// 当前目录不可用时(例如已被删除)使用相对路径
 fn get_current_dir() -> String {
-    let project_path = env::current_dir().unwrap();
-    return project_path.display().to_string();
+    let project_path = env::current_dir().unwrap_or_else(|_| ".".into());
+    project_path.display().to_string()
 }
 
 fn get_build_dir() -> String {
-    let project_path = env::current_dir().unwrap();
+    let project_path = env::current_dir().unwrap_or_else(|_| ".".into());
     let build_path = project_path.join("build");
-    return build_path.display().to_string();
+    build_path.display().to_string()
 }
 
 fn build_file(filename: &str) -> String {
-    let project_path = env::current_dir().unwrap();
+    let project_path = env::current_dir().unwrap_or_else(|_| ".".into());
     let build_path = project_path.join("build");
-    return build_path.join(filename).to_str().unwrap().to_string();
+    build_path.join(filename).display().to_string()
 }
 
 fn check_build_path(opts: &mut CommonOpts) {
@@ -478,17 +1431,12 @@ fn check_build_path(opts: &mut CommonOpts) {
     }
 }
 
-fn check_input_file(filename: &str) -> Result<(), String> {
-    if filename.is_empty() {
-        println_message("Error: Please input the apk file: --input ./build/app.apk");
-        return Err("Input file path is empty".to_string());
+// 根据 --input 创建来源，本地文件转换成绝对路径
+fn input_source(input: &str) -> Result<ApkSource, ApkError> {
+    if input == "-" || input.starts_with("http://") || input.starts_with("https://") {
+        return ApkSource::from_input(input);
     }
-    let file = Path::new(filename);
-    if !file.exists() {
-        println_message("Error: Please input the apk file: --input ./build/app.apk");
-        return Err("Input file path not exists".to_string());
-    }
-    return Ok(());
+    ApkSource::from_input(&absolute_path(input))
 }
 
 fn absolute_path(input: &str) -> String {
@@ -496,10 +1444,10 @@ fn absolute_path(input: &str) -> String {
     if file_path.starts_with(".") {
         file_path = format!("{}/{}", get_current_dir(), file_path);
     }
-    return file_path;
+    file_path
 }
 
-fn show_debug(debug: bool, sub_command: &str, apk_path: &str) {
+fn show_debug(debug: bool, sub_command: &str, source: &ApkSource) {
     if !debug {
         return;
     }
@@ -508,7 +1456,10 @@ fn show_debug(debug: bool, sub_command: &str, apk_path: &str) {
     system_message.push_str(format!("\nCmd         : {}", sub_command).as_str());
     system_message.push_str(format!("\nCurrent Path: {}", get_current_dir()).as_str());
     system_message.push_str(format!("\nBuild Path  : {}", get_build_dir()).as_str());
-    system_message.push_str(format!("\ninput Path  : {}", apk_path).as_str());
+    system_message.push_str(format!("\ninput Path  : {}", source.name()).as_str());
+    if let ApkSource::Http { length, .. } = source {
+        system_message.push_str(format!("\nRemote Size : {}", length).as_str());
+    }
     println_message(system_message.as_str());
 }
 
@@ -516,9 +1467,18 @@ fn show_debug(debug: bool, sub_command: &str, apk_path: &str) {
 //      cargo run -- --input /Users/liangrui/Work/liangrui/cliper/build/app.apk --filter-type Res --filter-ext .png --filter-size 10000 --filter-path assets
 // else use this
 //      ./cliper detail --input ./build/app.apk --filter-type Res --filter-ext .png --filter-size 10000 --filter-path assets
-fn main() -> Result<(), String> {
+fn main() {
+    if let Err(e) = run() {
+        println_message(&format!("Error: {}", e));
+        std::process::exit(e.exit_code());
+    }
+}
+
+fn run() -> Result<(), ApkError> {
     if std::env::args().len() == 1 {
-        Args::clap().print_help().expect("Failed to print help");
+        Args::clap()
+            .print_help()
+            .map_err(|e| ApkError::Io(std::io::Error::other(e.to_string())))?;
         println!(); // 打印换行符以更好地格式化输出
         return Ok(());
     }
@@ -528,44 +1488,147 @@ fn main() -> Result<(), String> {
         Args::Summary { common } => {
             let mut opts = common;
             check_build_path(&mut opts);
-            check_input_file(opts.input.as_str())?;
-            let apk_path = absolute_path(&opts.input.clone());
-            show_debug(opts.debug, "Summary", apk_path.as_str());
-            task::block_on(read_total(&apk_path, &opts));
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Summary", &source);
+            task::block_on(read_total(&source, &opts))?;
         }
         Args::Detail { common, detail } => {
             let mut opts = common;
             check_build_path(&mut opts);
-            check_input_file(opts.input.as_str())?;
-            let apk_path = absolute_path(&opts.input.clone());
-            show_debug(opts.debug, "Detail", apk_path.as_str());
-            task::block_on(read_detail_info(&apk_path, &opts, &detail));
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Detail", &source);
+            task::block_on(read_detail_info(&source, &opts, &detail))?;
+        }
+        Args::Browse { common, browse } => {
+            let mut opts = common;
+            check_build_path(&mut opts);
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Browse", &source);
+            task::block_on(browse_files(&source, &opts, &browse))?;
         }
-        Args::Same { common } => {
+        Args::Same { common, same } => {
             let mut opts = common;
             check_build_path(&mut opts);
-            check_input_file(opts.input.as_str())?;
-            let apk_path = absolute_path(&opts.input.clone());
-            show_debug(opts.debug, "Same", apk_path.as_str());
-            task::block_on(read_same_info(&apk_path, &opts));
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Same", &source);
+            task::block_on(read_same_info(&source, &opts, &same))?;
+        }
+        Args::Images {
+            common,
+            detail,
+            image,
+        } => {
+            image.validate()?;
+            let mut opts = common;
+            check_build_path(&mut opts);
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Images", &source);
+            task::block_on(read_images_info(&source, &opts, &detail, &image))?;
+        }
+        Args::Xml { common, detail, xml } => {
+            let mut opts = common;
+            check_build_path(&mut opts);
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Xml", &source);
+            task::block_on(read_xml_info(&source, &opts, &detail, &xml))?;
+        }
+        Args::Extract {
+            common,
+            detail,
+            output_dir,
+        } => {
+            let mut opts = common;
+            check_build_path(&mut opts);
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Extract", &source);
+            // 默认解压到 build 目录下与文件同名的目录
+            let output_dir = if output_dir.is_empty() {
+                format!("{}/{}", opts.build_path, source.stem())
+            } else {
+                absolute_path(&output_dir)
+            };
+            task::block_on(extract_files(&source, &opts, &detail, &output_dir))?;
         }
         Args::Info { common } => {
             let mut opts = common;
             check_build_path(&mut opts);
-            check_input_file(opts.input.as_str())?;
-            let apk_path = absolute_path(&opts.input.clone());
-            show_debug(opts.debug, "Info", apk_path.as_str());
-            task::block_on(read_info(&apk_path));
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Info", &source);
+            task::block_on(read_info(&source))?;
+        }
+        Args::Libraries { common, library } => {
+            let mut opts = common;
+            check_build_path(&mut opts);
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Libraries", &source);
+            task::block_on(read_libraries_info(&source, &opts, &library))?;
         }
-        Args::Diff { common, input_cmp } => {
+        Args::Modules { common, module, limit } => {
             let mut opts = common;
             check_build_path(&mut opts);
-            check_input_file(opts.input.as_str())?;
-            check_input_file(input_cmp.as_str())?;
-            let apk_path = absolute_path(&opts.input.clone());
-            let apk_cmp_path = absolute_path(&input_cmp.clone());
-            show_debug(opts.debug, "Diff", apk_path.as_str());
-            task::block_on(diff_files(&apk_path, &apk_cmp_path, &opts));
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Modules", &source);
+            task::block_on(read_modules_info(&source, &opts, &module, limit))?;
+        }
+        Args::Record { common, history, label } => {
+            let mut opts = common;
+            check_build_path(&mut opts);
+            let source = input_source(&opts.input)?;
+            show_debug(opts.debug, "Record", &source);
+            task::block_on(record_size(&source, &opts, &history, &label))?;
+        }
+        Args::Trend {
+            history,
+            limit,
+            top,
+            output_csv,
+        } => {
+            task::block_on(read_trend_info(&history, limit, top, output_csv))?;
+        }
+        Args::Compare {
+            debug,
+            input,
+            top,
+            output_csv,
+            output_json,
+            output_html,
+        } => {
+            if input.iter().filter(|input| *input == "-").count() > 1 {
+                return Err(ApkError::InvalidInput(
+                    "only one --input can read from stdin".to_string(),
+                ));
+            }
+            let sources = input
+                .iter()
+                .map(|input| input_source(input))
+                .collect::<Result<Vec<_>, _>>()?;
+            for source in &sources {
+                show_debug(debug, "Compare", source);
+            }
+            let output = CompareOutput {
+                csv: output_csv,
+                json: output_json,
+                html: output_html,
+            };
+            task::block_on(compare_files(&sources, top, &output))?;
+        }
+        Args::Diff {
+            common,
+            module,
+            output,
+            input_cmp,
+        } => {
+            let mut opts = common;
+            check_build_path(&mut opts);
+            if opts.input == "-" && input_cmp == "-" {
+                return Err(ApkError::InvalidInput(
+                    "only one of --input and --input-cmp can read from stdin".to_string(),
+                ));
+            }
+            let source = input_source(&opts.input)?;
+            let source_cmp = input_source(&input_cmp)?;
+            show_debug(opts.debug, "Diff", &source);
+            task::block_on(diff_files(&source, &source_cmp, &opts, &module, &output))?;
         }
     }
     Ok(())